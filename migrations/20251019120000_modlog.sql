@@ -0,0 +1,6 @@
+CREATE TABLE modlog_channels (
+    guild_id BIGINT NOT NULL PRIMARY KEY REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    channel_id BIGINT NOT NULL,
+    -- bitflag, see ModlogEvents.
+    events INT NOT NULL DEFAULT 63
+);
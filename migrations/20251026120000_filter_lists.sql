@@ -0,0 +1,15 @@
+CREATE TABLE filter_words (
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    -- see WordList.
+    list TEXT NOT NULL CHECK (list IN ('bad', 'fix')),
+    word TEXT NOT NULL,
+    PRIMARY KEY (guild_id, list, word)
+);
+
+CREATE TABLE filter_overrides (
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    word TEXT NOT NULL,
+    -- see TrieOverride.
+    kind TEXT NOT NULL CHECK (kind IN ('safe', 'mild', 'moderate', 'severe')),
+    PRIMARY KEY (guild_id, word)
+);
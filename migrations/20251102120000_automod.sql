@@ -0,0 +1,26 @@
+CREATE TABLE automod_tiers (
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    -- see Tier.
+    tier TEXT NOT NULL CHECK (tier IN ('mild', 'moderate', 'severe', 'evasive', 'spam')),
+    -- see AutomodAction.
+    action TEXT NOT NULL CHECK (action IN ('log', 'delete', 'warn', 'timeout')),
+    timeout_secs INT,
+    PRIMARY KEY (guild_id, tier)
+);
+
+CREATE TABLE automod_actions (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    channel_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    tier TEXT NOT NULL,
+    action TEXT NOT NULL,
+    timeout_secs INT,
+    words TEXT[] NOT NULL,
+    content TEXT NOT NULL,
+    analysis TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX automod_actions_guild_user ON automod_actions (guild_id, user_id);
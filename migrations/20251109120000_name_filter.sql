@@ -0,0 +1,5 @@
+CREATE TABLE name_filter (
+    guild_id BIGINT NOT NULL PRIMARY KEY REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    -- bitflag, see NameFilterActions.
+    actions INT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- NULL uses the guild's language.
+ALTER TABLE regexes ADD COLUMN ocr_language TEXT;
+
+CREATE TABLE ocr_languages (
+    guild_id BIGINT NOT NULL PRIMARY KEY REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    language TEXT NOT NULL
+);
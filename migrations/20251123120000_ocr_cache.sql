@@ -0,0 +1,10 @@
+CREATE TABLE ocr_cache (
+    -- sha256 of the image bytes.
+    hash BYTEA NOT NULL,
+    language TEXT NOT NULL,
+    -- see ocr::ENGINE_VERSION, older rows are ignored and overwritten.
+    engine_version INT NOT NULL,
+    text TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (hash, language)
+);
@@ -0,0 +1,4 @@
+-- Guilds that run the content filter over text read from images.
+CREATE TABLE ocr_filter (
+    guild_id BIGINT NOT NULL PRIMARY KEY REFERENCES guilds(guild_id) ON DELETE CASCADE
+);
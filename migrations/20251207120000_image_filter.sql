@@ -0,0 +1,26 @@
+-- Guilds hashing their image attachments, and what to do about blocklisted ones.
+CREATE TABLE image_filter (
+    guild_id BIGINT NOT NULL PRIMARY KEY REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    action TEXT NOT NULL,
+    max_distance INT NOT NULL
+);
+
+CREATE TABLE image_blocklist (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL REFERENCES guilds(guild_id) ON DELETE CASCADE,
+    -- dhash of the image, see phash.rs.
+    hash BIGINT NOT NULL,
+    label TEXT NOT NULL,
+    added_by BIGINT NOT NULL,
+    created_at BIGINT NOT NULL,
+    UNIQUE (guild_id, hash)
+);
+
+-- not referencing attachments, as hashes are inserted alongside the message itself.
+CREATE TABLE image_hashes (
+    attachment_id BIGINT NOT NULL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    hash BIGINT NOT NULL
+);
+
+CREATE INDEX idx_image_hashes_guild_id ON image_hashes (guild_id);
@@ -0,0 +1,6 @@
+-- 0 is global, 1 per channel and 2 per user, a cooldown of 0 and a NULL cap disable them.
+ALTER TABLE regexes ADD COLUMN cooldown_scope SMALLINT NOT NULL DEFAULT 0;
+ALTER TABLE regexes ADD COLUMN cooldown_seconds INT NOT NULL DEFAULT 0;
+ALTER TABLE regexes ADD COLUMN max_per_minute INT;
+-- how many matches the cooldown or cap kept from being responded to.
+ALTER TABLE regexes ADD COLUMN suppressed BIGINT NOT NULL DEFAULT 0;
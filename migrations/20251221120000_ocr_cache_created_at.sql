@@ -0,0 +1,2 @@
+-- the cache is pruned by age, see ocr::CACHE_TTL.
+CREATE INDEX idx_ocr_cache_created_at ON ocr_cache (created_at);
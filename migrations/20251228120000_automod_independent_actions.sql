@@ -0,0 +1,21 @@
+-- actions used to include the ones before them, now each is set on its own.
+-- bitflag, see AutomodFlags. delete is 1 and warn is 2, timeouts are set by timeout_secs.
+ALTER TABLE automod_tiers ADD COLUMN actions INT NOT NULL DEFAULT 0;
+UPDATE automod_tiers SET actions = CASE action
+    WHEN 'delete' THEN 1
+    WHEN 'warn' THEN 3
+    WHEN 'timeout' THEN 3
+    ELSE 0
+END;
+ALTER TABLE automod_tiers ALTER COLUMN actions DROP DEFAULT;
+ALTER TABLE automod_tiers DROP COLUMN action;
+
+ALTER TABLE automod_actions ADD COLUMN actions INT NOT NULL DEFAULT 0;
+UPDATE automod_actions SET actions = CASE action
+    WHEN 'delete' THEN 1
+    WHEN 'warn' THEN 3
+    WHEN 'timeout' THEN 3
+    ELSE 0
+END;
+ALTER TABLE automod_actions ALTER COLUMN actions DROP DEFAULT;
+ALTER TABLE automod_actions DROP COLUMN action;
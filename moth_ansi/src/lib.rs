@@ -1,8 +1,12 @@
-use aformat::aformat;
+mod style;
+
+pub use style::{Color, ColorMode, Style, StyledText, color_mode};
 
 pub const RESET: &str = "\x1B[0m";
 pub const BOLD: &str = "\x1B[1m";
 pub const DIM: &str = "\x1B[2m";
+pub const UNDERLINE: &str = "\x1B[4m";
+pub const STRIKETHROUGH: &str = "\x1B[9m";
 
 pub const RED: &str = "\x1B[31m";
 pub const GREEN: &str = "\x1B[32m";
@@ -17,12 +21,11 @@ pub const HI_GREEN: &str = "\x1B[92m";
 pub const HI_BLUE: &str = "\x1B[94m";
 pub const HI_MAGENTA: &str = "\x1B[95m";
 
-pub fn from_colour(num: u32) -> Option<aformat::ArrayString<19>> {
-    if num != 0 {
-        return Some(aformat!("\x1B[38;2;{};{};{}m", r(num), g(num), b(num)));
-    }
-
-    None
+/// The escape code for a role colour in the console's [`ColorMode`], `None` if the role has no
+/// colour.
+#[must_use]
+pub fn from_colour(num: u32) -> Option<String> {
+    Color::from_role(num).map(|c| c.escape(color_mode()))
 }
 
 #[must_use]
@@ -39,3 +42,28 @@ pub const fn g(num: u32) -> u8 {
 pub const fn b(num: u32) -> u8 {
     (num & 255) as u8
 }
+
+/// Removes ANSI escape sequences, for writing console output somewhere that can't render it.
+#[must_use]
+pub fn strip(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1B' {
+            // CSI sequences end in a byte in the range 0x40..=0x7E.
+            if chars.clone().next() == Some('[') {
+                chars.next();
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7E').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+
+    out
+}
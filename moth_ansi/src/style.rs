@@ -0,0 +1,495 @@
+//! A small styled text builder that renders to whatever the terminal supports, or to a Discord
+//! `ansi` code block.
+
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write;
+use std::sync::OnceLock;
+
+/// How much colour the output supports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    /// Styles are dropped entirely.
+    None,
+}
+
+impl ColorMode {
+    /// Detects the mode from the environment, honouring `NO_COLOR`, `COLORTERM` and `TERM`.
+    #[must_use]
+    pub fn detect() -> Self {
+        Self::from_vars(|name| std::env::var_os(name))
+    }
+
+    /// [`ColorMode::detect`], looking the variables up with `var`.
+    fn from_vars(var: impl Fn(&str) -> Option<OsString>) -> Self {
+        if var("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return Self::None;
+        }
+
+        let colorterm = var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return Self::TrueColor;
+        }
+
+        match var("TERM").as_deref().and_then(OsStr::to_str) {
+            Some("dumb") => Self::None,
+            Some(term) if term.contains("256color") => Self::Ansi256,
+            _ => Self::Ansi16,
+        }
+    }
+}
+
+/// The mode detected on first use, shared by everything that renders for the console.
+pub fn color_mode() -> ColorMode {
+    static MODE: OnceLock<ColorMode> = OnceLock::new();
+    *MODE.get_or_init(ColorMode::detect)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Rgb(u8, u8, u8),
+    /// One of the 16 standard terminal colours, 8-15 being the bright variants.
+    Ansi(u8),
+}
+
+impl Color {
+    pub const BLACK: Self = Self::Ansi(0);
+    pub const RED: Self = Self::Ansi(1);
+    pub const GREEN: Self = Self::Ansi(2);
+    pub const YELLOW: Self = Self::Ansi(3);
+    pub const BLUE: Self = Self::Ansi(4);
+    pub const MAGENTA: Self = Self::Ansi(5);
+    pub const CYAN: Self = Self::Ansi(6);
+    pub const WHITE: Self = Self::Ansi(7);
+    pub const HI_BLACK: Self = Self::Ansi(8);
+    pub const HI_RED: Self = Self::Ansi(9);
+    pub const HI_GREEN: Self = Self::Ansi(10);
+    pub const HI_BLUE: Self = Self::Ansi(12);
+    pub const HI_MAGENTA: Self = Self::Ansi(13);
+
+    /// A Discord role colour, `None` for 0 as that means the role has no colour.
+    #[must_use]
+    pub fn from_role(num: u32) -> Option<Self> {
+        (num != 0).then(|| Self::Rgb(crate::r(num), crate::g(num), crate::b(num)))
+    }
+
+    /// The escape code setting this as the foreground colour, empty when `mode` has no colour.
+    #[must_use]
+    pub fn escape(self, mode: ColorMode) -> String {
+        let mut out = String::new();
+        self.write_fg(&mut out, mode);
+        out
+    }
+
+    fn write_fg(self, out: &mut String, mode: ColorMode) {
+        match (self, mode) {
+            (_, ColorMode::None) => {}
+            (Self::Ansi(i), _) => write!(out, "\x1B[{}m", ansi16_code(i)).unwrap(),
+            (Self::Rgb(r, g, b), ColorMode::TrueColor) => {
+                write!(out, "\x1B[38;2;{r};{g};{b}m").unwrap();
+            }
+            (Self::Rgb(r, g, b), ColorMode::Ansi256) => {
+                write!(out, "\x1B[38;5;{}m", nearest_256(r, g, b)).unwrap();
+            }
+            (Self::Rgb(r, g, b), ColorMode::Ansi16) => {
+                let i = nearest(&ANSI16, (r, g, b));
+                write!(out, "\x1B[{}m", ansi16_code(i as u8)).unwrap();
+            }
+        }
+    }
+
+    /// Discord only has the 8 normal foreground colours.
+    fn discord_code(self) -> u8 {
+        match self {
+            Self::Ansi(i) => 30 + (i % 8),
+            Self::Rgb(r, g, b) => 30 + nearest(&DISCORD, (r, g, b)) as u8,
+        }
+    }
+}
+
+fn ansi16_code(i: u8) -> u8 {
+    if i < 8 { 30 + i } else { 90 + (i - 8) }
+}
+
+/// xterm's default palette.
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// What the Discord client renders codes 30 to 37 as.
+const DISCORD: [(u8, u8, u8); 8] = [
+    (79, 84, 92),
+    (220, 50, 47),
+    (133, 153, 0),
+    (181, 137, 0),
+    (38, 139, 210),
+    (211, 54, 130),
+    (42, 161, 152),
+    (255, 255, 255),
+];
+
+fn nearest(palette: &[(u8, u8, u8)], (r, g, b): (u8, u8, u8)) -> usize {
+    let distance = |&(pr, pg, pb): &(u8, u8, u8)| {
+        let dr = i32::from(pr) - i32::from(r);
+        let dg = i32::from(pg) - i32::from(g);
+        let db = i32::from(pb) - i32::from(b);
+        dr * dr + dg * dg + db * db
+    };
+
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, c)| distance(c))
+        .map_or(0, |(i, _)| i)
+}
+
+/// Maps onto the 6x6x6 colour cube, or the greyscale ramp for greys.
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        // the ramp runs from 8 to 238 in steps of 10, black and white are in the cube.
+        return match r {
+            0..4 => 16,
+            247.. => 231,
+            _ => 232 + ((r - 3) / 10).min(23),
+        };
+    }
+
+    let level = |v: u8| match v {
+        0..48 => 0,
+        48..115 => 1,
+        _ => (v - 35) / 40,
+    };
+
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub underline: bool,
+    /// Not supported by Discord, which falls back to the colour alone.
+    pub strikethrough: bool,
+}
+
+impl Style {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            fg: None,
+            bold: false,
+            dim: false,
+            underline: false,
+            strikethrough: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn fg(mut self, colour: Color) -> Self {
+        self.fg = Some(colour);
+        self
+    }
+
+    #[must_use]
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+
+    fn is_plain(self) -> bool {
+        self == Self::new()
+    }
+
+    fn write(self, out: &mut String, mode: ColorMode) {
+        if mode == ColorMode::None {
+            return;
+        }
+
+        if self.bold {
+            out.push_str(crate::BOLD);
+        }
+        if self.dim {
+            out.push_str(crate::DIM);
+        }
+        if self.underline {
+            out.push_str(crate::UNDERLINE);
+        }
+        if self.strikethrough {
+            out.push_str(crate::STRIKETHROUGH);
+        }
+        if let Some(fg) = self.fg {
+            fg.write_fg(out, mode);
+        }
+    }
+
+    fn write_discord(self, out: &mut String) {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push(1);
+        }
+        if self.underline {
+            codes.push(4);
+        }
+        if let Some(fg) = self.fg {
+            codes.push(fg.discord_code());
+        }
+
+        if !codes.is_empty() {
+            let codes = codes.iter().map(u8::to_string).collect::<Vec<_>>();
+            write!(out, "\x1B[{}m", codes.join(";")).unwrap();
+        }
+    }
+}
+
+/// Text made of differently styled spans.
+#[derive(Clone, Debug, Default)]
+pub struct StyledText {
+    spans: Vec<(Style, String)>,
+}
+
+impl StyledText {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends unstyled text.
+    #[must_use]
+    pub fn plain(self, text: impl Into<String>) -> Self {
+        self.styled(text, Style::new())
+    }
+
+    #[must_use]
+    pub fn styled(mut self, text: impl Into<String>, style: Style) -> Self {
+        self.push(text, style);
+        self
+    }
+
+    /// Appends text in a role colour, unstyled if the role has none.
+    #[must_use]
+    pub fn role(self, text: impl Into<String>, colour: u32) -> Self {
+        let style = Color::from_role(colour).map_or(Style::new(), |c| Style::new().fg(c));
+        self.styled(text, style)
+    }
+
+    pub fn push(&mut self, text: impl Into<String>, style: Style) {
+        let text = text.into();
+        if !text.is_empty() {
+            self.spans.push((style, text));
+        }
+    }
+
+    /// The text without any styling.
+    #[must_use]
+    pub fn to_plain(&self) -> String {
+        self.spans.iter().map(|(_, t)| t.as_str()).collect()
+    }
+
+    /// Renders with escape codes for `mode`.
+    #[must_use]
+    pub fn render(&self, mode: ColorMode) -> String {
+        let mut out = String::new();
+
+        for (style, text) in &self.spans {
+            if style.is_plain() || mode == ColorMode::None {
+                out.push_str(text);
+                continue;
+            }
+
+            style.write(&mut out, mode);
+            out.push_str(text);
+            out.push_str(crate::RESET);
+        }
+
+        out
+    }
+
+    /// Renders for the console, using the detected [`color_mode`].
+    #[must_use]
+    pub fn render_console(&self) -> String {
+        self.render(color_mode())
+    }
+
+    /// Renders as a Discord `ansi` code block no longer than `max_len` bytes, cutting off what
+    /// doesn't fit with an ellipsis.
+    #[must_use]
+    pub fn render_discord(&self, max_len: usize) -> String {
+        const OPEN: &str = "```ansi\n";
+        const CLOSE: &str = "\n```";
+        const ELLIPSIS: &str = "\x1B[0m…";
+
+        let budget = max_len.saturating_sub(OPEN.len() + CLOSE.len() + ELLIPSIS.len());
+        let mut body = String::new();
+
+        for (style, text) in &self.spans {
+            // a zero width space stops the content from closing the block early.
+            let text = text.replace("```", "`\u{200b}``");
+
+            let mut prefix = String::new();
+            style.write_discord(&mut prefix);
+            let suffix = if prefix.is_empty() { "" } else { crate::RESET };
+
+            let available = budget.saturating_sub(body.len() + prefix.len() + suffix.len());
+            if text.len() <= available {
+                body.push_str(&prefix);
+                body.push_str(&text);
+                body.push_str(suffix);
+                continue;
+            }
+
+            // fit what we can of this span, then stop.
+            let mut end = available;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            if end > 0 {
+                body.push_str(&prefix);
+                body.push_str(&text[..end]);
+                body.push_str(suffix);
+            }
+            body.push_str(ELLIPSIS);
+            break;
+        }
+
+        format!("{OPEN}{body}{CLOSE}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(set: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let set = set
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), OsString::from(v)))
+            .collect::<Vec<_>>();
+        move |name| set.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn no_color_wins_over_everything() {
+        let mode = ColorMode::from_vars(vars(&[
+            ("NO_COLOR", "1"),
+            ("COLORTERM", "truecolor"),
+            ("TERM", "xterm-256color"),
+        ]));
+        assert_eq!(mode, ColorMode::None);
+    }
+
+    #[test]
+    fn empty_no_color_is_ignored() {
+        let mode = ColorMode::from_vars(vars(&[("NO_COLOR", ""), ("TERM", "xterm-256color")]));
+        assert_eq!(mode, ColorMode::Ansi256);
+    }
+
+    #[test]
+    fn detects_from_colorterm_and_term() {
+        assert_eq!(
+            ColorMode::from_vars(vars(&[("COLORTERM", "24bit")])),
+            ColorMode::TrueColor
+        );
+        assert_eq!(
+            ColorMode::from_vars(vars(&[("TERM", "dumb")])),
+            ColorMode::None
+        );
+        assert_eq!(ColorMode::from_vars(vars(&[])), ColorMode::Ansi16);
+    }
+
+    #[test]
+    fn nearest_256_cube() {
+        assert_eq!(nearest_256(255, 0, 0), 196);
+        assert_eq!(nearest_256(0, 95, 135), 24);
+        // just past the midpoint between 95 and 135.
+        assert_eq!(nearest_256(116, 0, 0), 16 + 36 * 2);
+        assert_eq!(nearest_256(114, 0, 0), 16 + 36);
+    }
+
+    #[test]
+    fn nearest_256_greys() {
+        assert_eq!(nearest_256(0, 0, 0), 16);
+        assert_eq!(nearest_256(8, 8, 8), 232);
+        assert_eq!(nearest_256(128, 128, 128), 244);
+        assert_eq!(nearest_256(238, 238, 238), 255);
+        assert_eq!(nearest_256(250, 250, 250), 231);
+    }
+
+    #[test]
+    fn rgb_falls_back_per_mode() {
+        let colour = Color::Rgb(250, 10, 10);
+
+        assert_eq!(colour.escape(ColorMode::TrueColor), "\x1B[38;2;250;10;10m");
+        assert_eq!(colour.escape(ColorMode::Ansi256), "\x1B[38;5;196m");
+        assert_eq!(colour.escape(ColorMode::Ansi16), "\x1B[91m");
+        assert_eq!(colour.escape(ColorMode::None), "");
+    }
+
+    #[test]
+    fn discord_uses_the_normal_colours() {
+        assert_eq!(Color::HI_RED.discord_code(), 31);
+        assert_eq!(Color::Rgb(0, 0, 0).discord_code(), 30);
+        assert_eq!(Color::Rgb(40, 140, 200).discord_code(), 34);
+    }
+
+    #[test]
+    fn no_color_renders_plain_text() {
+        let text = StyledText::new()
+            .plain("a ")
+            .styled("b", Style::new().fg(Color::RED).bold());
+
+        assert_eq!(text.render(ColorMode::None), "a b");
+        assert_eq!(text.render(ColorMode::Ansi16), "a \x1B[1m\x1B[31mb\x1B[0m");
+    }
+
+    #[test]
+    fn discord_blocks_fit_the_limit() {
+        let text = StyledText::new().styled("x".repeat(100), Style::new().fg(Color::GREEN));
+        let rendered = text.render_discord(50);
+
+        assert!(rendered.len() <= 50, "{} bytes", rendered.len());
+        assert!(rendered.starts_with("```ansi\n\x1B[32mx"));
+        assert!(rendered.ends_with("\x1B[0m…\n```"));
+    }
+
+    #[test]
+    fn discord_blocks_escape_fences() {
+        let rendered = StyledText::new().plain("```").render_discord(100);
+        assert_eq!(rendered, "```ansi\n`\u{200b}``\n```");
+    }
+}
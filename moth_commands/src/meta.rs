@@ -165,6 +165,25 @@ async fn stats(ctx: Context<'_>) -> Result<(), Error> {
         );
     }
 
+    let ocr = ctx.data().ocr_engine.stats();
+    embed = embed.field(
+        "OCR",
+        format!(
+            "Workers: **{}/{}** busy\nQueued: **{}**\nProcessed: **{}**\nFailed: **{}**\nTimed \
+             out: **{}**\nRejected: **{}**\nLatency: **{}ms** avg, **{}ms** max",
+            ocr.busy,
+            ocr.workers,
+            ocr.queue_depth,
+            ocr.processed,
+            ocr.failed,
+            ocr.timed_out,
+            ocr.rejected,
+            ocr.average_latency.as_millis(),
+            ocr.max_latency.as_millis()
+        ),
+        true,
+    );
+
     ctx.send(lumi::CreateReply::default().embed(embed)).await?;
 
     Ok(())
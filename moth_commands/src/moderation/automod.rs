@@ -0,0 +1,143 @@
+use std::fmt::Write;
+
+use crate::{Context, Error};
+
+use lumi::serenity_prelude as serenity;
+use moth_core::data::automod::AutomodAction;
+use moth_filter::Tier;
+
+/// Configure what happens to messages the filter flags, nothing is done until a tier is set.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Automod",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    subcommands("set", "remove", "show"),
+    subcommand_required
+)]
+pub async fn automod(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set the actions for a tier: log, or any of delete, warn and timeout joined by +, like
+/// delete+timeout 10m.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Automod",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "mild, moderate, severe, evasive or spam"] tier: String,
+    #[description = "log, or delete, warn and timeout joined by +"] action: String,
+    #[description = "How long to time out for, like 30s, 10m, 2h or 1d"] duration: Option<String>,
+) -> Result<(), Error> {
+    let Some(tier) = parse_tier(ctx, &tier).await? else {
+        return Ok(());
+    };
+
+    let Some(action) = AutomodAction::parse(&action, duration.as_deref()) else {
+        ctx.say(
+            "Unknown action, expected `log` or any of `delete`, `warn` and `timeout` joined by `+`, \
+             like `delete+timeout 10m` (at most 28d).",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    ctx.data()
+        .database
+        .set_automod_tier(ctx.guild_id().unwrap(), tier, action)
+        .await?;
+
+    ctx.say(format!("**{}** messages will now {action}.", tier.name()))
+        .await?;
+
+    Ok(())
+}
+
+/// Stop acting on a tier.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Automod",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "mild, moderate, severe, evasive or spam"] tier: String,
+) -> Result<(), Error> {
+    let Some(tier) = parse_tier(ctx, &tier).await? else {
+        return Ok(());
+    };
+
+    let removed = ctx
+        .data()
+        .database
+        .remove_automod_tier(ctx.guild_id().unwrap(), tier)
+        .await?;
+
+    if removed {
+        ctx.say(format!(
+            "**{}** messages are no longer acted on.",
+            tier.name()
+        ))
+        .await?;
+    } else {
+        ctx.say(format!("**{}** doesn't have an action.", tier.name()))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Show the action for each tier.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Automod",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    let config = ctx
+        .data()
+        .database
+        .get_automod(ctx.guild_id().unwrap())
+        .unwrap_or_default();
+
+    let mut description = String::new();
+    for tier in Tier::ALL {
+        match config.get(&tier) {
+            Some(action) => writeln!(description, "**{}:** {action}", tier.name()).unwrap(),
+            None => writeln!(description, "**{}:** nothing", tier.name()).unwrap(),
+        }
+    }
+
+    if ctx
+        .data()
+        .database
+        .get_modlog(ctx.guild_id().unwrap())
+        .is_none()
+    {
+        description.push_str("\nSet a modlog channel to see what automod did.");
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Automod")
+        .description(description)
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
+
+async fn parse_tier(ctx: Context<'_>, tier: &str) -> Result<Option<Tier>, Error> {
+    let tier = Tier::from_name(tier);
+    if tier.is_none() {
+        ctx.say("Unknown tier, expected `mild`, `moderate`, `severe`, `evasive` or `spam`.")
+            .await?;
+    }
+
+    Ok(tier)
+}
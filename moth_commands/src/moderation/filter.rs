@@ -0,0 +1,207 @@
+use crate::{Context, Error};
+
+use lumi::serenity_prelude as serenity;
+use moth_filter::{TrieOverride, WordList};
+
+/// Manage this server's own filter words, checked on top of the global lists.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Filter",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    subcommands("add", "remove", "override_word", "list"),
+    subcommand_required
+)]
+pub async fn filter(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add a word to the badlist or fixlist, matched against lowercased and normalised text.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Filter",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "bad or fix"] list: String,
+    #[description = "Flagged (or unflagged) when a word contains it"]
+    #[rest]
+    word: String,
+) -> Result<(), Error> {
+    let Some(list) = parse_list(ctx, &list).await? else {
+        return Ok(());
+    };
+
+    let word = word.trim().to_lowercase();
+    let added = ctx
+        .data()
+        .database
+        .add_filter_word(ctx.guild_id().unwrap(), list, &word)
+        .await?;
+
+    if added {
+        ctx.say(format!("Added `{word}` to the {}list.", list.name()))
+            .await?;
+    } else {
+        ctx.say(format!("`{word}` is already on the {}list.", list.name()))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Remove a word from the badlist or fixlist.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Filter",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "bad or fix"] list: String,
+    #[description = "The word to remove"]
+    #[rest]
+    word: String,
+) -> Result<(), Error> {
+    let Some(list) = parse_list(ctx, &list).await? else {
+        return Ok(());
+    };
+
+    let word = word.trim().to_lowercase();
+    let removed = ctx
+        .data()
+        .database
+        .remove_filter_word(ctx.guild_id().unwrap(), list, &word)
+        .await?;
+
+    if removed {
+        ctx.say(format!("Removed `{word}` from the {}list.", list.name()))
+            .await?;
+    } else {
+        ctx.say(format!("`{word}` isn't on the {}list.", list.name()))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Change how severe the filter considers a word, leave out the kind to remove the override.
+#[lumi::command(
+    rename = "override",
+    prefix_command,
+    category = "Moderation - Filter",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn override_word(
+    ctx: Context<'_>,
+    #[description = "The word to override"] word: String,
+    #[description = "safe, mild, moderate or severe"] kind: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let word = word.trim().to_lowercase();
+    let Some(kind) = kind else {
+        if database.remove_filter_override(guild_id, &word).await? {
+            ctx.say(format!("Removed the override for `{word}`."))
+                .await?;
+        } else {
+            ctx.say(format!("`{word}` doesn't have an override."))
+                .await?;
+        }
+        return Ok(());
+    };
+
+    let Some(kind) = TrieOverride::from_name(&kind) else {
+        let kinds = TrieOverride::ALL
+            .iter()
+            .map(|k| format!("`{}`", k.name()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        ctx.say(format!("Unknown kind, expected one of: {kinds}"))
+            .await?;
+        return Ok(());
+    };
+
+    database.set_filter_override(guild_id, &word, kind).await?;
+    ctx.say(format!("`{word}` is now treated as {}.", kind.name()))
+        .await?;
+
+    Ok(())
+}
+
+/// Show this server's filter words and overrides.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Filter",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(filter) = ctx.data().database.get_filter(ctx.guild_id().unwrap()) else {
+        ctx.say("This server only uses the global lists.").await?;
+        return Ok(());
+    };
+
+    let words = |list: WordList| {
+        let mut words = filter
+            .list(list)
+            .iter()
+            .map(|w| format!("`{w}`"))
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+        field_value(&words)
+    };
+
+    let overrides = filter
+        .overrides()
+        .iter()
+        .map(|(word, kind)| format!("`{word}`: {}", kind.name()))
+        .collect::<Vec<_>>();
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Filter")
+        .field("Badlist", words(WordList::Bad), false)
+        .field("Fixlist", words(WordList::Fix), false)
+        .field("Overrides", field_value(&overrides), false)
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
+
+async fn parse_list(ctx: Context<'_>, list: &str) -> Result<Option<WordList>, Error> {
+    let list = WordList::from_name(list);
+    if list.is_none() {
+        ctx.say("Unknown list, expected `bad` or `fix`.").await?;
+    }
+
+    Ok(list)
+}
+
+/// Joins `items` to fit in an embed field, noting how many were left out.
+fn field_value(items: &[String]) -> String {
+    if items.is_empty() {
+        return "None".to_owned();
+    }
+
+    let mut value = String::new();
+    for (i, item) in items.iter().enumerate() {
+        let remaining = format!("\n…and {} more", items.len() - i);
+        if value.len() + item.len() + 2 + remaining.len() > 1024 {
+            value.push_str(&remaining);
+            break;
+        }
+
+        if !value.is_empty() {
+            value.push_str(", ");
+        }
+        value.push_str(item);
+    }
+
+    value
+}
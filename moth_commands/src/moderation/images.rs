@@ -0,0 +1,281 @@
+use std::fmt::Write;
+
+use crate::{Context, Error};
+
+use lumi::serenity_prelude::{self as serenity, Attachment};
+use moth_core::data::images::{ImageAction, ImageFilterConfig, DEFAULT_MAX_DISTANCE};
+use moth_core::ocr::is_discord_cdn;
+use moth_events::images::hash_image;
+
+/// Reposts shown by the repost lookup.
+const MAX_REPOSTS: i64 = 10;
+
+/// Hash image attachments, acting on ones in the blocklist and remembering them for reposts.
+#[lumi::command(
+    rename = "imagefilter",
+    prefix_command,
+    category = "Moderation - Images",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    subcommands("enable", "disable", "block", "unblock", "blocklist"),
+    subcommand_required
+)]
+pub async fn image_filter(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Start hashing images, with what to do about blocklisted ones.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Images",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn enable(
+    ctx: Context<'_>,
+    #[description = "alert or delete"] action: String,
+    #[description = "How many bits of the hash may differ, 6 by default"] distance: Option<u32>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let Some(action) = ImageAction::parse(&action) else {
+        ctx.say("Unknown action, expected `alert` or `delete`.")
+            .await?;
+        return Ok(());
+    };
+
+    let max_distance = distance.unwrap_or(DEFAULT_MAX_DISTANCE);
+    if max_distance > 32 {
+        ctx.say("A distance that high would match unrelated images, keep it to 32 or below.")
+            .await?;
+        return Ok(());
+    }
+
+    database
+        .set_image_filter(
+            guild_id,
+            ImageFilterConfig {
+                action,
+                max_distance,
+            },
+        )
+        .await?;
+
+    let mut response = format!(
+        "Images are now hashed, and blocklisted ones within {max_distance} bits will `{action}`."
+    );
+    if database.get_modlog(guild_id).is_none() {
+        response.push_str("\nSet a modlog channel to receive alerts.");
+    }
+    ctx.say(response).await?;
+
+    Ok(())
+}
+
+/// Stop hashing images, the blocklist is kept.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Images",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn disable(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    if ctx.data().database.remove_image_filter(guild_id).await? {
+        ctx.say("Image filter disabled.").await?;
+    } else {
+        ctx.say("This server doesn't filter images.").await?;
+    }
+
+    Ok(())
+}
+
+/// Add an image to the blocklist.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Images",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn block(
+    ctx: Context<'_>,
+    #[description = "The image to block"] image: Attachment,
+    #[description = "What the image is, shown in alerts"]
+    #[rest]
+    label: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let data = ctx.data();
+
+    let hash = match hash_image(&data, &image.url).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            println!("Failed to hash {}: {e}", image.url);
+            ctx.say("Couldn't read that image.").await?;
+            return Ok(());
+        }
+    };
+
+    let id = data
+        .database
+        .block_image(guild_id, hash, &label, ctx.author().id)
+        .await?;
+
+    let mut response = format!("Blocked as #{id} (`{hash:016x}`).");
+    if data.database.get_image_filter(guild_id).is_none() {
+        response.push_str("\nImages aren't checked until the image filter is enabled.");
+    }
+    ctx.say(response).await?;
+
+    Ok(())
+}
+
+/// Remove an image from the blocklist.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Images",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn unblock(
+    ctx: Context<'_>,
+    #[description = "The id shown in the blocklist"] id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    if ctx.data().database.unblock_image(guild_id, id).await? {
+        ctx.say(format!("Unblocked #{id}.")).await?;
+    } else {
+        ctx.say(format!("#{id} isn't in the blocklist.")).await?;
+    }
+
+    Ok(())
+}
+
+/// Show the blocked images and how they're acted on.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Images",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn blocklist(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let mut description = match database.get_image_filter(guild_id) {
+        Some(config) => format!(
+            "Blocklisted images within {} bits will `{}`.\n\n",
+            config.max_distance, config.action
+        ),
+        None => String::from("The image filter is disabled.\n\n"),
+    };
+
+    let blocklist = database.get_image_blocklist(guild_id);
+    if blocklist.is_empty() {
+        description.push_str("*No blocked images.*");
+    }
+    for image in &blocklist {
+        writeln!(
+            description,
+            "**#{}** {} (`{:016x}`), added by <@{}> <t:{}:R>",
+            image.id, image.label, image.hash, image.added_by, image.created_at
+        )
+        .unwrap();
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Image blocklist")
+        .description(description)
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Find earlier messages with a near-identical image, posted while the image filter was on.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Moderation - Images",
+    required_permissions = "MANAGE_MESSAGES",
+    guild_only
+)]
+pub async fn repost(
+    ctx: Context<'_>,
+    #[description = "The image to look up"] image: Option<Attachment>,
+    #[description = "A link to the image to look up"] url: Option<String>,
+    #[description = "How many bits of the hash may differ"] distance: Option<u32>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let data = ctx.data();
+
+    let Some(url) = image.map(|i| i.url.to_string()).or(url) else {
+        ctx.say("Give me an image or a link to one.").await?;
+        return Ok(());
+    };
+
+    if !is_discord_cdn(&url) {
+        ctx.say("Only links to images uploaded to Discord can be looked up.")
+            .await?;
+        return Ok(());
+    }
+
+    let max_distance = distance
+        .or_else(|| {
+            data.database
+                .get_image_filter(guild_id)
+                .map(|c| c.max_distance)
+        })
+        .unwrap_or(DEFAULT_MAX_DISTANCE)
+        .min(32);
+
+    ctx.defer().await?;
+
+    let hash = match hash_image(&data, &url).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            println!("Failed to hash {url}: {e}");
+            ctx.say("Couldn't read that image.").await?;
+            return Ok(());
+        }
+    };
+
+    let reposts = data
+        .database
+        .find_reposts(guild_id, hash, max_distance, MAX_REPOSTS)
+        .await?;
+
+    let mut description = String::new();
+    if reposts.is_empty() {
+        description.push_str("No earlier messages with this image.");
+    }
+    for repost in &reposts {
+        writeln!(
+            description,
+            "https://discord.com/channels/{guild_id}/{}/{} by <@{}> <t:{}:R>, `{}` ({} bits apart)",
+            repost.channel_id,
+            repost.message_id,
+            repost.user_id,
+            repost.created_at,
+            repost.file_name,
+            repost.distance
+        )
+        .unwrap();
+    }
+
+    description.push_str("\n\nOnly images posted while the image filter was enabled are found.");
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Reposts")
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Hash {hash:016x}, up to {max_distance} bits apart"
+        )))
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
@@ -1,5 +1,13 @@
 use std::{collections::HashSet, time::Duration};
 
+mod automod;
+mod filter;
+mod images;
+mod modlog;
+mod names;
+mod ocr;
+mod responses;
+
 use crate::{Error, PrefixContext};
 
 use lumi::serenity_prelude as serenity;
@@ -196,6 +204,10 @@ async fn delete_messages(
 ) -> Result<(), Error> {
     let reason = &format!("Purged by {} (ID:{})", ctx.author().name, ctx.author().id);
 
+    ctx.data()
+        .modlog_queue
+        .mark_own_deletions(deleted.iter().copied().chain([ctx.msg.id]));
+
     if deleted.len() > 99 {
         let _ = ctx.msg.delete(ctx.http(), Some(reason)).await;
     } else {
@@ -432,6 +444,18 @@ fn has_permissions(ctx: &PrefixContext) -> (bool, bool) {
 }
 
 #[must_use]
-pub fn commands() -> [crate::Command; 2] {
-    [purge(), purge_in()]
+pub fn commands() -> [crate::Command; 11] {
+    [
+        purge(),
+        purge_in(),
+        modlog::modlog(),
+        filter::filter(),
+        automod::automod(),
+        names::name_filter(),
+        ocr::ocr_language(),
+        ocr::ocr_filter(),
+        images::image_filter(),
+        images::repost(),
+        responses::responses(),
+    ]
 }
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+
+use crate::{Context, Error};
+
+use lumi::serenity_prelude::{self as serenity, GenericChannelId};
+use moth_core::data::modlog::{ModlogConfig, ModlogEvents};
+
+/// Configure the channel edits, deletions and channel/role changes are mirrored to.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Modlog",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    subcommands("set", "disable", "toggle", "show"),
+    subcommand_required
+)]
+pub async fn modlog(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set the modlog channel, keeping the current event toggles.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Modlog",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "Channel to post events to"] channel: GenericChannelId,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    if let Some(problem) = unusable_channel(ctx, channel) {
+        ctx.say(problem).await?;
+        return Ok(());
+    }
+
+    let events = database
+        .get_modlog(guild_id)
+        .map_or(ModlogEvents::all(), |c| c.events);
+
+    database
+        .set_modlog(
+            guild_id,
+            ModlogConfig {
+                channel_id: channel,
+                events,
+            },
+        )
+        .await?;
+
+    ctx.say(format!("Events will now be mirrored to <#{channel}>."))
+        .await?;
+
+    Ok(())
+}
+
+/// Why the bot couldn't post modlog embeds to `channel`, checked against the guild cache.
+fn unusable_channel(ctx: Context<'_>, channel_id: GenericChannelId) -> Option<&'static str> {
+    let Some(guild) = ctx.guild() else {
+        return Some("Could not retrieve this server from the cache, try again later.");
+    };
+
+    let (channel, is_thread) =
+        if let Some(channel) = guild.channels.get(&channel_id.expect_channel()) {
+            (channel, false)
+        } else if let Some(thread) = guild
+            .threads
+            .iter()
+            .find(|t| t.id == channel_id.expect_thread())
+        {
+            let Some(parent) = guild.channels.get(&thread.parent_id) else {
+                return Some("Could not find that thread's channel in the cache.");
+            };
+            (parent, true)
+        } else {
+            return Some("That channel isn't in this server.");
+        };
+
+    if !is_thread
+        && matches!(
+            channel.base.kind,
+            serenity::ChannelType::Category | serenity::ChannelType::Forum
+        )
+    {
+        return Some("Messages can't be posted directly in that channel.");
+    }
+
+    let Some(member) = guild.members.get(&ctx.cache().current_user().id) else {
+        return Some("Could not retrieve my permissions from the cache, try again later.");
+    };
+
+    let permissions = guild.user_permissions_in(channel, member);
+    let can_send = if is_thread {
+        permissions.send_messages_in_threads()
+    } else {
+        permissions.send_messages()
+    };
+
+    if !(permissions.view_channel() && can_send && permissions.embed_links()) {
+        return Some("I need to be able to view, send messages and embed links in that channel.");
+    }
+
+    None
+}
+
+/// Stop mirroring events.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Modlog",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn disable(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    if ctx.data().database.remove_modlog(guild_id).await? {
+        ctx.say("Modlog disabled.").await?;
+    } else {
+        ctx.say("This server doesn't have a modlog channel.")
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Turn mirroring of an event kind on or off.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Modlog",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn toggle(
+    ctx: Context<'_>,
+    #[description = "message_edit, message_delete, channels, threads, roles or member_roles"]
+    event: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let Some(mut config) = database.get_modlog(guild_id) else {
+        ctx.say("Set a modlog channel first.").await?;
+        return Ok(());
+    };
+
+    let Some(flag) = ModlogEvents::from_name(&event.to_uppercase().replace('-', "_")) else {
+        ctx.say(format!(
+            "Unknown event, expected one of: {}",
+            event_names(ModlogEvents::all())
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    config.events.toggle(flag);
+    database.set_modlog(guild_id, config).await?;
+
+    let state = if config.events.contains(flag) {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    ctx.say(format!("`{}` is now {state}.", event.to_lowercase()))
+        .await?;
+
+    Ok(())
+}
+
+/// Show the modlog channel and which events are mirrored.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Modlog",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    let Some(config) = ctx.data().database.get_modlog(guild_id) else {
+        ctx.say("This server doesn't have a modlog channel.")
+            .await?;
+        return Ok(());
+    };
+
+    let mut description = format!("**Channel:** <#{}>\n\n", config.channel_id);
+    for (name, flag) in ModlogEvents::all().iter_names() {
+        let mark = if config.events.contains(flag) {
+            "✅"
+        } else {
+            "❌"
+        };
+        writeln!(description, "{mark} `{}`", name.to_lowercase()).unwrap();
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Modlog")
+        .description(description)
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
+
+fn event_names(events: ModlogEvents) -> String {
+    events
+        .iter_names()
+        .map(|(name, _)| format!("`{}`", name.to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
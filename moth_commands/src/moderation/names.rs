@@ -0,0 +1,125 @@
+use std::fmt::Write;
+
+use crate::{Context, Error};
+
+use lumi::serenity_prelude as serenity;
+use moth_core::data::names::NameFilterActions;
+
+/// Filter member names and custom statuses when they join or change them.
+#[lumi::command(
+    rename = "namefilter",
+    prefix_command,
+    category = "Moderation - Names",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    subcommands("toggle", "disable", "show"),
+    subcommand_required
+)]
+pub async fn name_filter(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Turn an action on or off, names are only checked while at least one is on.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Names",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn toggle(
+    ctx: Context<'_>,
+    #[description = "alert, reset_nick or dehoist"] action: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let Some(flag) = NameFilterActions::from_name(&action.to_uppercase().replace('-', "_")) else {
+        ctx.say(format!(
+            "Unknown action, expected one of: {}",
+            action_names(NameFilterActions::all())
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let mut actions = database
+        .get_name_filter(guild_id)
+        .unwrap_or(NameFilterActions::empty());
+    actions.toggle(flag);
+    database.set_name_filter(guild_id, actions).await?;
+
+    let state = if actions.contains(flag) {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    ctx.say(format!("`{}` is now {state}.", action.to_lowercase()))
+        .await?;
+
+    Ok(())
+}
+
+/// Stop checking names.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Names",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn disable(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    if database.get_name_filter(guild_id).is_some() {
+        database
+            .set_name_filter(guild_id, NameFilterActions::empty())
+            .await?;
+        ctx.say("Name filter disabled.").await?;
+    } else {
+        ctx.say("This server doesn't filter names.").await?;
+    }
+
+    Ok(())
+}
+
+/// Show which actions are taken on flagged names.
+#[lumi::command(
+    prefix_command,
+    category = "Moderation - Names",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let actions = database
+        .get_name_filter(guild_id)
+        .unwrap_or(NameFilterActions::empty());
+
+    let mut description = String::new();
+    for (name, flag) in NameFilterActions::all().iter_names() {
+        let mark = if actions.contains(flag) { "✅" } else { "❌" };
+        writeln!(description, "{mark} `{}`", name.to_lowercase()).unwrap();
+    }
+
+    if actions.contains(NameFilterActions::ALERT) && database.get_modlog(guild_id).is_none() {
+        description.push_str("\nSet a modlog channel to receive alerts.");
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Name filter")
+        .description(description)
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
+
+fn action_names(actions: NameFilterActions) -> String {
+    actions
+        .iter_names()
+        .map(|(name, _)| format!("`{}`", name.to_lowercase()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
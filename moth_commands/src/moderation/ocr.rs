@@ -0,0 +1,81 @@
+use crate::{Context, Error};
+
+use moth_core::ocr::{is_valid_language, DEFAULT_LANGUAGE};
+
+/// Set the language images are read in for OCR regexes, leave it out to go back to the default.
+#[lumi::command(
+    rename = "ocr-language",
+    prefix_command,
+    category = "Moderation - OCR",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn ocr_language(
+    ctx: Context<'_>,
+    #[description = "A Tesseract language code like eng, deu or eng+jpn"] language: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let Some(language) = language else {
+        database.remove_ocr_language(guild_id).await?;
+        ctx.say(format!("Images will be read in `{DEFAULT_LANGUAGE}`."))
+            .await?;
+        return Ok(());
+    };
+
+    let language = language.to_lowercase();
+    if !is_valid_language(&language) {
+        ctx.say("That isn't a Tesseract language code, like `eng`, `chi_sim` or `eng+jpn`.")
+            .await?;
+        return Ok(());
+    }
+
+    database.set_ocr_language(guild_id, &language).await?;
+    ctx.say(format!(
+        "Images will be read in `{language}`, unless a regex picks its own."
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Run the content filter over text in images, feeding automod and the modlog.
+#[lumi::command(
+    rename = "ocr-filter",
+    prefix_command,
+    category = "Moderation - OCR",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn ocr_filter(
+    ctx: Context<'_>,
+    #[description = "Whether images are filtered, leave out to see the current setting"]
+    enabled: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let Some(enabled) = enabled else {
+        let state = if database.get_ocr_filter(guild_id) {
+            "filtered"
+        } else {
+            "not filtered"
+        };
+        ctx.say(format!("Text in images is {state}.")).await?;
+        return Ok(());
+    };
+
+    database.set_ocr_filter(guild_id, enabled).await?;
+    if enabled {
+        let language = database.get_ocr_language(guild_id);
+        ctx.say(format!(
+            "Text in images, read in `{language}`, now goes through the filter."
+        ))
+        .await?;
+    } else {
+        ctx.say("Text in images is no longer filtered.").await?;
+    }
+
+    Ok(())
+}
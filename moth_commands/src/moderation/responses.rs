@@ -0,0 +1,865 @@
+// slash commands take every option as an argument.
+#![allow(clippy::too_many_arguments)]
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::{Context, Error};
+
+use lumi::serenity_prelude::{
+    self as serenity, ChannelType, GenericChannelId, MessageId, ReactionType,
+};
+use moth_core::data::responses::{
+    compile_pattern, validate_template, Cooldown, CooldownScope, DetectionType, RegexData,
+    ResponseType, MAX_COOLDOWN,
+};
+use moth_core::ocr::{is_valid_language, OcrPriority};
+use moth_events::handlers::messages::responses::{
+    check_scope, extract_message_content, locate, scoped,
+};
+use moth_events::ocr::MessageFrames;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, lumi::ChoiceParameter)]
+pub enum Scope {
+    Guild,
+    Channel,
+    Category,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, lumi::ChoiceParameter)]
+pub enum Detection {
+    Content,
+    #[name = "OCR"]
+    Ocr,
+    Both,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, lumi::ChoiceParameter)]
+pub enum Per {
+    #[name = "Server"]
+    Guild,
+    Channel,
+    User,
+}
+
+impl From<Per> for CooldownScope {
+    fn from(value: Per) -> Self {
+        match value {
+            Per::Guild => CooldownScope::Global,
+            Per::Channel => CooldownScope::Channel,
+            Per::User => CooldownScope::User,
+        }
+    }
+}
+
+impl From<Detection> for DetectionType {
+    fn from(value: Detection) -> Self {
+        match value {
+            Detection::Content => DetectionType::CONTENT,
+            Detection::Ocr => DetectionType::OCR,
+            Detection::Both => DetectionType::CONTENT | DetectionType::OCR,
+        }
+    }
+}
+
+/// Manage the regexes the bot responds to.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Moderation - Responses",
+    required_permissions = "MANAGE_GUILD",
+    guild_only,
+    subcommands("add", "edit", "remove", "list", "show", "test"),
+    subcommand_required
+)]
+pub async fn responses(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+fn is_category(ctx: Context<'_>, channel: GenericChannelId) -> bool {
+    ctx.guild().and_then(|g| {
+        g.channels
+            .get(&channel.expect_channel())
+            .map(|c| c.base.kind)
+    }) == Some(ChannelType::Category)
+}
+
+/// Checks the channel given for a scope, returning what the regex is stored under.
+fn resolve_scope(
+    ctx: Context<'_>,
+    scope: Scope,
+    channel: Option<GenericChannelId>,
+) -> Result<Option<GenericChannelId>, &'static str> {
+    if scope == Scope::Guild {
+        return Ok(None);
+    }
+
+    let Some(channel) = channel else {
+        return Err("Pick the channel or category this applies to.");
+    };
+
+    match (scope, is_category(ctx, channel)) {
+        (Scope::Channel, true) => Err("That's a category, use the category scope for it."),
+        (Scope::Category, false) => Err("That isn't a category."),
+        _ => Ok(Some(channel)),
+    }
+}
+
+fn describe_scope(ctx: Context<'_>, scope: Option<GenericChannelId>) -> String {
+    let Some(channel) = scope else {
+        return String::from("Server");
+    };
+
+    if is_category(ctx, channel) {
+        format!("Category <#{channel}>")
+    } else {
+        format!("<#{channel}>")
+    }
+}
+
+/// Parses channel mentions or ids separated by spaces or commas.
+fn parse_channels(text: &str) -> Result<HashSet<GenericChannelId>, String> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            part.trim_start_matches("<#")
+                .trim_end_matches('>')
+                .parse::<u64>()
+                .ok()
+                .filter(|id| *id != 0)
+                .map(GenericChannelId::new)
+                .ok_or_else(|| format!("`{part}` isn't a channel."))
+        })
+        .collect()
+}
+
+fn parse_response(
+    message: Option<String>,
+    emoji: Option<String>,
+) -> Result<Option<ResponseType>, &'static str> {
+    match (message, emoji) {
+        (Some(_), Some(_)) => Err("Respond with either a message or an emoji, not both."),
+        (Some(message), None) if message.chars().count() > 2000 => {
+            Err("Responses can be at most 2000 characters long.")
+        }
+        (Some(message), None) => Ok(Some(ResponseType::Message(message))),
+        (None, Some(emoji)) => {
+            let emoji = emoji.trim();
+            match emoji.parse::<ReactionType>() {
+                Ok(reaction @ ReactionType::Custom { .. }) => {
+                    Ok(Some(ResponseType::Emoji(reaction)))
+                }
+                Ok(reaction @ ReactionType::Unicode(_)) if emojis::get(emoji).is_some() => {
+                    Ok(Some(ResponseType::Emoji(reaction)))
+                }
+                _ => Err("That isn't an emoji."),
+            }
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Checks the placeholders of a message response against the groups of its pattern.
+fn check_template(regex: &RegexData) -> Result<(), String> {
+    match &regex.response {
+        ResponseType::Message(template) => validate_template(template, &regex.pattern),
+        ResponseType::Emoji(_) => Ok(()),
+    }
+}
+
+fn describe_response(response: &ResponseType) -> String {
+    match response {
+        ResponseType::Message(message) => message.clone(),
+        ResponseType::Emoji(reaction) => format!("React with {reaction}"),
+    }
+}
+
+fn describe_detection(detection: DetectionType) -> &'static str {
+    if detection == DetectionType::CONTENT | DetectionType::OCR {
+        "Content and OCR"
+    } else if detection.contains(DetectionType::OCR) {
+        "OCR"
+    } else {
+        "Content"
+    }
+}
+
+/// Changes the parts of a cooldown that were given, 0 turning off the wait or the cap.
+fn apply_cooldown(
+    cooldown: &mut Cooldown,
+    per: Option<Per>,
+    seconds: Option<u32>,
+    max_per_minute: Option<u32>,
+) -> Result<(), String> {
+    if let Some(seconds) = seconds {
+        if seconds > MAX_COOLDOWN {
+            return Err(format!(
+                "Cooldowns can be at most {MAX_COOLDOWN} seconds long."
+            ));
+        }
+        cooldown.seconds = seconds;
+    }
+
+    if let Some(max) = max_per_minute {
+        cooldown.max_per_minute = (max > 0).then_some(max);
+    }
+
+    if let Some(per) = per {
+        cooldown.scope = per.into();
+    }
+
+    Ok(())
+}
+
+fn describe_cooldown(cooldown: Cooldown) -> String {
+    if !cooldown.is_enabled() {
+        return String::from("None");
+    }
+
+    let per = match cooldown.scope {
+        CooldownScope::Global => "server",
+        CooldownScope::Channel => "channel",
+        CooldownScope::User => "user",
+    };
+
+    let mut limits = Vec::new();
+    if cooldown.seconds > 0 {
+        limits.push(format!("{}s between responses", cooldown.seconds));
+    }
+    if let Some(max) = cooldown.max_per_minute {
+        limits.push(format!("at most {max} a minute"));
+    }
+
+    format!("{} per {per}", limits.join(", "))
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_owned();
+    }
+
+    let mut truncated = text.chars().take(max - 1).collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+/// Respond to messages matching a regex.
+///
+/// Messages can use `{user}`, `{user.mention}`, `{channel}`, `{guild}`, the groups of the
+/// pattern like `{1}` or `{name}`, and `{a|b|c}` to pick one at random.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Moderation - Responses",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "The regex to match"] pattern: String,
+    #[description = "Where the regex applies, the whole server by default"] scope: Option<Scope>,
+    #[description = "The channel or category for those scopes"] channel: Option<GenericChannelId>,
+    #[description = "The message to reply with"] message: Option<String>,
+    #[description = "The emoji to react with"] emoji: Option<String>,
+    #[description = "What to match against, the message content by default"] detection: Option<
+        Detection,
+    >,
+    #[description = "If a category regex applies to its channels, on by default"]
+    recurse_channels: Option<bool>,
+    #[description = "If the regex applies to threads, on by default"] recurse_threads: Option<bool>,
+    #[description = "Channels, threads or categories to ignore"] exceptions: Option<String>,
+    #[description = "The Tesseract language for OCR, the server's by default"] ocr_language: Option<
+        String,
+    >,
+    #[description = "Seconds to wait between responses"] cooldown: Option<u32>,
+    #[description = "How many responses a minute at most"] max_per_minute: Option<u32>,
+    #[description = "What the cooldown is kept per, the server by default"] cooldown_per: Option<
+        Per,
+    >,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    let pattern = match compile_pattern(&pattern) {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            ctx.say(e).await?;
+            return Ok(());
+        }
+    };
+
+    let scope = match resolve_scope(ctx, scope.unwrap_or(Scope::Guild), channel) {
+        Ok(scope) => scope,
+        Err(e) => {
+            ctx.say(e).await?;
+            return Ok(());
+        }
+    };
+
+    let response = match parse_response(message, emoji) {
+        Ok(Some(response)) => response,
+        Ok(None) => {
+            ctx.say("Give a message to reply with or an emoji to react with.")
+                .await?;
+            return Ok(());
+        }
+        Err(e) => {
+            ctx.say(e).await?;
+            return Ok(());
+        }
+    };
+
+    let exceptions = match exceptions.as_deref().map(parse_channels).transpose() {
+        Ok(exceptions) => exceptions.unwrap_or_default(),
+        Err(e) => {
+            ctx.say(e).await?;
+            return Ok(());
+        }
+    };
+
+    let ocr_language = ocr_language.map(|l| l.to_lowercase());
+    if ocr_language
+        .as_deref()
+        .is_some_and(|l| !is_valid_language(l))
+    {
+        ctx.say("That isn't a Tesseract language code, like `eng`, `chi_sim` or `eng+jpn`.")
+            .await?;
+        return Ok(());
+    }
+
+    let mut limits = Cooldown::default();
+    if let Err(e) = apply_cooldown(&mut limits, cooldown_per, cooldown, max_per_minute) {
+        ctx.say(e).await?;
+        return Ok(());
+    }
+
+    let regex = RegexData {
+        id: 0,
+        pattern,
+        recurse_channels: recurse_channels.unwrap_or(true),
+        recurse_threads: recurse_threads.unwrap_or(true),
+        response,
+        exceptions,
+        detection_type: detection.unwrap_or(Detection::Content).into(),
+        ocr_language,
+        cooldown: limits,
+    };
+
+    if let Err(e) = check_template(&regex) {
+        ctx.say(e).await?;
+        return Ok(());
+    }
+
+    let id = ctx
+        .data()
+        .database
+        .add_regex(guild_id, scope, regex)
+        .await?;
+    ctx.say(format!(
+        "Added response #{id} for {}.",
+        describe_scope(ctx, scope)
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Change a regex response, leaving out what stays the same.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Moderation - Responses",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn edit(
+    ctx: Context<'_>,
+    #[description = "The id of the response"] id: i32,
+    #[description = "The regex to match"] pattern: Option<String>,
+    #[description = "Where the regex applies"] scope: Option<Scope>,
+    #[description = "The channel or category for those scopes"] channel: Option<GenericChannelId>,
+    #[description = "The message to reply with"] message: Option<String>,
+    #[description = "The emoji to react with"] emoji: Option<String>,
+    #[description = "What to match against"] detection: Option<Detection>,
+    #[description = "If a category regex applies to its channels"] recurse_channels: Option<bool>,
+    #[description = "If the regex applies to threads"] recurse_threads: Option<bool>,
+    #[description = "Channels, threads or categories to ignore, or none"] exceptions: Option<
+        String,
+    >,
+    #[description = "The Tesseract language for OCR, or default"] ocr_language: Option<String>,
+    #[description = "Seconds to wait between responses, or 0"] cooldown: Option<u32>,
+    #[description = "How many responses a minute at most, or 0"] max_per_minute: Option<u32>,
+    #[description = "What the cooldown is kept per"] cooldown_per: Option<Per>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let Some((old_scope, mut regex)) = database.get_regex(guild_id, id).await? else {
+        ctx.say(format!("There's no response #{id}.")).await?;
+        return Ok(());
+    };
+
+    if let Some(pattern) = pattern {
+        match compile_pattern(&pattern) {
+            Ok(pattern) => regex.pattern = pattern,
+            Err(e) => {
+                ctx.say(e).await?;
+                return Ok(());
+            }
+        }
+    }
+
+    let new_scope = if scope.is_some() || channel.is_some() {
+        // a channel alone keeps the kind of scope the regex already had.
+        let scope = scope.unwrap_or(match old_scope {
+            Some(old) if is_category(ctx, old) => Scope::Category,
+            _ => Scope::Channel,
+        });
+
+        match resolve_scope(ctx, scope, channel.or(old_scope)) {
+            Ok(scope) => scope,
+            Err(e) => {
+                ctx.say(e).await?;
+                return Ok(());
+            }
+        }
+    } else {
+        old_scope
+    };
+
+    match parse_response(message, emoji) {
+        Ok(Some(response)) => regex.response = response,
+        Ok(None) => {}
+        Err(e) => {
+            ctx.say(e).await?;
+            return Ok(());
+        }
+    }
+
+    if let Some(exceptions) = exceptions {
+        if exceptions.eq_ignore_ascii_case("none") {
+            regex.exceptions.clear();
+        } else {
+            match parse_channels(&exceptions) {
+                Ok(exceptions) => regex.exceptions = exceptions,
+                Err(e) => {
+                    ctx.say(e).await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    if let Some(language) = ocr_language {
+        let language = language.to_lowercase();
+        if language == "default" {
+            regex.ocr_language = None;
+        } else if is_valid_language(&language) {
+            regex.ocr_language = Some(language);
+        } else {
+            ctx.say("That isn't a Tesseract language code, like `eng`, `chi_sim` or `eng+jpn`.")
+                .await?;
+            return Ok(());
+        }
+    }
+
+    if let Some(detection) = detection {
+        regex.detection_type = detection.into();
+    }
+    if let Some(recurse_channels) = recurse_channels {
+        regex.recurse_channels = recurse_channels;
+    }
+    if let Some(recurse_threads) = recurse_threads {
+        regex.recurse_threads = recurse_threads;
+    }
+
+    if let Err(e) = apply_cooldown(&mut regex.cooldown, cooldown_per, cooldown, max_per_minute) {
+        ctx.say(e).await?;
+        return Ok(());
+    }
+
+    // a new pattern can drop groups the response still uses.
+    if let Err(e) = check_template(&regex) {
+        ctx.say(e).await?;
+        return Ok(());
+    }
+
+    database
+        .edit_regex(guild_id, old_scope, new_scope, regex)
+        .await?;
+    ctx.say(format!("Updated response #{id}.")).await?;
+
+    Ok(())
+}
+
+/// Remove a regex response.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Moderation - Responses",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "The id of the response"] id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    if ctx.data().database.remove_regex(guild_id, id).await? {
+        ctx.say(format!("Removed response #{id}.")).await?;
+    } else {
+        ctx.say(format!("There's no response #{id}.")).await?;
+    }
+
+    Ok(())
+}
+
+/// List the server's regex responses.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Moderation - Responses",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    let database = &ctx.data().database;
+
+    let Some(cache) = database.get_responses_regexes(guild_id).await? else {
+        ctx.say("This server has no responses.").await?;
+        return Ok(());
+    };
+
+    let mut regexes = cache
+        .global
+        .iter()
+        .map(|r| (None, r))
+        .chain(
+            cache
+                .channel
+                .iter()
+                .flat_map(|(c, r)| r.iter().map(move |r| (Some(*c), r))),
+        )
+        .collect::<Vec<_>>();
+    regexes.sort_by_key(|(_, r)| r.id);
+
+    if regexes.is_empty() {
+        ctx.say("This server has no responses.").await?;
+        return Ok(());
+    }
+
+    let mut description = String::new();
+    for (scope, regex) in &regexes {
+        let mut line = format!(
+            "**#{}** {} `{}` → {}",
+            regex.id,
+            describe_scope(ctx, *scope),
+            truncate(regex.pattern.as_str(), 60).replace('`', "'"),
+            truncate(&describe_response(&regex.response), 60)
+        );
+        let suppressed = database.get_suppressed(regex.id);
+        if suppressed > 0 {
+            write!(line, " ({suppressed} suppressed)").unwrap();
+        }
+        line.push('\n');
+
+        if description.len() + line.len() > 4000 {
+            description.push_str("…");
+            break;
+        }
+        description.push_str(&line);
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Responses")
+        .description(description)
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Show everything about a regex response.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Moderation - Responses",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn show(
+    ctx: Context<'_>,
+    #[description = "The id of the response"] id: i32,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let database = &ctx.data().database;
+
+    let Some((scope, regex)) = database.get_regex(guild_id, id).await? else {
+        ctx.say(format!("There's no response #{id}.")).await?;
+        return Ok(());
+    };
+
+    let mut exceptions = String::new();
+    for channel_id in &regex.exceptions {
+        write!(exceptions, "<#{channel_id}> ").unwrap();
+    }
+    if exceptions.is_empty() {
+        exceptions.push_str("None");
+    }
+
+    let ocr_language = regex
+        .ocr_language
+        .clone()
+        .unwrap_or_else(|| format!("{} (server)", database.get_ocr_language(guild_id)));
+
+    let embed = serenity::CreateEmbed::new()
+        .title(format!("Response #{id}"))
+        .field(
+            "Pattern",
+            format!("```\n{}\n```", truncate(regex.pattern.as_str(), 1000)),
+            false,
+        )
+        .field("Scope", describe_scope(ctx, scope), true)
+        .field("Detection", describe_detection(regex.detection_type), true)
+        .field("OCR language", ocr_language, true)
+        .field("Recurse channels", regex.recurse_channels.to_string(), true)
+        .field("Recurse threads", regex.recurse_threads.to_string(), true)
+        .field("Cooldown", describe_cooldown(regex.cooldown), true)
+        .field("Suppressed", database.get_suppressed(id).to_string(), true)
+        .field("Exceptions", truncate(&exceptions, 1024), false)
+        .field(
+            "Response",
+            truncate(&describe_response(&regex.response), 1024),
+            false,
+        )
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Parses a `https://discord.com/channels/guild/channel/message` link.
+fn parse_message_link(link: &str) -> Option<(serenity::GuildId, GenericChannelId, MessageId)> {
+    let path = link
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .split_once("/channels/")?
+        .1;
+
+    let mut ids = path
+        .split('/')
+        .map(|id| id.parse::<u64>().ok().filter(|id| *id != 0));
+    let guild_id = ids.next()??;
+    let channel_id = ids.next()??;
+    let message_id = ids.next()??;
+
+    Some((
+        serenity::GuildId::new(guild_id),
+        GenericChannelId::new(channel_id),
+        MessageId::new(message_id),
+    ))
+}
+
+/// Whether the invoker can view `channel_id` and read its history, so linked messages aren't
+/// shown to someone who couldn't read them there.
+async fn can_read_channel(ctx: Context<'_>, channel_id: GenericChannelId) -> bool {
+    let guild_id = ctx.guild_id().unwrap();
+    let Ok(member) = guild_id.member(ctx, ctx.author().id).await else {
+        return false;
+    };
+
+    let Some(guild) = ctx.guild() else {
+        return false;
+    };
+
+    let channel = guild
+        .channels
+        .get(&channel_id.expect_channel())
+        .or_else(|| {
+            guild
+                .threads
+                .iter()
+                .find(|t| t.id == channel_id.expect_thread())
+                .and_then(|thread| guild.channels.get(&thread.parent_id))
+        });
+
+    channel.is_some_and(|channel| {
+        let permissions = guild.user_permissions_in(channel, &member);
+        permissions.view_channel() && permissions.read_message_history()
+    })
+}
+
+/// Where a regex first matches, as a line of the test results.
+fn describe_match(source: &str, text: &str, span: std::ops::Range<usize>) -> String {
+    format!(
+        "matched {source} at `{}..{}`: `{}`",
+        span.start,
+        span.end,
+        truncate(&text[span.clone()], 40).replace('`', "'")
+    )
+}
+
+/// Check which responses would trigger, without sending anything.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Moderation - Responses",
+    required_permissions = "MANAGE_GUILD",
+    guild_only
+)]
+pub async fn test(
+    ctx: Context<'_>,
+    #[description = "The text to test, or the message's content"] text: Option<String>,
+    #[description = "A link to a message to test"] message: Option<String>,
+    #[description = "Read the images of the linked message too"] ocr: Option<bool>,
+    #[description = "The channel to test in"] channel: Option<GenericChannelId>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let data = ctx.data();
+    let ocr = ocr.unwrap_or(false);
+
+    let message = match message {
+        Some(link) => {
+            let Some((link_guild, channel_id, message_id)) = parse_message_link(&link) else {
+                ctx.say("That isn't a message link.").await?;
+                return Ok(());
+            };
+            if link_guild != guild_id {
+                ctx.say("That message isn't in this server.").await?;
+                return Ok(());
+            }
+            if !can_read_channel(ctx, channel_id).await {
+                ctx.say("You can't read the messages of that channel.")
+                    .await?;
+                return Ok(());
+            }
+
+            match channel_id.message(ctx, message_id).await {
+                Ok(message) => Some(message),
+                Err(_) => {
+                    ctx.say("I couldn't find that message.").await?;
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+
+    if ocr && message.is_none() {
+        ctx.say("OCR needs a message link to read the images of.")
+            .await?;
+        return Ok(());
+    }
+
+    let Some(content) = text.or_else(|| {
+        message
+            .as_ref()
+            .map(|m| extract_message_content(m).into_owned())
+    }) else {
+        ctx.say("Give me some text or a message link to test.")
+            .await?;
+        return Ok(());
+    };
+
+    let channel_id = channel
+        .or_else(|| message.as_ref().map(|m| m.channel_id))
+        .unwrap_or_else(|| ctx.channel_id());
+
+    let Some(regexes) = data.database.get_responses_regexes(guild_id).await? else {
+        ctx.say("This server has no responses.").await?;
+        return Ok(());
+    };
+
+    let location = locate(ctx.serenity_context(), guild_id, channel_id);
+    let mut results = scoped(&regexes)
+        .map(|(scope, regex)| (regex, check_scope(scope, regex, &location)))
+        .collect::<Vec<_>>();
+    results.sort_by_key(|(regex, _)| regex.id);
+
+    if results.is_empty() {
+        ctx.say("This server has no responses.").await?;
+        return Ok(());
+    }
+
+    let guild_language = data.database.get_ocr_language(guild_id);
+    let ocr_language = |regex: &RegexData| {
+        regex
+            .ocr_language
+            .clone()
+            .unwrap_or_else(|| guild_language.clone())
+    };
+
+    let ocr_texts = match &message {
+        Some(message) if ocr => {
+            ctx.defer().await?;
+
+            // content only regexes too, so it can say when one would have matched an image.
+            let languages = results
+                .iter()
+                .filter(|(_, skip)| skip.is_ok())
+                .map(|(regex, _)| ocr_language(regex))
+                .collect::<HashSet<_>>();
+
+            MessageFrames::new(&data, message)
+                .read(&languages, OcrPriority::High)
+                .await
+        }
+        _ => Vec::new(),
+    };
+
+    let mut description = format!("Tested in <#{channel_id}>");
+    if let Some(parent) = location.thread_parent {
+        write!(description, ", a thread of <#{parent}>").unwrap();
+    }
+    description.push_str("\n\n");
+    for (regex, skip) in &results {
+        let result = if let Err(skip) = skip {
+            format!("skipped, {skip}")
+        } else {
+            let reads_content = regex.detection_type.contains(DetectionType::CONTENT);
+            let reads_ocr = regex.detection_type.contains(DetectionType::OCR);
+
+            let content_match = regex
+                .pattern
+                .find(&content)
+                .map(|m| describe_match("content", &content, m.range()));
+
+            let language = ocr_language(regex);
+            let ocr_match = ocr_texts
+                .iter()
+                .filter(|(l, _)| *l == language)
+                .find_map(|(_, text)| regex.pattern.find(text).map(|m| (text, m.range())))
+                .map(|(text, span)| describe_match(&format!("OCR ({language})"), text, span));
+
+            match (content_match, ocr_match) {
+                (Some(matched), _) if reads_content => matched,
+                (_, Some(matched)) if reads_ocr => matched,
+                (Some(_), _) => String::from("skipped, detection type excludes content"),
+                (_, Some(_)) => String::from("skipped, detection type excludes OCR"),
+                _ if reads_ocr && !ocr && reads_content => String::from("no match, OCR wasn't run"),
+                _ if reads_ocr && !ocr => {
+                    String::from("skipped, only reads images and OCR wasn't run")
+                }
+                _ => String::from("no match"),
+            }
+        };
+
+        let line = format!("**#{}** {result}\n", regex.id);
+        if description.len() + line.len() > 4000 {
+            description.push('…');
+            break;
+        }
+        description.push_str(&line);
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Response test")
+        .description(description)
+        .colour(serenity::Colour::BLUE);
+    ctx.send(lumi::CreateReply::new().embed(embed)).await?;
+
+    Ok(())
+}
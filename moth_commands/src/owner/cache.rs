@@ -210,12 +210,78 @@ pub async fn invalidate_response_cache(ctx: crate::PrefixContext<'_>) -> Result<
     Ok(())
 }
 
+/// Show how often OCR is skipped thanks to the cache.
+#[lumi::command(
+    rename = "ocr-cache",
+    prefix_command,
+    category = "Owner - Cache",
+    hide_in_help,
+    check = "owner",
+    subcommands("ocr_cache_purge")
+)]
+pub async fn ocr_cache(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    let stats = data.ocr_engine.cache_stats();
+    let stored = data.database.count_cached_ocr().await?;
+
+    let embed = serenity::CreateEmbed::default()
+        .title("OCR Cache")
+        .field(
+            "Memory",
+            format!(
+                "Entries: **{}/{}**\nHits: **{}**",
+                stats.memory_entries, stats.memory_capacity, stats.memory_hits
+            ),
+            true,
+        )
+        .field(
+            "Database",
+            format!("Entries: **{stored}**\nHits: **{}**", stats.database_hits),
+            true,
+        )
+        .field(
+            "Overall",
+            format!(
+                "Misses: **{}**\nHit rate: **{:.1}%**",
+                stats.misses,
+                stats.hit_rate() * 100.0
+            ),
+            true,
+        );
+
+    ctx.send(lumi::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// Forget all cached OCR text, in memory and in the database.
+#[lumi::command(
+    rename = "purge",
+    prefix_command,
+    category = "Owner - Cache",
+    hide_in_help,
+    check = "owner"
+)]
+pub async fn ocr_cache_purge(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+    data.ocr_engine.clear_cache();
+    let removed = data.database.purge_cached_ocr().await?;
+
+    ctx.say(format!(
+        "Purged the OCR cache, **{removed}** stored entries removed."
+    ))
+    .await?;
+
+    Ok(())
+}
+
 #[must_use]
-pub fn commands() -> [crate::Command; 4] {
+pub fn commands() -> [crate::Command; 5] {
     [
         max_messages(),
         guild_cache_stats(),
         guild_user_cache(),
         invalidate_response_cache(),
+        ocr_cache(),
     ]
 }
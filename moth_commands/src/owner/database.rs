@@ -1,5 +1,6 @@
 use crate::{owner::owner, Context, Error};
 use moth_ansi::RESET;
+use moth_core::data::migrations::{migration_status, MigrationState};
 use lumi::serenity_prelude::{self as serenity, CreateEmbedFooter};
 use sqlx::{query, Pool, Postgres, Row};
 use std::fmt::Write;
@@ -126,7 +127,72 @@ pub async fn sql(
     Ok(())
 }
 
+#[lumi::command(
+    prefix_command,
+    category = "Owner - Database",
+    check = "owner",
+    hide_in_help,
+    subcommands("migrations"),
+    subcommand_required
+)]
+pub async fn db(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Lists applied and pending migrations along with their checksums.
+#[lumi::command(prefix_command, category = "Owner - Database", check = "owner", hide_in_help)]
+pub async fn migrations(ctx: Context<'_>) -> Result<(), Error> {
+    let statuses = migration_status(&ctx.data().database.db).await?;
+
+    let mut description = String::from("```\n");
+    for status in &statuses {
+        writeln!(
+            description,
+            "{:<8} {} {} {}",
+            status.state,
+            status.version,
+            // the full sha384 is too long to be useful here.
+            &status.checksum[..status.checksum.len().min(16)],
+            status.description
+        )
+        .unwrap();
+    }
+    description.push_str("```");
+
+    let applied = statuses
+        .iter()
+        .filter(|m| m.state == MigrationState::Applied)
+        .count();
+    let pending = statuses
+        .iter()
+        .filter(|m| m.state == MigrationState::Pending)
+        .count();
+
+    let colour = if statuses
+        .iter()
+        .any(|m| matches!(m.state, MigrationState::Modified | MigrationState::Unknown))
+    {
+        serenity::Colour::RED
+    } else if pending == 0 {
+        serenity::Colour::DARK_GREEN
+    } else {
+        serenity::Colour::GOLD
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title("Migrations")
+        .description(description)
+        .colour(colour)
+        .footer(CreateEmbedFooter::new(format!(
+            "{applied} applied • {pending} pending"
+        )));
+
+    ctx.send(lumi::CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
 #[must_use]
-pub fn commands() -> [crate::Command; 2] {
-    [dbstats(), sql()]
+pub fn commands() -> [crate::Command; 3] {
+    [dbstats(), sql(), db()]
 }
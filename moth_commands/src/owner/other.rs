@@ -283,8 +283,55 @@ async fn sudo(
     guild_only
 )]
 async fn analyze(ctx: Context<'_>, #[rest] msg: String) -> Result<(), Error> {
-    let kind = format!("{:?}", moth_filter::analyze(&msg));
-    ctx.say(kind).await?;
+    let data = ctx.data();
+    let guild_filter = data.database.get_filter(ctx.guild_id().unwrap());
+
+    let mut response = String::new();
+    {
+        let config = &data.config.read().events;
+        let verdict = moth_filter::check(
+            &msg,
+            moth_filter::Lists {
+                badlist: &config.badlist,
+                fixlist: &config.fixlist,
+                leet: &config.leet,
+                guild: guild_filter.as_deref(),
+            },
+        );
+
+        writeln!(response, "**Analysis:** `{:?}`", verdict.kind).unwrap();
+        let tiers = verdict.tiers();
+        if !tiers.is_empty() {
+            let tiers = tiers.iter().map(|t| t.name()).collect::<Vec<_>>();
+            writeln!(response, "**Tiers:** {}", tiers.join(", ")).unwrap();
+        }
+
+        for span in &verdict.spans {
+            let mut line = format!(
+                "`{}..{}` `{}`: {}, {}",
+                span.range.start,
+                span.range.end,
+                &msg[span.range.clone()],
+                span.source,
+                span.severity.map_or("no severity", |s| s.name())
+            );
+            if let Some(fix) = &span.fixlist {
+                if span.flagged() {
+                    write!(line, ", flagged despite the fixlist (`{fix}`)").unwrap();
+                } else {
+                    write!(line, ", unflagged by the fixlist (`{fix}`)").unwrap();
+                }
+            }
+
+            if response.len() + line.len() > 1900 {
+                response.push_str("…");
+                break;
+            }
+            writeln!(response, "{line}").unwrap();
+        }
+    }
+
+    ctx.say(response).await?;
     Ok(())
 }
 
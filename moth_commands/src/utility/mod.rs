@@ -1,6 +1,7 @@
 pub mod charinfo;
 pub mod expressions;
 pub mod guild;
+pub mod ocr;
 pub mod random;
 pub mod users;
 
@@ -13,6 +14,7 @@ pub fn commands() -> Vec<crate::Command> {
             .chain(users::commands())
             .chain(guild::commands())
             .chain(charinfo::commands())
+            .chain(ocr::commands())
             .collect()
     }
 }
@@ -0,0 +1,233 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use crate::{Context, Error};
+use lumi::CreateReply;
+use moth_core::ocr::{is_discord_cdn, is_valid_language, OcrPriority, DEFAULT_LANGUAGE};
+use moth_events::ocr::{download_images, message_images, ImageSource, MessageImage};
+use serenity::all::{
+    Attachment, ButtonStyle, ComponentInteractionCollector, CreateActionRow, CreateButton,
+    CreateComponent, CreateEmbed, CreateEmbedFooter, Message,
+};
+
+/// Languages offered to re-run with, as buttons under the result.
+const RERUN_LANGUAGES: [&str; 6] = ["eng", "deu", "fra", "spa", "rus", "jpn"];
+
+/// Characters shared by the fields of the result, what discord allows in an embed minus room
+/// for the title and footer.
+const FIELDS_LENGTH: usize = 5800;
+
+/// Fields discord allows in an embed.
+const MAX_FIELDS: usize = 25;
+
+/// Read the text in an image.
+#[lumi::command(
+    slash_command,
+    prefix_command,
+    category = "Utility",
+    install_context = "Guild|User",
+    interaction_context = "Guild|BotDm|PrivateChannel"
+)]
+pub async fn ocr(
+    ctx: Context<'_>,
+    #[description = "The image to read"] image: Option<Attachment>,
+    #[description = "A link to the image to read"] url: Option<String>,
+    #[description = "A Tesseract language code like eng, deu or eng+jpn"] language: Option<String>,
+) -> Result<(), Error> {
+    let mut images = Vec::new();
+    if let Some(image) = image {
+        images.push(MessageImage {
+            url: image.url.to_string(),
+            source: ImageSource::Attachment,
+            size: Some(image.size),
+        });
+    }
+
+    if let Some(link) = url {
+        if !is_discord_cdn(&link) {
+            ctx.say("Only links to images uploaded to Discord can be read.")
+                .await?;
+            return Ok(());
+        }
+
+        images.push(MessageImage {
+            url: link,
+            source: ImageSource::Link,
+            size: None,
+        });
+    }
+
+    if images.is_empty() {
+        ctx.say("Give me an image or a link to one.").await?;
+        return Ok(());
+    }
+
+    extract(ctx, images, language).await
+}
+
+/// Read the text in the images of a message.
+#[lumi::command(
+    context_menu_command = "Extract text",
+    category = "Utility",
+    install_context = "Guild|User",
+    interaction_context = "Guild|BotDm|PrivateChannel"
+)]
+pub async fn extract_text(ctx: Context<'_>, message: Message) -> Result<(), Error> {
+    let images = message_images(&message);
+    if images.is_empty() {
+        ctx.say("That message has no images I can read.").await?;
+        return Ok(());
+    }
+
+    extract(ctx, images, None).await
+}
+
+fn truncate(text: &str, max: usize) -> Cow<'_, str> {
+    if text.chars().count() <= max {
+        return Cow::Borrowed(text);
+    }
+
+    let mut truncated = text.chars().take(max - 1).collect::<String>();
+    truncated.push('…');
+    Cow::Owned(truncated)
+}
+
+/// Reads every frame in `language`, as an embed with a field per image that splits the space
+/// discord allows between them.
+async fn read_frames(
+    ctx: Context<'_>,
+    frames: &[(ImageSource, Vec<u8>)],
+    language: &str,
+) -> CreateEmbed<'static> {
+    let data = ctx.data();
+
+    let mut embed = CreateEmbed::new()
+        .title("Extracted text")
+        .footer(CreateEmbedFooter::new(format!("Language: {language}")));
+
+    let frames = &frames[..frames.len().min(MAX_FIELDS)];
+    // leaves room for the name, code block and confidence of each field.
+    let max_text = (FIELDS_LENGTH / frames.len().max(1))
+        .min(1024)
+        .saturating_sub(64)
+        .max(1);
+
+    for (i, (source, frame)) in frames.iter().enumerate() {
+        let name = format!("Image {} ({source})", i + 1);
+
+        let value = match data
+            .ocr_engine
+            .process_with_confidence(frame.clone(), language, OcrPriority::High)
+            .await
+        {
+            Ok(ocr) if ocr.text.trim().is_empty() => String::from("No text found."),
+            Ok(ocr) => {
+                let text = ocr.text.trim().replace("```", "`\u{200b}``");
+                let mut value = format!("```\n{}\n```", truncate(&text, max_text));
+                if let Some(confidence) = ocr.confidence {
+                    write!(value, "Confidence: {confidence}%").unwrap();
+                }
+                value
+            }
+            Err(e) => format!("Failed to read: {e}"),
+        };
+
+        embed = embed.field(name, value, false);
+    }
+
+    embed
+}
+
+fn language_buttons(ctx_id: u64, current: &str) -> Vec<CreateComponent<'static>> {
+    let buttons = RERUN_LANGUAGES
+        .iter()
+        .filter(|l| **l != current)
+        .take(5)
+        .map(|l| {
+            CreateButton::new(format!("{ctx_id}ocr_{l}"))
+                .label(*l)
+                .style(ButtonStyle::Secondary)
+        })
+        .collect::<Vec<_>>();
+
+    vec![CreateComponent::ActionRow(CreateActionRow::Buttons(
+        Cow::Owned(buttons),
+    ))]
+}
+
+async fn extract(
+    ctx: Context<'_>,
+    images: Vec<MessageImage>,
+    language: Option<String>,
+) -> Result<(), Error> {
+    let mut language = match language {
+        Some(language) => language.to_lowercase(),
+        None => match ctx.guild_id() {
+            Some(guild_id) => ctx.data().database.get_ocr_language(guild_id),
+            None => DEFAULT_LANGUAGE.to_owned(),
+        },
+    };
+
+    if !is_valid_language(&language) {
+        ctx.say("That isn't a Tesseract language code, like `eng`, `chi_sim` or `eng+jpn`.")
+            .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+
+    let data = ctx.data();
+    let frames = download_images(&data, &images, data.ocr_engine.budget).await;
+    if frames.is_empty() {
+        ctx.say("I couldn't download any of the images.").await?;
+        return Ok(());
+    }
+
+    let ctx_id = ctx.id();
+    let mut embed = read_frames(ctx, &frames, &language).await;
+    let msg = ctx
+        .send(
+            CreateReply::new()
+                .embed(embed.clone())
+                .components(language_buttons(ctx_id, &language)),
+        )
+        .await?;
+
+    let prefix = format!("{ctx_id}ocr_");
+    while let Some(press) = ComponentInteractionCollector::new(ctx.serenity_context())
+        .author_id(ctx.author().id)
+        .filter({
+            let prefix = prefix.clone();
+            move |press| press.data.custom_id.starts_with(&prefix)
+        })
+        .timeout(std::time::Duration::from_secs(180))
+        .await
+    {
+        let Some(pressed) = press.data.custom_id.strip_prefix(&prefix) else {
+            continue;
+        };
+        language = pressed.to_owned();
+
+        // reading can take longer than discord waits for a response.
+        press.defer(ctx.http()).await?;
+
+        embed = read_frames(ctx, &frames, &language).await;
+        msg.edit(
+            ctx,
+            CreateReply::new()
+                .embed(embed.clone())
+                .components(language_buttons(ctx_id, &language)),
+        )
+        .await?;
+    }
+
+    msg.edit(ctx, CreateReply::new().embed(embed).components(vec![]))
+        .await?;
+
+    Ok(())
+}
+
+#[must_use]
+pub fn commands() -> [crate::Command; 2] {
+    [ocr(), extract_text()]
+}
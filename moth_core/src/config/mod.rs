@@ -4,10 +4,20 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use lumi::serenity_prelude::{ChannelId, GuildId};
+use moth_filter::{LeetMap, normalize_entry};
 
 mod serialize;
 use serialize::{read_words_from_file, regex_patterns};
 
+/// Reads a word list, normalising every entry the way message text is so it can be matched
+/// against it.
+pub fn read_list(path: &str, leet: &LeetMap) -> std::io::Result<HashSet<String>> {
+    Ok(read_words_from_file(path)?
+        .iter()
+        .filter_map(|word| normalize_entry(word, leet))
+        .collect())
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
 pub struct MothConfig {
     // configuration for the event handler.
@@ -53,8 +63,13 @@ impl MothConfig {
         if let Ok(config_file) = config_result {
             if let Ok(mut config) = serde_json::from_str::<MothConfig>(&config_file) {
                 // Set value of unconfigurable properties.
-                config.events.badlist = read_words_from_file("config/lists/badwords.txt");
-                config.events.fixlist = read_words_from_file("config/lists/fixwords.txt");
+                // the lists are matched against normalised text, so they are normalised too.
+                let leet = LeetMap::new(config.events.leet_map.clone());
+                config.events.badlist =
+                    read_list("config/lists/badwords.txt", &leet).expect("Failed to read the file");
+                config.events.fixlist =
+                    read_list("config/lists/fixwords.txt", &leet).expect("Failed to read the file");
+                config.events.leet = leet;
 
                 config
             } else {
@@ -92,5 +107,10 @@ pub struct Events {
     pub badlist: HashSet<String>,
     #[serde(skip)]
     pub fixlist: HashSet<String>,
+    /// Replaces the default leet map when set, like `{"4": "a", "3": "e"}`.
+    #[serde(default)]
+    pub leet_map: Option<HashMap<char, char>>,
+    #[serde(skip)]
+    pub leet: LeetMap,
     pub guild_name_override: Option<HashMap<GuildId, String>>,
 }
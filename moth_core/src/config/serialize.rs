@@ -1,15 +1,14 @@
 use std::collections::HashSet;
 
-pub fn read_words_from_file(filename: &str) -> HashSet<String> {
-    std::fs::read_to_string(filename)
-        .expect("Failed to read the file")
+pub fn read_words_from_file(filename: &str) -> std::io::Result<HashSet<String>> {
+    Ok(std::fs::read_to_string(filename)?
         .lines()
         .map(|line| line.trim().to_lowercase())
-        .collect()
+        .collect())
 }
 
 pub mod regex_patterns {
-    use base64::engine::{general_purpose, Engine as _};
+    use base64::engine::{Engine as _, general_purpose};
     use regex::Regex;
     use serde::{Deserialize, Deserializer, Serializer};
 
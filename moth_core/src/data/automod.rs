@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use lumi::serenity_prelude::{GenericChannelId, GuildId, MessageId, UserId};
+use moth_filter::Tier;
+
+/// The longest timeout Discord allows.
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(28 * 24 * 60 * 60);
+
+bitflags::bitflags! {
+    /// What automod does about a message in a tier besides timing out, each on its own.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct AutomodFlags: u32 {
+        /// Delete the message.
+        const DELETE = 0b00000001;
+        /// Tell the user in the channel that their message broke the filter.
+        const WARN   = 0b00000010;
+    }
+}
+
+impl From<u32> for AutomodFlags {
+    fn from(value: u32) -> Self {
+        AutomodFlags::from_bits_truncate(value)
+    }
+}
+
+/// What automod does about a message in a tier.
+///
+/// Every action is independent, and the message is logged whichever are set, so no actions
+/// at all only logs it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AutomodAction {
+    pub flags: AutomodFlags,
+    pub timeout: Option<Duration>,
+}
+
+impl AutomodAction {
+    /// Parses actions joined by `+`, like `delete+warn` or `warn+timeout`, with the duration
+    /// for timeouts. `log` alone takes no action.
+    #[must_use]
+    pub fn parse(names: &str, duration: Option<&str>) -> Option<Self> {
+        let mut action = AutomodAction::default();
+        for name in names.split(['+', ',']) {
+            match name.trim().to_lowercase().as_str() {
+                "log" => {}
+                "delete" => action.flags |= AutomodFlags::DELETE,
+                "warn" => action.flags |= AutomodFlags::WARN,
+                "timeout" => action.timeout = Some(parse_duration(duration?)?),
+                _ => return None,
+            }
+        }
+
+        Some(action)
+    }
+
+    /// Rebuilds the action from a row's `actions` and `timeout_secs`.
+    #[must_use]
+    pub fn from_row(flags: i32, timeout_secs: Option<i32>) -> Self {
+        AutomodAction {
+            flags: (flags as u32).into(),
+            timeout: timeout_secs
+                .and_then(|secs| u64::try_from(secs).ok())
+                .map(Duration::from_secs),
+        }
+    }
+
+    #[must_use]
+    pub fn timeout_secs(self) -> Option<i32> {
+        self.timeout.map(|duration| duration.as_secs() as i32)
+    }
+
+    #[must_use]
+    pub fn deletes(self) -> bool {
+        self.flags.contains(AutomodFlags::DELETE)
+    }
+
+    #[must_use]
+    pub fn warns(self) -> bool {
+        self.flags.contains(AutomodFlags::WARN)
+    }
+
+    /// Orders actions by how much they do to the user, for picking one when a message falls
+    /// into several tiers.
+    #[must_use]
+    pub fn strength(self) -> (Option<Duration>, bool, bool) {
+        (self.timeout, self.warns(), self.deletes())
+    }
+}
+
+impl std::fmt::Display for AutomodAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut actions = Vec::new();
+        if self.deletes() {
+            actions.push(String::from("delete"));
+        }
+        if self.warns() {
+            actions.push(String::from("warn"));
+        }
+        if let Some(duration) = self.timeout {
+            actions.push(format!("timeout ({})", format_duration(duration)));
+        }
+
+        if actions.is_empty() {
+            f.write_str("log")
+        } else {
+            f.write_str(&actions.join(" and "))
+        }
+    }
+}
+
+/// Parses durations like `30s`, `10m`, `2h` or `1d`, capped at [`MAX_TIMEOUT`].
+#[must_use]
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let input = input.trim();
+    let split = input.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = input.split_at(split);
+    let amount = amount.parse::<u64>().ok()?;
+
+    let seconds = match unit {
+        "s" => amount,
+        "m" => amount.checked_mul(60)?,
+        "h" => amount.checked_mul(60 * 60)?,
+        "d" => amount.checked_mul(24 * 60 * 60)?,
+        _ => return None,
+    };
+
+    let duration = Duration::from_secs(seconds);
+    (seconds != 0 && duration <= MAX_TIMEOUT).then_some(duration)
+}
+
+#[must_use]
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        s if s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
+/// The action for each tier a guild has opted into.
+pub type AutomodConfig = HashMap<Tier, AutomodAction>;
+
+/// A row of `automod_actions`, what automod did and why.
+#[derive(Clone, Debug)]
+pub struct AutomodRecord {
+    pub guild_id: GuildId,
+    pub channel_id: GenericChannelId,
+    pub message_id: MessageId,
+    pub user_id: UserId,
+    pub tier: Tier,
+    pub action: AutomodAction,
+    pub words: Vec<String>,
+    pub content: String,
+    /// rustrict's analysis, as its debug output.
+    pub analysis: String,
+    pub created_at: i64,
+}
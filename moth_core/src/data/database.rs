@@ -1,15 +1,16 @@
-use ::serenity::{
-    all::{GenericChannelId, MessageId, ReactionType},
-    small_fixed_array,
-};
+use ::serenity::all::{AttachmentId, GenericChannelId, MessageId};
+use dashmap::mapref::entry::Entry;
 use dashmap::{DashMap, DashSet};
 use parking_lot::Mutex;
-use regex::Regex;
 use serenity::all::UserId;
-use sqlx::{Executor, PgPool, postgres::PgPoolOptions, query};
+use sqlx::{
+    Executor, PgPool,
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
 use std::{
     collections::{HashMap, HashSet},
     env,
+    sync::Arc,
 };
 
 use crate::data::structs::{DmActivity, Error};
@@ -18,7 +19,14 @@ use lumi::serenity_prelude as serenity;
 
 use std::ops::Deref;
 
-use super::responses::{GuildCache, RegexData, ResponseCache, ResponseType};
+use super::automod::{AutomodAction, AutomodConfig, AutomodRecord};
+use super::images::{BlockedImage, ImageFilterConfig, Repost};
+use super::modlog::ModlogConfig;
+use super::names::NameFilterActions;
+use super::responses::{GuildCache, RegexData, ResponseCache};
+use super::storage::{MemoryStorage, PgStorage, Storage};
+use crate::ocr::DEFAULT_LANGUAGE;
+use moth_filter::{GuildFilter, LeetMap, Tier, TrieOverride, WordList};
 
 macro_rules! id_wrapper {
     ($wrapper_name:ident, $inner_name:ident) => {
@@ -71,7 +79,8 @@ impl Deref for MaybeMessageIdWrapper {
     }
 }
 
-pub async fn init_data() -> Database {
+/// Connects to `DATABASE_URL`, guild filter entries are normalised with `leet`.
+pub async fn init_data(leet: LeetMap) -> Database {
     let database_url =
         env::var("DATABASE_URL").expect("No database url found in environment variables!");
 
@@ -85,52 +94,17 @@ pub async fn init_data() -> Database {
         .await
         .unwrap();
 
-    sqlx::migrate!("../migrations")
-        .run(&database)
-        .await
-        .expect("Unable to apply migrations!");
-
-    let user_ids = query!("SELECT user_id FROM banned_users")
-        .fetch_all(&database)
-        .await
-        .unwrap();
-
-    let banned_users = user_ids
-        .iter()
-        .map(|r| UserId::new(r.user_id as u64))
-        .collect::<DashSet<UserId>>();
-
-    let db_checks = query!("SELECT * FROM owner_access")
-        .fetch_all(&database)
-        .await
-        .unwrap();
-
-    let checks = Checks::default();
+    let apply_migrations = env::var("MIGRATE_ON_STARTUP")
+        .map(|e| e.parse::<bool>().unwrap())
+        .unwrap_or(false);
 
-    for check in db_checks {
-        if let Some(command_name) = check.command_name {
-            let mut entry = checks
-                .owners_single
-                .entry(command_name)
-                .or_insert_with(HashSet::new);
-            entry.insert(UserId::new(check.user_id as u64));
-        } else {
-            checks.owners_all.insert(UserId::new(check.user_id as u64));
-        }
+    if let Err(e) = super::migrations::run_migrations(&database, apply_migrations).await {
+        panic!("Refusing to start: {e}");
     }
 
-    Database {
-        starboard: Mutex::new(
-            StarboardHandler::new(&database)
-                .await
-                .expect("Database must be avaliable."),
-        ),
-        db: database,
-        owner_overwrites: checks,
-        banned_users,
-        dm_activity: DashMap::new(),
-        responses: ResponseCache::default(),
-    }
+    Database::new(Arc::new(PgStorage::new(database.clone())), database, leet)
+        .await
+        .expect("Database must be avaliable.")
 }
 
 /// Custom type.
@@ -143,7 +117,9 @@ pub enum EmoteUsageType {
 }
 
 pub struct Database {
+    /// Raw pool for queries that don't go through [`Storage`].
     pub db: PgPool,
+    storage: Arc<dyn Storage>,
     banned_users: DashSet<UserId>,
     owner_overwrites: Checks,
     // TODO: return privacy
@@ -154,8 +130,39 @@ pub struct Database {
 
     /// caches for regex autoresponse stuff.
     pub(crate) responses: ResponseCache,
+
+    /// Where each guild mirrors its events to.
+    modlog: DashMap<serenity::GuildId, ModlogConfig>,
+
+    /// Guilds with their own filter entries, checked on top of the global lists.
+    filters: DashMap<serenity::GuildId, Arc<GuildFilter>>,
+    /// What the filter entries are normalised with, the same map messages are.
+    leet: LeetMap,
+
+    /// Guilds that opted into automod, with the action for each tier.
+    automod: DashMap<serenity::GuildId, AutomodConfig>,
+
+    /// Guilds that check member names and statuses, with what to do about flagged ones.
+    name_filter: DashMap<serenity::GuildId, NameFilterActions>,
+
+    /// Guilds that OCR in something other than [`DEFAULT_LANGUAGE`].
+    ocr_languages: DashMap<serenity::GuildId, String>,
+
+    /// Guilds that run the content filter over text read from images.
+    ocr_filter: DashSet<serenity::GuildId>,
+
+    /// Guilds hashing their image attachments.
+    image_filter: DashMap<serenity::GuildId, ImageFilterConfig>,
+    image_blocklist: DashMap<serenity::GuildId, Vec<BlockedImage>>,
 }
 
+/// A guild's badlist, fixlist and trie overrides, before being built into a [`GuildFilter`].
+type FilterEntries = (
+    HashSet<String>,
+    HashSet<String>,
+    std::collections::BTreeMap<String, TrieOverride>,
+);
+
 #[derive(Debug)]
 pub struct StarboardHandler {
     messages: Vec<StarboardMessage>,
@@ -167,18 +174,12 @@ pub struct StarboardHandler {
 }
 
 impl StarboardHandler {
-    async fn new(db: &PgPool) -> Result<Self, Error> {
-        let results = sqlx::query!("SELECT * FROM starboard_overrides")
-            .fetch_all(db)
-            .await?;
-
-        let mut overrides = HashMap::with_capacity(results.len());
-        for result in results {
-            overrides.insert(
-                GenericChannelId::new(result.channel_id as u64),
-                result.star_count as u8,
-            );
-        }
+    async fn new(storage: &dyn Storage) -> Result<Self, Error> {
+        let overrides = storage
+            .get_starboard_overrides()
+            .await?
+            .into_iter()
+            .collect();
 
         Ok(Self {
             overrides,
@@ -223,17 +224,136 @@ pub enum StarboardStatus {
 }
 
 impl Database {
-    pub async fn insert_user(&self, user_id: serenity::UserId) -> Result<(), Error> {
-        query!(
-            "INSERT INTO users (user_id)
-            VALUES ($1)
-            ON CONFLICT (user_id) DO NOTHING",
-            user_id.get() as i64
-        )
-        .execute(&self.db)
-        .await?;
+    /// Builds the database wrapper, loading the bans, owner access and starboard overrides from
+    /// `storage` into the caches.
+    ///
+    /// `db` is only used by the queries that don't go through [`Storage`].
+    pub async fn new(storage: Arc<dyn Storage>, db: PgPool, leet: LeetMap) -> Result<Self, Error> {
+        let banned_users = storage
+            .get_banned_users()
+            .await?
+            .into_iter()
+            .collect::<DashSet<UserId>>();
+
+        let checks = Checks::default();
+
+        for access in storage.get_owner_access().await? {
+            if let Some(command_name) = access.command {
+                let mut entry = checks
+                    .owners_single
+                    .entry(command_name)
+                    .or_insert_with(HashSet::new);
+                entry.insert(access.user_id);
+            } else {
+                checks.owners_all.insert(access.user_id);
+            }
+        }
 
-        Ok(())
+        let modlog = storage
+            .get_modlog_configs()
+            .await?
+            .into_iter()
+            .collect::<DashMap<_, _>>();
+
+        let mut filter_entries: HashMap<serenity::GuildId, FilterEntries> = HashMap::new();
+        for (guild_id, list, word) in storage.get_filter_words().await? {
+            let entries = filter_entries.entry(guild_id).or_default();
+            match list {
+                WordList::Bad => entries.0.insert(word),
+                WordList::Fix => entries.1.insert(word),
+            };
+        }
+        for (guild_id, word, kind) in storage.get_filter_overrides().await? {
+            filter_entries
+                .entry(guild_id)
+                .or_default()
+                .2
+                .insert(word, kind);
+        }
+
+        let filters = filter_entries
+            .into_iter()
+            .map(|(guild_id, (bad, fix, overrides))| {
+                (
+                    guild_id,
+                    Arc::new(GuildFilter::new(bad, fix, overrides, &leet)),
+                )
+            })
+            .collect();
+
+        let automod: DashMap<serenity::GuildId, AutomodConfig> = DashMap::new();
+        for (guild_id, tier, action) in storage.get_automod_tiers().await? {
+            automod.entry(guild_id).or_default().insert(tier, action);
+        }
+
+        let name_filter = storage
+            .get_name_filters()
+            .await?
+            .into_iter()
+            .collect::<DashMap<_, _>>();
+
+        let ocr_languages = storage
+            .get_ocr_languages()
+            .await?
+            .into_iter()
+            .collect::<DashMap<_, _>>();
+
+        let ocr_filter = storage
+            .get_ocr_filters()
+            .await?
+            .into_iter()
+            .collect::<DashSet<_>>();
+
+        let image_filter = storage
+            .get_image_filters()
+            .await?
+            .into_iter()
+            .collect::<DashMap<_, _>>();
+
+        let image_blocklist: DashMap<serenity::GuildId, Vec<BlockedImage>> = DashMap::new();
+        for (guild_id, image) in storage.get_image_blocklist().await? {
+            image_blocklist.entry(guild_id).or_default().push(image);
+        }
+
+        Ok(Database {
+            modlog,
+            filters,
+            automod,
+            name_filter,
+            ocr_languages,
+            ocr_filter,
+            image_filter,
+            image_blocklist,
+            starboard: Mutex::new(StarboardHandler::new(&*storage).await?),
+            db,
+            storage,
+            owner_overwrites: checks,
+            banned_users,
+            dm_activity: DashMap::new(),
+            responses: ResponseCache::default(),
+            leet,
+        })
+    }
+
+    /// Builds a database backed by [`MemoryStorage`].
+    ///
+    /// The pool is lazy and never connects, so anything that uses [`Database::db`] directly
+    /// will error. Filter entries are normalised with the default [`LeetMap`].
+    pub async fn in_memory(storage: Arc<MemoryStorage>) -> Self {
+        let db = PgPoolOptions::new().connect_lazy_with(PgConnectOptions::new());
+
+        Database::new(storage, db, LeetMap::default())
+            .await
+            .expect("Memory storage is infallible when loading.")
+    }
+
+    #[must_use]
+    pub fn storage(&self) -> &dyn Storage {
+        &*self.storage
+    }
+
+    pub async fn insert_user(&self, user_id: serenity::UserId) -> Result<(), Error> {
+        self.storage.insert_user(user_id).await
     }
 
     pub async fn insert_channel(
@@ -241,34 +361,11 @@ impl Database {
         channel_id: serenity::GenericChannelId,
         guild_id: Option<serenity::GuildId>,
     ) -> Result<(), Error> {
-        if let Some(guild_id) = guild_id {
-            self.insert_guild(guild_id).await?;
-        }
-
-        query!(
-            "INSERT INTO channels (channel_id, guild_id)
-             VALUES ($1, $2)
-             ON CONFLICT (channel_id) DO NOTHING",
-            channel_id.get() as i64,
-            guild_id.map(|g| g.get() as i64),
-        )
-        .execute(&self.db)
-        .await?;
-
-        Ok(())
+        self.storage.insert_channel(channel_id, guild_id).await
     }
 
     pub async fn insert_guild(&self, guild_id: serenity::GuildId) -> Result<(), Error> {
-        query!(
-            "INSERT INTO guilds (guild_id)
-             VALUES ($1)
-             ON CONFLICT (guild_id) DO NOTHING",
-            guild_id.get() as i64
-        )
-        .execute(&self.db)
-        .await?;
-
-        Ok(())
+        self.storage.insert_guild(guild_id).await
     }
 
     /// Checks if a user is banned from using commands.
@@ -291,22 +388,10 @@ impl Database {
 
         if banned {
             self.banned_users.insert(user_id);
-            self.insert_user(user_id).await?;
-            query!(
-                "INSERT INTO banned_users (user_id) VALUES ($1)",
-                user_id.get() as i64
-            )
-            .execute(&self.db)
-            .await?;
         } else {
             self.banned_users.remove(&user_id);
-            query!(
-                "DELETE FROM banned_users WHERE user_id = $1",
-                user_id.get() as i64
-            )
-            .execute(&self.db)
-            .await?;
         }
+        self.storage.set_banned(user_id, banned).await?;
 
         Ok(old_status)
     }
@@ -335,13 +420,7 @@ impl Database {
                 return Ok(true);
             }
 
-            self.insert_user(user_id).await?;
-            query!(
-                "INSERT INTO owner_access (user_id) VALUES ($1)",
-                user_id.get() as i64
-            )
-            .execute(&self.db)
-            .await?;
+            self.storage.insert_owner_access(user_id, None).await?;
 
             self.owner_overwrites.owners_all.insert(user_id);
             return Ok(false);
@@ -355,14 +434,9 @@ impl Database {
             }
         }
 
-        self.insert_user(user_id).await?;
-        query!(
-            "INSERT INTO owner_access (user_id, command_name) VALUES ($1, $2)",
-            user_id.get() as i64,
-            command
-        )
-        .execute(&self.db)
-        .await?;
+        self.storage
+            .insert_owner_access(user_id, Some(command))
+            .await?;
 
         self.owner_overwrites
             .owners_single
@@ -383,12 +457,7 @@ impl Database {
             if !self.owner_overwrites.owners_all.contains(&user_id) {
                 return Ok(false);
             }
-            query!(
-                "DELETE FROM owner_access WHERE user_id = $1",
-                user_id.get() as i64
-            )
-            .execute(&self.db)
-            .await?;
+            self.storage.remove_owner_access(user_id, None).await?;
             self.owner_overwrites.owners_all.remove(&user_id);
 
             return Ok(true);
@@ -406,13 +475,9 @@ impl Database {
             return Ok(false);
         }
 
-        query!(
-            "DELETE FROM owner_access WHERE user_id = $1 AND command_name = $2",
-            user_id.get() as i64,
-            command
-        )
-        .execute(&self.db)
-        .await?;
+        self.storage
+            .remove_owner_access(user_id, Some(command))
+            .await?;
 
         let mut should_remove_entry = false;
         if let Some(mut cmd_cache) = self.owner_overwrites.owners_single.get_mut(command) {
@@ -442,25 +507,14 @@ impl Database {
             return Ok(starboard);
         }
 
-        let starboard = self.get_starboard_msg_(msg_id).await?;
+        let starboard = self.storage.get_starboard(msg_id).await?;
 
         self.starboard.lock().messages.push(starboard.clone());
 
         Ok(starboard)
     }
 
-    async fn get_starboard_msg_(&self, msg_id: MessageId) -> Result<StarboardMessage, sqlx::Error> {
-        sqlx::query_as!(StarboardMessage,
-        r#"
-        SELECT id, user_id, username, avatar_url, content, channel_id, message_id, attachment_urls, star_count, starboard_message_id, starboard_message_channel, starboard_status as "starboard_status: StarboardStatus", reply_message_id, forwarded, reply_username
-        FROM starboard
-        WHERE message_id = $1
-        "#, msg_id.get() as i64)
-            .fetch_one(&self.db)
-            .await
-    }
-
-    pub async fn update_star_count(&self, id: i32, count: i16) -> Result<(), sqlx::Error> {
+    pub async fn update_star_count(&self, id: i32, count: i16) -> Result<(), Error> {
         {
             let mut starboard = self.starboard.lock();
             let entry = starboard.messages.iter_mut().find(|s| s.id == id);
@@ -469,15 +523,7 @@ impl Database {
             }
         };
 
-        query!(
-            "UPDATE starboard SET star_count = $1 WHERE id = $2",
-            count,
-            id,
-        )
-        .execute(&self.db)
-        .await?;
-
-        Ok(())
+        self.storage.update_star_count(id, count).await
     }
 
     pub async fn get_responses_regexes(
@@ -488,92 +534,26 @@ impl Database {
             return Ok(Some(cache.clone()));
         }
 
-        let all_data = sqlx::query!(
-            "SELECT
-                r.id AS regex_id, r.channel_id, r.pattern,
-                r.recurse_channels, r.recurse_threads, r.detection_type,
-                resp.message, resp.emote_id
-            FROM regexes r
-            LEFT JOIN responses resp ON r.id = resp.regex_id
-            WHERE r.guild_id = $1",
-            guild_id.get() as i64
-        )
-        .fetch_all(&self.db)
-        .await?;
-
         let mut guild_cache = GuildCache::default();
-        let mut emoji_cache: HashMap<i32, ReactionType> = HashMap::new();
-
-        for record in &all_data {
-            let exceptions = sqlx::query!(
-                "SELECT channel_id FROM regex_exceptions WHERE regex_id = $1",
-                record.regex_id
-            )
-            .fetch_all(&self.db)
-            .await
-            .unwrap();
-
-            let exception_channels = exceptions
-                .into_iter()
-                .map(|row| GenericChannelId::new(row.channel_id as u64))
-                .collect::<HashSet<_>>();
-
-            let response = if let Some(message) = &record.message {
-                ResponseType::Message(message.clone())
-            } else if let Some(emote_id) = record.emote_id {
-                let reaction = if let Some(cached_reaction) = emoji_cache.get(&emote_id) {
-                    cached_reaction.clone()
-                } else {
-                    let emote = sqlx::query!(
-                        "SELECT emote_name, discord_id FROM emotes WHERE id = $1",
-                        emote_id
-                    )
-                    .fetch_one(&self.db)
-                    .await
-                    .unwrap();
-
-                    let reaction = if let Some(discord_id) = emote.discord_id {
-                        ReactionType::Custom {
-                            animated: false,
-                            id: serenity::EmojiId::new(discord_id as u64),
-                            name: Some(small_fixed_array::FixedString::from_static_trunc("_")),
-                        }
-                    } else {
-                        ReactionType::Unicode(
-                            serenity::small_fixed_array::FixedString::from_string_trunc(
-                                emote.emote_name,
-                            ),
-                        )
-                    };
-
-                    emoji_cache.insert(emote_id, reaction.clone());
-
-                    reaction
-                };
-
-                ResponseType::Emoji(reaction)
-            } else {
-                ResponseType::Message("Default response".to_string())
-            };
-
-            let regex_data = RegexData {
-                id: record.regex_id,
-                pattern: Regex::new(&record.pattern).unwrap(),
-                recurse_channels: record.recurse_channels,
-                recurse_threads: record.recurse_threads,
-                response,
-                exceptions: exception_channels,
-                detection_type: (record.detection_type as u8).into(),
-            };
-
-            if let Some(channel_id) = record.channel_id {
+        for (channel_id, regex, suppressed) in self.storage.get_regexes(guild_id).await? {
+            // counts that haven't been flushed yet aren't in the database.
+            let pending = self
+                .responses
+                .pending_suppressed
+                .get(&regex.id)
+                .map_or(0, |c| *c);
+            self.responses
+                .suppressed
+                .insert(regex.id, suppressed + pending);
+
+            if let Some(channel_id) = channel_id {
                 guild_cache
                     .channel
-                    .entry(GenericChannelId::new(channel_id as u64))
+                    .entry(channel_id)
                     .or_insert_with(Vec::new)
-                    .push(regex_data);
+                    .push(regex);
             } else {
-                guild_cache.global.push(regex_data);
+                guild_cache.global.push(regex);
             }
         }
 
@@ -583,6 +563,138 @@ impl Database {
         Ok(self.responses.guild.get(&guild_id).map(|g| g.clone()))
     }
 
+    /// A regex response of the guild by id, with the channel or category it's scoped to.
+    pub async fn get_regex(
+        &self,
+        guild_id: serenity::GuildId,
+        id: i32,
+    ) -> Result<Option<(Option<GenericChannelId>, RegexData)>, Error> {
+        let Some(cache) = self.get_responses_regexes(guild_id).await? else {
+            return Ok(None);
+        };
+
+        if let Some(regex) = cache.global.iter().find(|r| r.id == id) {
+            return Ok(Some((None, regex.clone())));
+        }
+
+        Ok(cache.channel.iter().find_map(|(channel_id, regexes)| {
+            regexes
+                .iter()
+                .find(|r| r.id == id)
+                .map(|r| (Some(*channel_id), r.clone()))
+        }))
+    }
+
+    /// Saves a new regex response, returning its id.
+    pub async fn add_regex(
+        &self,
+        guild_id: serenity::GuildId,
+        channel_id: Option<GenericChannelId>,
+        mut regex: RegexData,
+    ) -> Result<i32, Error> {
+        self.insert_guild(guild_id).await?;
+        let id = self
+            .storage
+            .insert_regex(guild_id, channel_id, &regex)
+            .await?;
+
+        regex.id = id;
+        self.responses.suppressed.insert(id, 0);
+        self.responses.add_regex(guild_id, channel_id, regex);
+
+        Ok(id)
+    }
+
+    /// Overwrites a regex response with `regex`, keeping its id.
+    pub async fn edit_regex(
+        &self,
+        guild_id: serenity::GuildId,
+        old_channel_id: Option<GenericChannelId>,
+        channel_id: Option<GenericChannelId>,
+        regex: RegexData,
+    ) -> Result<(), Error> {
+        self.storage
+            .update_regex(guild_id, channel_id, &regex)
+            .await?;
+
+        self.responses
+            .replace_regex(guild_id, old_channel_id, channel_id, regex);
+
+        Ok(())
+    }
+
+    /// Returns if the guild had a regex response with this id.
+    pub async fn remove_regex(&self, guild_id: serenity::GuildId, id: i32) -> Result<bool, Error> {
+        let Some(channel_id) = self.storage.delete_regex(guild_id, id).await? else {
+            return Ok(false);
+        };
+
+        self.responses.remove_regex(guild_id, channel_id, id);
+        self.responses.suppressed.remove(&id);
+        self.responses.pending_suppressed.remove(&id);
+
+        Ok(true)
+    }
+
+    /// Records a regex response being sent, returning `false` and counting it as suppressed when
+    /// its cooldown or cap doesn't allow it yet.
+    ///
+    /// Suppressed counts are saved by [`Self::flush_suppressed`].
+    #[must_use]
+    pub fn trigger_regex(
+        &self,
+        regex: &RegexData,
+        channel_id: GenericChannelId,
+        user_id: UserId,
+    ) -> bool {
+        if self.responses.try_trigger(regex, channel_id, user_id) {
+            return true;
+        }
+
+        *self.responses.suppressed.entry(regex.id).or_default() += 1;
+        *self
+            .responses
+            .pending_suppressed
+            .entry(regex.id)
+            .or_default() += 1;
+
+        false
+    }
+
+    /// Saves the suppressed counts recorded since the last flush, keeping them for the next one
+    /// if that fails.
+    pub async fn flush_suppressed(&self) -> Result<(), Error> {
+        let ids = self
+            .responses
+            .pending_suppressed
+            .iter()
+            .map(|c| *c.key())
+            .collect::<Vec<_>>();
+        let counts = ids
+            .into_iter()
+            .filter_map(|id| self.responses.pending_suppressed.remove(&id))
+            .collect::<Vec<_>>();
+
+        if counts.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(e) = self.storage.add_suppressed(&counts).await {
+            for (id, count) in counts {
+                *self.responses.pending_suppressed.entry(id).or_default() += count;
+            }
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// How many matches of a regex response were suppressed by its cooldown.
+    #[must_use]
+    pub fn get_suppressed(&self, id: i32) -> u64 {
+        self.responses.suppressed.get(&id).map_or(0, |c| *c)
+    }
+
     /// Check if a starboard is being handled, and if its not, handle it.
     ///
     /// returns if its already being handled.
@@ -599,7 +711,7 @@ impl Database {
         &self,
         m: StarboardMessage,
         guild_id: Option<serenity::GuildId>,
-    ) -> Result<(), sqlx::Error> {
+    ) -> Result<(), Error> {
         let m_id = *m.message_id;
         let _ = self.insert_starboard_msg_(m, guild_id).await;
         self.stop_handle_starboard(&m_id);
@@ -621,45 +733,15 @@ impl Database {
             .await?;
         self.insert_user(*m.user_id).await?;
 
-        let val = match sqlx::query!(
-            r#"
-                INSERT INTO starboard (
-                    user_id, username, avatar_url, content, channel_id, message_id,
-                    attachment_urls, star_count, starboard_status,
-                    starboard_message_id, starboard_message_channel, forwarded, reply_message_id, reply_username
-                )
-                VALUES (
-                    $1, $2, $3, $4, $5, $6,
-                    $7, $8, $9, $10, $11,
-                    $12, $13, $14
-                ) RETURNING id
-                "#,
-            m.user_id.get() as i64,
-            m.username,
-            m.avatar_url,
-            m.content,
-            m.channel_id.get() as i64,
-            m.message_id.get() as i64,
-            &m.attachment_urls,
-            m.star_count,
-            m.starboard_status as _,
-            m.starboard_message_id.get() as i64,
-            m.starboard_message_channel.get() as i64,
-            m.forwarded,
-            m.reply_message_id.map(|m| m.get() as i64),
-            m.reply_username
-        )
-        .fetch_one(&self.db)
-        .await
-        {
-            Ok(result) => result,
+        let id = match self.storage.insert_starboard(&m).await {
+            Ok(id) => id,
             Err(e) => {
                 println!("SQL query failed: {e:?}");
-                return Err(e.into());
+                return Err(e);
             }
         };
 
-        m.id = val.id;
+        m.id = id;
 
         let mut lock = self.starboard.lock();
         let m_id = *m.message_id;
@@ -686,7 +768,8 @@ impl Database {
         }
 
         let starboard = self
-            .get_starboard_msg_by_starboard_id_(starboard_msg_id)
+            .storage
+            .get_starboard_by_starboard_id(starboard_msg_id)
             .await?;
 
         self.starboard.lock().messages.push(starboard.clone());
@@ -694,38 +777,15 @@ impl Database {
         Ok(starboard)
     }
 
-    async fn get_starboard_msg_by_starboard_id_(
-        &self,
-        starboard_msg_id: MessageId,
-    ) -> Result<StarboardMessage, sqlx::Error> {
-        sqlx::query_as!(StarboardMessage,
-        r#"
-        SELECT id, user_id, username, avatar_url, content, channel_id, message_id, attachment_urls, star_count, starboard_message_id, starboard_message_channel, starboard_status as "starboard_status: StarboardStatus", reply_message_id, forwarded, reply_username
-        FROM starboard
-        WHERE starboard_message_id = $1
-        "#, starboard_msg_id.get() as i64)
-            .fetch_one(&self.db)
-            .await
-    }
-
     pub async fn approve_starboard(
         &self,
         starboard_message_id: MessageId,
         new_message_id: MessageId,
         new_channel_id: GenericChannelId,
     ) -> Result<(), Error> {
-        let status = StarboardStatus::Accepted;
-
-        query!(
-            "UPDATE starboard SET starboard_status = $1, starboard_message_id = $2, \
-             starboard_message_channel = $3 WHERE starboard_message_id = $4",
-            status as _,
-            new_message_id.get() as i64,
-            new_channel_id.get() as i64,
-            starboard_message_id.get() as i64,
-        )
-        .execute(&self.db)
-        .await?;
+        self.storage
+            .approve_starboard(starboard_message_id, new_message_id, new_channel_id)
+            .await?;
 
         let mut lock = self.starboard.lock();
         let m = lock
@@ -743,15 +803,7 @@ impl Database {
     }
 
     pub async fn deny_starboard(&self, starboard_message_id: MessageId) -> Result<(), Error> {
-        let status = StarboardStatus::Denied;
-
-        query!(
-            "UPDATE starboard SET starboard_status = $1 WHERE starboard_message_id = $2",
-            status as _,
-            starboard_message_id.get() as i64,
-        )
-        .execute(&self.db)
-        .await?;
+        self.storage.deny_starboard(starboard_message_id).await?;
 
         let mut lock = self.starboard.lock();
         if let Some(index) = lock
@@ -766,12 +818,7 @@ impl Database {
     }
 
     pub async fn get_all_starboard(&self) -> Result<Vec<StarboardMessage>, Error> {
-        let messages = sqlx::query_as!(StarboardMessage,
-            r#"
-            SELECT id, user_id, username, avatar_url, content, channel_id, message_id, attachment_urls, star_count, starboard_message_id, starboard_message_channel, starboard_status as "starboard_status: StarboardStatus", reply_message_id, forwarded, reply_username
-            FROM starboard"#)
-                .fetch_all(&self.db)
-                .await?;
+        let messages = self.storage.get_all_starboard().await?;
 
         let mut guard = self.starboard.lock();
 
@@ -801,18 +848,9 @@ impl Database {
         )
         .await?;
 
-        sqlx::query!(
-            r#"
-            INSERT INTO starboard_overrides (channel_id, star_count)
-            VALUES ($1, $2)
-            ON CONFLICT (channel_id) DO UPDATE
-            SET star_count = EXCLUDED.star_count
-            "#,
-            channel_id.get() as i64,
-            i16::from(starcount)
-        )
-        .execute(&self.db)
-        .await?;
+        self.storage
+            .set_starboard_override(channel_id, starcount)
+            .await?;
 
         starboard_handler
             .lock()
@@ -827,14 +865,7 @@ impl Database {
         starboard_handler: &Mutex<StarboardHandler>,
         channel_id: GenericChannelId,
     ) -> Result<bool, Error> {
-        let result = sqlx::query!(
-            "DELETE FROM starboard_overrides WHERE channel_id = $1",
-            channel_id.get() as i64
-        )
-        .execute(&self.db)
-        .await?;
-
-        if result.rows_affected() == 0 {
+        if !self.storage.remove_starboard_override(channel_id).await? {
             return Ok(false);
         }
 
@@ -843,6 +874,401 @@ impl Database {
         Ok(true)
     }
 
+    #[must_use]
+    pub fn get_modlog(&self, guild_id: serenity::GuildId) -> Option<ModlogConfig> {
+        self.modlog.get(&guild_id).map(|c| *c)
+    }
+
+    pub async fn set_modlog(
+        &self,
+        guild_id: serenity::GuildId,
+        config: ModlogConfig,
+    ) -> Result<(), Error> {
+        self.storage.set_modlog_config(guild_id, config).await?;
+        self.modlog.insert(guild_id, config);
+
+        Ok(())
+    }
+
+    /// Returns if the guild had a modlog channel.
+    pub async fn remove_modlog(&self, guild_id: serenity::GuildId) -> Result<bool, Error> {
+        let removed = self.storage.remove_modlog_config(guild_id).await?;
+        self.modlog.remove(&guild_id);
+
+        Ok(removed)
+    }
+
+    #[must_use]
+    pub fn get_filter(&self, guild_id: serenity::GuildId) -> Option<Arc<GuildFilter>> {
+        self.filters.get(&guild_id).map(|f| Arc::clone(&f))
+    }
+
+    /// Returns if the word wasn't already on the list.
+    pub async fn add_filter_word(
+        &self,
+        guild_id: serenity::GuildId,
+        list: WordList,
+        word: &str,
+    ) -> Result<bool, Error> {
+        let added = self.storage.add_filter_word(guild_id, list, word).await?;
+        self.rebuild_filter(guild_id, |entries| {
+            match list {
+                WordList::Bad => entries.0.insert(word.to_owned()),
+                WordList::Fix => entries.1.insert(word.to_owned()),
+            };
+        });
+
+        Ok(added)
+    }
+
+    /// Returns if the word was on the list.
+    pub async fn remove_filter_word(
+        &self,
+        guild_id: serenity::GuildId,
+        list: WordList,
+        word: &str,
+    ) -> Result<bool, Error> {
+        let removed = self
+            .storage
+            .remove_filter_word(guild_id, list, word)
+            .await?;
+        self.rebuild_filter(guild_id, |entries| {
+            match list {
+                WordList::Bad => entries.0.remove(word),
+                WordList::Fix => entries.1.remove(word),
+            };
+        });
+
+        Ok(removed)
+    }
+
+    pub async fn set_filter_override(
+        &self,
+        guild_id: serenity::GuildId,
+        word: &str,
+        kind: TrieOverride,
+    ) -> Result<(), Error> {
+        self.storage
+            .set_filter_override(guild_id, word, kind)
+            .await?;
+        self.rebuild_filter(guild_id, |entries| {
+            entries.2.insert(word.to_owned(), kind);
+        });
+
+        Ok(())
+    }
+
+    /// Returns if the word had an override.
+    pub async fn remove_filter_override(
+        &self,
+        guild_id: serenity::GuildId,
+        word: &str,
+    ) -> Result<bool, Error> {
+        let removed = self.storage.remove_filter_override(guild_id, word).await?;
+        self.rebuild_filter(guild_id, |entries| {
+            entries.2.remove(word);
+        });
+
+        Ok(removed)
+    }
+
+    /// The trie has to be rebuilt on every change, so the filter is replaced rather than mutated.
+    ///
+    /// The entry stays locked throughout, so concurrent changes to a guild can't drop each other.
+    fn rebuild_filter(&self, guild_id: serenity::GuildId, change: impl FnOnce(&mut FilterEntries)) {
+        let entry = self.filters.entry(guild_id);
+
+        let mut entries = match &entry {
+            Entry::Occupied(e) => {
+                let f = e.get();
+                (f.badlist.clone(), f.fixlist.clone(), f.overrides().clone())
+            }
+            Entry::Vacant(_) => FilterEntries::default(),
+        };
+
+        change(&mut entries);
+
+        let (bad, fix, overrides) = entries;
+        let filter = GuildFilter::new(bad, fix, overrides, &self.leet);
+        match entry {
+            Entry::Occupied(e) if filter.is_empty() => {
+                e.remove();
+            }
+            Entry::Occupied(mut e) => {
+                e.insert(Arc::new(filter));
+            }
+            Entry::Vacant(e) if !filter.is_empty() => {
+                e.insert(Arc::new(filter));
+            }
+            Entry::Vacant(_) => {}
+        }
+    }
+
+    #[must_use]
+    pub fn get_automod(&self, guild_id: serenity::GuildId) -> Option<AutomodConfig> {
+        self.automod.get(&guild_id).map(|c| c.clone())
+    }
+
+    pub async fn set_automod_tier(
+        &self,
+        guild_id: serenity::GuildId,
+        tier: Tier,
+        action: AutomodAction,
+    ) -> Result<(), Error> {
+        self.storage
+            .set_automod_tier(guild_id, tier, action)
+            .await?;
+        self.automod
+            .entry(guild_id)
+            .or_default()
+            .insert(tier, action);
+
+        Ok(())
+    }
+
+    /// Returns if the tier had an action.
+    pub async fn remove_automod_tier(
+        &self,
+        guild_id: serenity::GuildId,
+        tier: Tier,
+    ) -> Result<bool, Error> {
+        let removed = self.storage.remove_automod_tier(guild_id, tier).await?;
+        self.automod.remove_if_mut(&guild_id, |_, config| {
+            config.remove(&tier);
+            config.is_empty()
+        });
+
+        Ok(removed)
+    }
+
+    /// Returns the id of the record, for looking it up again later.
+    pub async fn insert_automod_record(&self, record: &AutomodRecord) -> Result<i32, Error> {
+        self.storage.insert_automod_record(record).await
+    }
+
+    pub async fn get_automod_record(&self, id: i32) -> Result<Option<AutomodRecord>, Error> {
+        self.storage.get_automod_record(id).await
+    }
+
+    #[must_use]
+    pub fn get_name_filter(&self, guild_id: serenity::GuildId) -> Option<NameFilterActions> {
+        self.name_filter.get(&guild_id).map(|a| *a)
+    }
+
+    /// Disables the name filter when `actions` is empty.
+    pub async fn set_name_filter(
+        &self,
+        guild_id: serenity::GuildId,
+        actions: NameFilterActions,
+    ) -> Result<(), Error> {
+        if actions.is_empty() {
+            self.storage.remove_name_filter(guild_id).await?;
+            self.name_filter.remove(&guild_id);
+        } else {
+            self.storage.set_name_filter(guild_id, actions).await?;
+            self.name_filter.insert(guild_id, actions);
+        }
+
+        Ok(())
+    }
+
+    /// The language the guild's images are read in when a regex doesn't pick one.
+    #[must_use]
+    pub fn get_ocr_language(&self, guild_id: serenity::GuildId) -> String {
+        self.ocr_languages
+            .get(&guild_id)
+            .map_or_else(|| DEFAULT_LANGUAGE.to_owned(), |l| l.clone())
+    }
+
+    pub async fn set_ocr_language(
+        &self,
+        guild_id: serenity::GuildId,
+        language: &str,
+    ) -> Result<(), Error> {
+        self.storage.set_ocr_language(guild_id, language).await?;
+        self.ocr_languages.insert(guild_id, language.to_owned());
+
+        Ok(())
+    }
+
+    /// Returns if the guild had a language set.
+    pub async fn remove_ocr_language(&self, guild_id: serenity::GuildId) -> Result<bool, Error> {
+        let removed = self.storage.remove_ocr_language(guild_id).await?;
+        self.ocr_languages.remove(&guild_id);
+
+        Ok(removed)
+    }
+
+    /// If the guild runs the content filter over text read from images.
+    #[must_use]
+    pub fn get_ocr_filter(&self, guild_id: serenity::GuildId) -> bool {
+        self.ocr_filter.contains(&guild_id)
+    }
+
+    pub async fn set_ocr_filter(
+        &self,
+        guild_id: serenity::GuildId,
+        enabled: bool,
+    ) -> Result<(), Error> {
+        if enabled {
+            self.storage.set_ocr_filter(guild_id).await?;
+            self.ocr_filter.insert(guild_id);
+        } else {
+            self.storage.remove_ocr_filter(guild_id).await?;
+            self.ocr_filter.remove(&guild_id);
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn get_image_filter(&self, guild_id: serenity::GuildId) -> Option<ImageFilterConfig> {
+        self.image_filter.get(&guild_id).map(|c| *c)
+    }
+
+    pub async fn set_image_filter(
+        &self,
+        guild_id: serenity::GuildId,
+        config: ImageFilterConfig,
+    ) -> Result<(), Error> {
+        self.storage.set_image_filter(guild_id, config).await?;
+        self.image_filter.insert(guild_id, config);
+
+        Ok(())
+    }
+
+    /// Returns if the guild had the image filter enabled.
+    pub async fn remove_image_filter(&self, guild_id: serenity::GuildId) -> Result<bool, Error> {
+        let removed = self.storage.remove_image_filter(guild_id).await?;
+        self.image_filter.remove(&guild_id);
+
+        Ok(removed)
+    }
+
+    #[must_use]
+    pub fn get_image_blocklist(&self, guild_id: serenity::GuildId) -> Vec<BlockedImage> {
+        self.image_blocklist
+            .get(&guild_id)
+            .map(|b| b.clone())
+            .unwrap_or_default()
+    }
+
+    /// The closest blocklisted image within `max_distance` of `hash`, with its distance.
+    #[must_use]
+    pub fn match_blocked_image(
+        &self,
+        guild_id: serenity::GuildId,
+        hash: u64,
+        max_distance: u32,
+    ) -> Option<(BlockedImage, u32)> {
+        let blocklist = self.image_blocklist.get(&guild_id)?;
+        blocklist
+            .iter()
+            .map(|image| (image, crate::phash::distance(hash, image.hash)))
+            .filter(|(_, distance)| *distance <= max_distance)
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(image, distance)| (image.clone(), distance))
+    }
+
+    /// Blocks the image, or relabels it if the hash was already blocked, returning its id.
+    pub async fn block_image(
+        &self,
+        guild_id: serenity::GuildId,
+        hash: u64,
+        label: &str,
+        added_by: UserId,
+    ) -> Result<i32, Error> {
+        let mut image = BlockedImage {
+            id: 0,
+            hash,
+            label: label.to_owned(),
+            added_by,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        image.id = self.storage.insert_blocked_image(guild_id, &image).await?;
+
+        let mut blocklist = self.image_blocklist.entry(guild_id).or_default();
+        if let Some(existing) = blocklist.iter_mut().find(|i| i.id == image.id) {
+            existing.label = image.label;
+        } else {
+            blocklist.push(image.clone());
+        }
+
+        Ok(image.id)
+    }
+
+    /// Returns if the image was in the guild's blocklist.
+    pub async fn unblock_image(&self, guild_id: serenity::GuildId, id: i32) -> Result<bool, Error> {
+        let removed = self.storage.remove_blocked_image(guild_id, id).await?;
+        if let Some(mut blocklist) = self.image_blocklist.get_mut(&guild_id) {
+            blocklist.retain(|i| i.id != id);
+        }
+
+        Ok(removed)
+    }
+
+    pub async fn insert_image_hash(
+        &self,
+        attachment_id: AttachmentId,
+        guild_id: serenity::GuildId,
+        hash: u64,
+    ) -> Result<(), Error> {
+        self.storage
+            .insert_image_hash(attachment_id, guild_id, hash)
+            .await
+    }
+
+    /// Logged attachments of the guild within `max_distance` of `hash`, closest first.
+    pub async fn find_reposts(
+        &self,
+        guild_id: serenity::GuildId,
+        hash: u64,
+        max_distance: u32,
+        limit: i64,
+    ) -> Result<Vec<Repost>, Error> {
+        self.storage
+            .find_reposts(guild_id, hash, max_distance, limit)
+            .await
+    }
+
+    pub async fn get_cached_ocr(
+        &self,
+        hash: &[u8],
+        language: &str,
+        engine_version: i32,
+    ) -> Result<Option<String>, Error> {
+        self.storage
+            .get_ocr_cache(hash, language, engine_version)
+            .await
+    }
+
+    pub async fn insert_cached_ocr(
+        &self,
+        hash: &[u8],
+        language: &str,
+        engine_version: i32,
+        text: &str,
+    ) -> Result<(), Error> {
+        self.storage
+            .insert_ocr_cache(hash, language, engine_version, text)
+            .await
+    }
+
+    pub async fn count_cached_ocr(&self) -> Result<i64, Error> {
+        self.storage.count_ocr_cache().await
+    }
+
+    /// Returns how many entries were removed.
+    pub async fn purge_cached_ocr(&self) -> Result<u64, Error> {
+        self.storage.purge_ocr_cache().await
+    }
+
+    /// Removes text cached before `before` or by another engine version, returning how many
+    /// entries were removed.
+    pub async fn prune_cached_ocr(&self, before: i64, engine_version: i32) -> Result<u64, Error> {
+        self.storage.prune_ocr_cache(before, engine_version).await
+    }
+
     // temporary function to give access to the inner command overwrites while i figure something out.
     #[must_use]
     pub fn inner_overwrites(&self) -> &Checks {
@@ -0,0 +1,68 @@
+use lumi::serenity_prelude::{AttachmentId, GenericChannelId, MessageId, UserId};
+
+/// Hashes this many bits apart or fewer count as the same image unless a guild picks otherwise.
+pub const DEFAULT_MAX_DISTANCE: u32 = 6;
+
+/// What happens when an attachment matches the guild's image blocklist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageAction {
+    /// Post the match to the modlog channel.
+    Alert,
+    /// Delete the message, then alert.
+    Delete,
+}
+
+impl ImageAction {
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageAction::Alert => "alert",
+            ImageAction::Delete => "delete",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "alert" => Some(ImageAction::Alert),
+            "delete" => Some(ImageAction::Delete),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ImageAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A guild hashing its image attachments, and what to do about blocklisted ones.
+#[derive(Clone, Copy, Debug)]
+pub struct ImageFilterConfig {
+    pub action: ImageAction,
+    /// The most bits a hash may differ by to still match.
+    pub max_distance: u32,
+}
+
+/// An image a guild doesn't want posted, like a known scam or shock image.
+#[derive(Clone, Debug)]
+pub struct BlockedImage {
+    pub id: i32,
+    pub hash: u64,
+    pub label: String,
+    pub added_by: UserId,
+    pub created_at: i64,
+}
+
+/// An earlier attachment with a near-identical image.
+#[derive(Clone, Debug)]
+pub struct Repost {
+    pub attachment_id: AttachmentId,
+    pub message_id: MessageId,
+    pub channel_id: GenericChannelId,
+    pub user_id: UserId,
+    pub file_name: String,
+    pub created_at: i64,
+    pub distance: u32,
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use sqlx::PgPool;
+use sqlx::migrate::{Migrate, Migrator};
+
+use crate::data::structs::Error;
+
+/// Migrations embedded into the binary at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationState {
+    /// Applied and the checksum matches the embedded migration.
+    Applied,
+    /// Embedded in the binary but not yet applied.
+    Pending,
+    /// Applied, but the checksum no longer matches the embedded migration.
+    Modified,
+    /// Applied to the database but not known to this binary.
+    Unknown,
+}
+
+impl std::fmt::Display for MigrationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationState::Applied => write!(f, "applied"),
+            MigrationState::Pending => write!(f, "pending"),
+            MigrationState::Modified => write!(f, "modified"),
+            MigrationState::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    /// Hex encoded checksum, from the binary where possible, otherwise from the database.
+    pub checksum: String,
+    pub state: MigrationState,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut string = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(string, "{byte:02x}").unwrap();
+    }
+    string
+}
+
+/// Compares the migrations embedded in the binary against the ones applied to the database.
+pub async fn migration_status(db: &PgPool) -> Result<Vec<MigrationStatus>, Error> {
+    let mut conn = db.acquire().await?;
+    conn.ensure_migrations_table().await?;
+
+    let mut applied = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| (m.version, m.checksum))
+        .collect::<HashMap<_, _>>();
+
+    let mut statuses = Vec::new();
+    for migration in MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+    {
+        let state = match applied.remove(&migration.version) {
+            Some(checksum) if checksum == migration.checksum => MigrationState::Applied,
+            Some(_) => MigrationState::Modified,
+            None => MigrationState::Pending,
+        };
+
+        statuses.push(MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            checksum: hex(&migration.checksum),
+            state,
+        });
+    }
+
+    // Anything left over was applied by a newer binary.
+    for (version, checksum) in applied {
+        statuses.push(MigrationStatus {
+            version,
+            description: String::from("<not in binary>"),
+            checksum: hex(&checksum),
+            state: MigrationState::Unknown,
+        });
+    }
+
+    statuses.sort_by_key(|m| m.version);
+
+    Ok(statuses)
+}
+
+/// Checks the schema against the binary, applying pending migrations if `apply` is set.
+///
+/// Errors if the database has migrations this binary does not know about, or if there are
+/// pending migrations and `apply` is not set.
+pub async fn run_migrations(db: &PgPool, apply: bool) -> Result<(), Error> {
+    let statuses = migration_status(db).await?;
+
+    let unknown = statuses
+        .iter()
+        .filter(|m| m.state == MigrationState::Unknown)
+        .map(|m| m.version.to_string())
+        .collect::<Vec<_>>();
+
+    if !unknown.is_empty() {
+        return Err(format!(
+            "Database schema is ahead of this binary, unknown migrations: {}",
+            unknown.join(", ")
+        )
+        .into());
+    }
+
+    for modified in statuses
+        .iter()
+        .filter(|m| m.state == MigrationState::Modified)
+    {
+        tracing::warn!(
+            "Migration {} ({}) was modified after being applied.",
+            modified.version,
+            modified.description
+        );
+    }
+
+    let pending = statuses
+        .iter()
+        .filter(|m| m.state == MigrationState::Pending)
+        .count();
+
+    if pending == 0 {
+        return Ok(());
+    }
+
+    if !apply {
+        return Err(format!(
+            "{pending} pending migration(s), set MIGRATE_ON_STARTUP=true or apply them manually."
+        )
+        .into());
+    }
+
+    MIGRATOR.run(db).await?;
+    println!("Applied {pending} migration(s).");
+
+    Ok(())
+}
@@ -1,4 +1,10 @@
+pub mod automod;
 pub mod database;
+pub mod images;
 pub mod lob;
+pub mod migrations;
+pub mod modlog;
+pub mod names;
 pub mod responses;
+pub mod storage;
 pub mod structs;
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use lumi::serenity_prelude::{GenericChannelId, MessageId};
+use parking_lot::Mutex;
+
+use crate::event_log::{EventKind, LogRecord};
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct ModlogEvents: u32 {
+        const MESSAGE_EDIT   = 0b00000001;
+        const MESSAGE_DELETE = 0b00000010;
+        const CHANNELS       = 0b00000100;
+        const THREADS        = 0b00001000;
+        const ROLES          = 0b00010000;
+        const MEMBER_ROLES   = 0b00100000;
+    }
+}
+
+impl ModlogEvents {
+    /// The toggle covering `kind`, if that kind can be mirrored at all.
+    #[must_use]
+    pub fn for_kind(kind: EventKind) -> Option<Self> {
+        Some(match kind {
+            EventKind::MessageEdit => Self::MESSAGE_EDIT,
+            EventKind::MessageDelete => Self::MESSAGE_DELETE,
+            EventKind::ChannelCreate | EventKind::ChannelUpdate | EventKind::ChannelDelete => {
+                Self::CHANNELS
+            }
+            EventKind::ThreadCreate | EventKind::ThreadUpdate | EventKind::ThreadDelete => {
+                Self::THREADS
+            }
+            EventKind::RoleCreate | EventKind::RoleUpdate | EventKind::RoleDelete => Self::ROLES,
+            EventKind::MemberRoleUpdate => Self::MEMBER_ROLES,
+            _ => return None,
+        })
+    }
+}
+
+impl From<u32> for ModlogEvents {
+    fn from(value: u32) -> Self {
+        ModlogEvents::from_bits_truncate(value)
+    }
+}
+
+/// A row of `modlog_channels`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModlogConfig {
+    pub channel_id: GenericChannelId,
+    pub events: ModlogEvents,
+}
+
+/// Records waiting to be posted, flushed in batches so bursts like a mass deletion don't hit the
+/// rate limit.
+///
+/// Also remembers the messages the bot deleted itself, the gateway doesn't say who deleted a
+/// message so their deletions would otherwise be mirrored.
+#[derive(Default)]
+pub struct ModlogQueue {
+    pending: Mutex<HashMap<GenericChannelId, Vec<LogRecord>>>,
+    own_deletions: Mutex<HashMap<MessageId, Instant>>,
+}
+
+impl ModlogQueue {
+    /// How many records are kept per channel before the oldest get dropped.
+    pub const MAX_PENDING: usize = 100;
+    /// How long a deletion by the bot is waited for before it is forgotten.
+    const OWN_DELETION_TTL: Duration = Duration::from_secs(60);
+
+    pub fn push(&self, channel_id: GenericChannelId, record: LogRecord) {
+        let mut pending = self.pending.lock();
+        let queue = pending.entry(channel_id).or_default();
+
+        if queue.len() >= Self::MAX_PENDING {
+            queue.remove(0);
+        }
+        queue.push(record);
+    }
+
+    /// Takes everything that is queued.
+    pub fn drain(&self) -> Vec<(GenericChannelId, Vec<LogRecord>)> {
+        self.pending.lock().drain().collect()
+    }
+
+    /// Marks messages the bot is about to delete, call this before the request so the gateway
+    /// event can't arrive first.
+    pub fn mark_own_deletions(&self, message_ids: impl IntoIterator<Item = MessageId>) {
+        let now = Instant::now();
+        let mut own_deletions = self.own_deletions.lock();
+
+        own_deletions.retain(|_, marked| marked.elapsed() < Self::OWN_DELETION_TTL);
+        own_deletions.extend(message_ids.into_iter().map(|id| (id, now)));
+    }
+
+    /// If the bot deleted `message_id` itself, forgetting it afterwards.
+    pub fn is_own_deletion(&self, message_id: MessageId) -> bool {
+        self.own_deletions
+            .lock()
+            .remove(&message_id)
+            .is_some_and(|marked| marked.elapsed() < Self::OWN_DELETION_TTL)
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use lumi::serenity_prelude::{GuildId, UserId};
+
+bitflags::bitflags! {
+    /// What happens when a member's name or custom status is flagged by the filter.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct NameFilterActions: u32 {
+        /// Post the flagged words to the modlog channel.
+        const ALERT      = 0b00000001;
+        /// Clear a flagged nickname, or cover a flagged username or display name with one.
+        const RESET_NICK = 0b00000010;
+        /// Strip the symbols from the start of names that sort above everyone else.
+        const DEHOIST    = 0b00000100;
+    }
+}
+
+impl From<u32> for NameFilterActions {
+    fn from(value: u32) -> Self {
+        NameFilterActions::from_bits_truncate(value)
+    }
+}
+
+/// The nickname given to members whose username or display name was flagged.
+pub const MODERATED_NICK: &str = "Moderated name";
+
+/// `name` without leading characters that aren't letters or numbers, or [`None`] if there were
+/// none to strip.
+#[must_use]
+pub fn dehoist(name: &str) -> Option<&str> {
+    let stripped = name.trim_start_matches(|c: char| !c.is_alphanumeric());
+    (stripped.len() != name.len()).then_some(stripped)
+}
+
+/// How many members [`CheckedNames`] holds before forgetting the ones not updated recently.
+const MAX_CHECKED_NAMES: usize = 10_000;
+
+/// How long the names of a member are kept once the map is full.
+const CHECKED_NAMES_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The names last checked for each member, for updates where the cache doesn't have the member
+/// from before to compare against.
+#[derive(Default)]
+pub struct CheckedNames(DashMap<(GuildId, UserId), (Instant, Vec<(&'static str, String)>)>);
+
+impl CheckedNames {
+    /// Records `names` as the ones last checked for the member, returning the ones recorded
+    /// before.
+    pub fn replace(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        names: Vec<(&'static str, String)>,
+    ) -> Option<Vec<(&'static str, String)>> {
+        // keep the map from growing with every member that was ever updated.
+        if self.0.len() > MAX_CHECKED_NAMES {
+            self.0
+                .retain(|_, (checked, _)| checked.elapsed() < CHECKED_NAMES_TTL);
+        }
+
+        self.0
+            .insert((guild_id, user_id), (Instant::now(), names))
+            .map(|(_, names)| names)
+    }
+
+    /// Forgets the member, once they leave.
+    pub fn remove(&self, guild_id: GuildId, user_id: UserId) {
+        self.0.remove(&(guild_id, user_id));
+    }
+
+    /// Forgets every member of the guild, once the bot leaves it.
+    pub fn remove_guild(&self, guild_id: GuildId) {
+        self.0.retain(|(guild, _), _| *guild != guild_id);
+    }
+}
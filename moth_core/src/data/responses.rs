@@ -1,12 +1,21 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
-use regex::Regex;
-use serenity::all::{GenericChannelId, GuildId, ReactionType};
+use rand::seq::IteratorRandom;
+use regex::{Captures, Regex};
+use serenity::all::{GenericChannelId, GuildId, ReactionType, UserId};
 
 #[derive(Default)]
 pub struct ResponseCache {
     pub guild: DashMap<GuildId, GuildCache>,
+    /// When each regex last responded within its cooldown scope, in the last minute or cooldown.
+    cooldowns: DashMap<(i32, u64), VecDeque<Instant>>,
+    /// How many matches of each regex a cooldown kept from being responded to.
+    pub(crate) suppressed: DashMap<i32, u64>,
+    /// Suppressed matches not saved to the database yet.
+    pub(crate) pending_suppressed: DashMap<i32, u64>,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -29,6 +38,58 @@ pub struct RegexData {
     /// What channels/threads to ignore.
     pub exceptions: HashSet<GenericChannelId>,
     pub detection_type: DetectionType,
+    /// The language images are read in for OCR, the guild's when unset.
+    pub ocr_language: Option<String>,
+    pub cooldown: Cooldown,
+}
+
+/// Longest cooldown a regex response can have, in seconds.
+pub const MAX_COOLDOWN: u32 = 3600;
+
+/// What a cooldown of a regex response is kept per.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CooldownScope {
+    #[default]
+    Global,
+    Channel,
+    User,
+}
+
+impl From<i16> for CooldownScope {
+    fn from(value: i16) -> Self {
+        match value {
+            1 => CooldownScope::Channel,
+            2 => CooldownScope::User,
+            _ => CooldownScope::Global,
+        }
+    }
+}
+
+impl From<CooldownScope> for i16 {
+    fn from(value: CooldownScope) -> Self {
+        match value {
+            CooldownScope::Global => 0,
+            CooldownScope::Channel => 1,
+            CooldownScope::User => 2,
+        }
+    }
+}
+
+/// How often a regex response may be sent, both limits applying within the scope.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cooldown {
+    pub scope: CooldownScope,
+    /// Seconds to wait after responding, 0 to not wait.
+    pub seconds: u32,
+    /// How many responses are sent in a minute at most.
+    pub max_per_minute: Option<u32>,
+}
+
+impl Cooldown {
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.seconds > 0 || self.max_per_minute.is_some()
+    }
 }
 
 bitflags::bitflags! {
@@ -51,67 +112,452 @@ pub enum ResponseType {
     Emoji(ReactionType),
 }
 
-/* impl ResponseCache {
-    pub(crate) fn get_guild_ref(
-        &self,
-        guild_id: GuildId,
-    ) -> Option<dashmap::mapref::one::Ref<'_, GuildId, GuildCache>> {
-        self.guild.get(&guild_id)
+/// Longest pattern a regex response can have.
+pub const MAX_PATTERN_LEN: usize = 1000;
+
+/// Compiles a pattern for a regex response, refusing ones that are too large or would match
+/// every message.
+pub fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(format!(
+            "Patterns can be at most {MAX_PATTERN_LEN} characters long."
+        ));
+    }
+
+    let regex = regex::RegexBuilder::new(pattern)
+        .size_limit(1 << 20)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {e}"))?;
+
+    if regex.is_match("") {
+        return Err("That pattern matches an empty message, so it would match everything.".into());
+    }
+
+    Ok(regex)
+}
+
+/// A piece of a response template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part<'a> {
+    Text(String),
+    /// `{user}`, `{1}` or `{name}`, without the braces.
+    Placeholder(&'a str),
+    /// `{a|b|c}`, one picked at random each time.
+    Choice(Vec<&'a str>),
+}
+
+/// Splits a template into text, placeholders and choices, `{{` and `}}` being literal braces.
+fn parse_template(template: &str) -> Result<Vec<Part<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        text.push_str(&rest[..i]);
+        let brace = rest.as_bytes()[i];
+        rest = &rest[i + 1..];
+
+        if brace == b'}' {
+            let Some(after) = rest.strip_prefix('}') else {
+                return Err("Unmatched `}`, write `}}` for a literal one.".into());
+            };
+            text.push('}');
+            rest = after;
+            continue;
+        }
+
+        if let Some(after) = rest.strip_prefix('{') {
+            text.push('{');
+            rest = after;
+            continue;
+        }
+
+        let Some(end) = rest.find('}') else {
+            return Err("Unclosed `{`, write `{{` for a literal one.".into());
+        };
+        let inner = &rest[..end];
+        rest = &rest[end + 1..];
+
+        if inner.contains('{') {
+            return Err(format!("Placeholders can't be nested, in `{{{inner}}}`."));
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(std::mem::take(&mut text)));
+        }
+        if inner.contains('|') {
+            parts.push(Part::Choice(inner.split('|').collect()));
+        } else {
+            parts.push(Part::Placeholder(inner.trim()));
+        }
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+
+    Ok(parts)
+}
+
+const BUILTIN_PLACEHOLDERS: [&str; 4] = ["user", "user.mention", "channel", "guild"];
+
+/// Checks a response template only uses placeholders that exist, including the capture groups
+/// of `pattern`.
+pub fn validate_template(template: &str, pattern: &Regex) -> Result<(), String> {
+    for part in parse_template(template)? {
+        let Part::Placeholder(name) = part else {
+            continue;
+        };
+
+        if BUILTIN_PLACEHOLDERS.contains(&name) {
+            continue;
+        }
+
+        if let Ok(group) = name.parse::<usize>() {
+            if group >= pattern.captures_len() {
+                return Err(format!(
+                    "`{{{group}}}` isn't a group of the pattern, which has {}.",
+                    pattern.captures_len() - 1
+                ));
+            }
+            continue;
+        }
+
+        if !pattern.capture_names().flatten().any(|n| n == name) {
+            return Err(format!(
+                "`{{{name}}}` isn't a placeholder or a named group of the pattern."
+            ));
+        }
     }
 
-    pub(crate) fn remove_guild(&self, guild_id: GuildId) {
-        self.guild.remove(&guild_id);
+    Ok(())
+}
+
+/// What the placeholders of a template are filled in with.
+pub struct TemplateContext<'a> {
+    pub user_id: UserId,
+    pub user_name: &'a str,
+    pub channel_id: GenericChannelId,
+    pub guild_name: &'a str,
+}
+
+/// Fills in a response template, sending it as written if it was never valid.
+#[must_use]
+pub fn render_template(
+    template: &str,
+    context: &TemplateContext<'_>,
+    captures: Option<&Captures<'_>>,
+) -> String {
+    let Ok(parts) = parse_template(template) else {
+        return template.to_owned();
+    };
+
+    let mut rng = rand::rng();
+    let mut rendered = String::with_capacity(template.len());
+    for part in parts {
+        match part {
+            Part::Text(text) => rendered.push_str(&text),
+            Part::Choice(choices) => {
+                rendered.push_str(choices.into_iter().choose(&mut rng).unwrap_or_default());
+            }
+            Part::Placeholder("user") => rendered.push_str(context.user_name),
+            Part::Placeholder("user.mention") => {
+                write!(rendered, "<@{}>", context.user_id).unwrap();
+            }
+            Part::Placeholder("channel") => write!(rendered, "<#{}>", context.channel_id).unwrap(),
+            Part::Placeholder("guild") => rendered.push_str(context.guild_name),
+            Part::Placeholder(name) => {
+                let group = match name.parse::<usize>() {
+                    Ok(group) => captures.and_then(|c| c.get(group)),
+                    Err(_) => captures.and_then(|c| c.name(name)),
+                };
+                rendered.push_str(group.map_or("", |m| m.as_str()));
+            }
+        }
     }
 
-    pub(crate) fn remove_regex(&self, guild_id: GuildId, channel_id: Option<ChannelId>, id: i32) {
+    rendered
+}
+
+impl ResponseCache {
+    /// Records `regex` responding to `user_id` in `channel_id`, returning `false` without
+    /// recording anything when its cooldown or cap doesn't allow it yet.
+    pub(crate) fn try_trigger(
+        &self,
+        regex: &RegexData,
+        channel_id: GenericChannelId,
+        user_id: UserId,
+    ) -> bool {
+        let cooldown = regex.cooldown;
+        if !cooldown.is_enabled() {
+            return true;
+        }
+
+        // keep the map from growing with every user that ever triggered a response.
+        if self.cooldowns.len() > 10_000 {
+            let max_age = Duration::from_secs(u64::from(MAX_COOLDOWN).max(60));
+            self.cooldowns
+                .retain(|_, t| t.back().is_some_and(|t| t.elapsed() < max_age));
+        }
+
+        let key = match cooldown.scope {
+            CooldownScope::Global => 0,
+            CooldownScope::Channel => channel_id.get(),
+            CooldownScope::User => user_id.get(),
+        };
+
+        let now = Instant::now();
+        let window = Duration::from_secs(u64::from(cooldown.seconds).max(60));
+        let mut triggers = self.cooldowns.entry((regex.id, key)).or_default();
+        while triggers
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= window)
+        {
+            triggers.pop_front();
+        }
+
+        if triggers
+            .back()
+            .is_some_and(|t| now.duration_since(*t).as_secs() < u64::from(cooldown.seconds))
+        {
+            return false;
+        }
+
+        if let Some(max) = cooldown.max_per_minute {
+            let last_minute = triggers
+                .iter()
+                .filter(|t| now.duration_since(**t) < Duration::from_secs(60))
+                .count();
+            if last_minute >= max as usize {
+                return false;
+            }
+        }
+
+        triggers.push_back(now);
+        true
+    }
+
+    pub(crate) fn remove_regex(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<GenericChannelId>,
+        id: i32,
+    ) {
+        let Some(mut g) = self.guild.get_mut(&guild_id) else {
+            return;
+        };
+
         if let Some(channel_id) = channel_id {
-            self.guild.get_mut(&guild_id).map(|mut g| {
-                g.channel
-                    .get_mut(&channel_id)
-                    .map(|c| c.retain(|i| i.id != id))
-            });
-        } else if let Some(mut g) = self.guild.get_mut(&guild_id) {
+            if let Some(c) = g.channel.get_mut(&channel_id) {
+                c.retain(|i| i.id != id);
+                if c.is_empty() {
+                    g.channel.remove(&channel_id);
+                }
+            }
+        } else {
             g.global.retain(|i| i.id != id);
         }
     }
 
+    /// Adds the regex to the guild's entry, guilds that aren't cached yet load it with the rest.
     pub(crate) fn add_regex(
         &self,
         guild_id: GuildId,
-        channel_id: Option<ChannelId>,
+        channel_id: Option<GenericChannelId>,
         regex: RegexData,
     ) {
+        let Some(mut g) = self.guild.get_mut(&guild_id) else {
+            return;
+        };
+
         if let Some(channel_id) = channel_id {
-            if let Some(mut g) = self.guild.get_mut(&guild_id) {
-                g.channel
-                    .entry(channel_id)
-                    .or_insert_with(Vec::new)
-                    .push(regex);
-            }
-        } else if let Some(mut g) = self.guild.get_mut(&guild_id) {
+            g.channel.entry(channel_id).or_default().push(regex);
+        } else {
             g.global.push(regex);
         }
     }
 
+    /// Replaces the regex, moving it if its channel changed.
     pub(crate) fn replace_regex(
         &self,
         guild_id: GuildId,
-        channel_id: Option<ChannelId>,
-        id: i32,
+        old_channel_id: Option<GenericChannelId>,
+        new_channel_id: Option<GenericChannelId>,
         new_regex: RegexData,
     ) {
-        if let Some(channel_id) = channel_id {
-            if let Some(mut g) = self.guild.get_mut(&guild_id) {
-                if let Some(c) = g.channel.get_mut(&channel_id) {
-                    if let Some(existing) = c.iter_mut().find(|i| i.id == id) {
-                        *existing = new_regex;
-                    }
-                }
-            };
-        } else if let Some(mut g) = self.guild.get_mut(&guild_id) {
-            if let Some(existing) = g.global.iter_mut().find(|i| i.id == id) {
-                *existing = new_regex;
-            }
+        if old_channel_id != new_channel_id {
+            self.remove_regex(guild_id, old_channel_id, new_regex.id);
+            self.add_regex(guild_id, new_channel_id, new_regex);
+            return;
         }
+
+        let Some(mut g) = self.guild.get_mut(&guild_id) else {
+            return;
+        };
+
+        let regexes = match new_channel_id {
+            Some(channel_id) => g.channel.get_mut(&channel_id),
+            None => Some(&mut g.global),
+        };
+
+        if let Some(existing) = regexes.and_then(|r| r.iter_mut().find(|i| i.id == new_regex.id)) {
+            *existing = new_regex;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(pattern: &str) -> Regex {
+        Regex::new(pattern).unwrap()
     }
-} */
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(
+            parse_template("{{user}} }}").unwrap(),
+            vec![Part::Text("{user} }".into())]
+        );
+    }
+
+    #[test]
+    fn unmatched_braces() {
+        assert!(
+            parse_template("a } b")
+                .unwrap_err()
+                .contains("Unmatched `}`")
+        );
+        assert!(
+            parse_template("a {user")
+                .unwrap_err()
+                .contains("Unclosed `{`")
+        );
+    }
+
+    #[test]
+    fn nested_placeholders() {
+        assert!(
+            parse_template("{a{b}}")
+                .unwrap_err()
+                .contains("can't be nested")
+        );
+    }
+
+    #[test]
+    fn placeholders_and_choices() {
+        assert_eq!(
+            parse_template("hi { user }, {a|b}!").unwrap(),
+            vec![
+                Part::Text("hi ".into()),
+                Part::Placeholder("user"),
+                Part::Text(", ".into()),
+                Part::Choice(vec!["a", "b"]),
+                Part::Text("!".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn builtins_are_valid() {
+        let regex = pattern("hello");
+        assert!(validate_template("{user} {user.mention} {channel} {guild}", &regex).is_ok());
+    }
+
+    #[test]
+    fn groups_of_the_pattern_are_valid() {
+        let regex = pattern("(?<word>\\w+) (\\d+)");
+        assert!(validate_template("{0} {1} {2} {word}", &regex).is_ok());
+    }
+
+    #[test]
+    fn unknown_groups_are_rejected() {
+        let regex = pattern("(?<word>\\w+)");
+        assert_eq!(
+            validate_template("{2}", &regex).unwrap_err(),
+            "`{2}` isn't a group of the pattern, which has 1."
+        );
+        assert_eq!(
+            validate_template("{other}", &regex).unwrap_err(),
+            "`{other}` isn't a placeholder or a named group of the pattern."
+        );
+    }
+
+    #[test]
+    fn choices_are_not_placeholders() {
+        let regex = pattern("hello");
+        assert!(validate_template("{yes|no}", &regex).is_ok());
+    }
+
+    fn regex(cooldown: Cooldown) -> RegexData {
+        RegexData {
+            id: 1,
+            pattern: pattern("hello"),
+            recurse_channels: false,
+            recurse_threads: false,
+            response: ResponseType::Message("hi".into()),
+            exceptions: HashSet::new(),
+            detection_type: DetectionType::CONTENT,
+            ocr_language: None,
+            cooldown,
+        }
+    }
+
+    const CHANNEL: GenericChannelId = GenericChannelId::new(10);
+    const OTHER_CHANNEL: GenericChannelId = GenericChannelId::new(11);
+    const USER: UserId = UserId::new(20);
+    const OTHER_USER: UserId = UserId::new(21);
+
+    #[test]
+    fn no_cooldown_always_triggers() {
+        let cache = ResponseCache::default();
+        let regex = regex(Cooldown::default());
+        for _ in 0..5 {
+            assert!(cache.try_trigger(&regex, CHANNEL, USER));
+        }
+    }
+
+    #[test]
+    fn cooldown_waits_within_its_scope() {
+        let cache = ResponseCache::default();
+        let regex = regex(Cooldown {
+            scope: CooldownScope::User,
+            seconds: 30,
+            max_per_minute: None,
+        });
+
+        assert!(cache.try_trigger(&regex, CHANNEL, USER));
+        assert!(!cache.try_trigger(&regex, OTHER_CHANNEL, USER));
+        assert!(cache.try_trigger(&regex, CHANNEL, OTHER_USER));
+    }
+
+    #[test]
+    fn global_cooldown_applies_everywhere() {
+        let cache = ResponseCache::default();
+        let regex = regex(Cooldown {
+            scope: CooldownScope::Global,
+            seconds: 30,
+            max_per_minute: None,
+        });
+
+        assert!(cache.try_trigger(&regex, CHANNEL, USER));
+        assert!(!cache.try_trigger(&regex, OTHER_CHANNEL, OTHER_USER));
+    }
+
+    #[test]
+    fn per_minute_cap() {
+        let cache = ResponseCache::default();
+        let regex = regex(Cooldown {
+            scope: CooldownScope::Channel,
+            seconds: 0,
+            max_per_minute: Some(2),
+        });
+
+        assert!(cache.try_trigger(&regex, CHANNEL, USER));
+        assert!(cache.try_trigger(&regex, CHANNEL, USER));
+        assert!(!cache.try_trigger(&regex, CHANNEL, USER));
+        assert!(cache.try_trigger(&regex, OTHER_CHANNEL, USER));
+    }
+}
@@ -0,0 +1,822 @@
+use std::collections::{HashMap, HashSet};
+
+use lumi::serenity_prelude as serenity;
+use parking_lot::Mutex;
+use serenity::{AttachmentId, GenericChannelId, GuildId, MessageId, UserId};
+
+use super::{Emote, EmoteUsage, MessageSnapshot, NewMessage, OwnerAccess, Storage};
+use crate::data::automod::{AutomodAction, AutomodRecord};
+use crate::data::database::{
+    ChannelIdWrapper, EmoteUsageType, MessageIdWrapper, StarboardMessage, StarboardStatus,
+};
+use crate::data::images::{BlockedImage, ImageFilterConfig, Repost};
+use crate::data::modlog::ModlogConfig;
+use crate::data::names::NameFilterActions;
+use crate::data::responses::RegexData;
+use crate::data::structs::{DmActivity, Error};
+use moth_filter::{Tier, TrieOverride, WordList};
+
+/// Keeps everything in memory, for running handlers without a database.
+///
+/// Nothing is persisted, and constraints are only enforced where the handlers depend on them.
+#[derive(Default)]
+pub struct MemoryStorage {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    users: HashSet<UserId>,
+    guilds: HashSet<GuildId>,
+    channels: HashMap<GenericChannelId, Option<GuildId>>,
+    messages: Vec<NewMessage>,
+    edits: Vec<MessageSnapshot>,
+    deletions: Vec<MessageSnapshot>,
+    emotes: HashMap<Emote, i32>,
+    emote_usage: Vec<(i32, EmoteUsage)>,
+    starboard: Vec<StarboardMessage>,
+    starboard_overrides: HashMap<GenericChannelId, u8>,
+    modlog: HashMap<GuildId, ModlogConfig>,
+    filter_words: HashSet<(GuildId, WordList, String)>,
+    filter_overrides: HashMap<(GuildId, String), TrieOverride>,
+    automod_tiers: HashMap<(GuildId, Tier), AutomodAction>,
+    automod_records: Vec<AutomodRecord>,
+    name_filter: HashMap<GuildId, NameFilterActions>,
+    ocr_languages: HashMap<GuildId, String>,
+    ocr_filter: HashSet<GuildId>,
+    image_filter: HashMap<GuildId, ImageFilterConfig>,
+    image_blocklist: Vec<(GuildId, BlockedImage)>,
+    image_hashes: HashMap<AttachmentId, (GuildId, u64)>,
+    /// (hash, language) to (engine version, text, created at).
+    ocr_cache: HashMap<(Vec<u8>, String), (i32, String, i64)>,
+    /// Regex responses by id, with their guild, channel and suppressed count.
+    regexes: HashMap<i32, (GuildId, Option<GenericChannelId>, RegexData, u64)>,
+    next_regex_id: i32,
+    dm_activity: HashMap<UserId, DmActivity>,
+    banned_users: HashSet<UserId>,
+    owner_access: Vec<OwnerAccess>,
+}
+
+impl Inner {
+    fn emote_id(&mut self, emote: &Emote) -> i32 {
+        let next = self.emotes.len() as i32 + 1;
+        *self.emotes.entry(emote.clone()).or_insert(next)
+    }
+}
+
+impl MemoryStorage {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every message inserted so far, in insertion order.
+    #[must_use]
+    pub fn messages(&self) -> Vec<NewMessage> {
+        self.inner.lock().messages.clone()
+    }
+
+    #[must_use]
+    pub fn edits(&self) -> Vec<MessageSnapshot> {
+        self.inner.lock().edits.clone()
+    }
+
+    #[must_use]
+    pub fn deletions(&self) -> Vec<MessageSnapshot> {
+        self.inner.lock().deletions.clone()
+    }
+
+    /// Every emote usage, including those from message content.
+    #[must_use]
+    pub fn emote_usage(&self) -> Vec<EmoteUsage> {
+        self.inner
+            .lock()
+            .emote_usage
+            .iter()
+            .map(|(_, usage)| usage.clone())
+            .collect()
+    }
+}
+
+#[serenity::async_trait]
+impl Storage for MemoryStorage {
+    async fn insert_user(&self, user_id: UserId) -> Result<(), Error> {
+        self.inner.lock().users.insert(user_id);
+        Ok(())
+    }
+
+    async fn insert_guild(&self, guild_id: GuildId) -> Result<(), Error> {
+        self.inner.lock().guilds.insert(guild_id);
+        Ok(())
+    }
+
+    async fn insert_channel(
+        &self,
+        channel_id: GenericChannelId,
+        guild_id: Option<GuildId>,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        if let Some(guild_id) = guild_id {
+            inner.guilds.insert(guild_id);
+        }
+        inner.channels.entry(channel_id).or_insert(guild_id);
+        Ok(())
+    }
+
+    async fn insert_message(&self, message: &NewMessage) -> Result<(), Error> {
+        self.insert_channel(message.channel_id, message.guild_id)
+            .await?;
+        self.insert_user(message.user_id).await?;
+
+        let mut inner = self.inner.lock();
+        if inner
+            .messages
+            .iter()
+            .any(|m| m.message_id == message.message_id)
+        {
+            return Err(format!("Message {} already exists.", message.message_id).into());
+        }
+
+        for emote in &message.emotes {
+            let id = inner.emote_id(emote);
+            // Only guild messages carry emotes, but skip rather than panic if one slips through.
+            let Some(guild_id) = message.guild_id else {
+                continue;
+            };
+            inner.emote_usage.push((
+                id,
+                EmoteUsage {
+                    emote: emote.clone(),
+                    message_id: message.message_id,
+                    user_id: message.user_id,
+                    channel_id: message.channel_id,
+                    guild_id,
+                    used_at: message.created_at,
+                    usage_type: EmoteUsageType::Message,
+                },
+            ));
+        }
+
+        inner.messages.push(message.clone());
+        Ok(())
+    }
+
+    async fn insert_edit(&self, edit: &MessageSnapshot) -> Result<(), Error> {
+        self.insert_channel(edit.channel_id, edit.guild_id).await?;
+        self.insert_user(edit.user_id).await?;
+        self.inner.lock().edits.push(edit.clone());
+        Ok(())
+    }
+
+    async fn insert_deletion(&self, deletion: &MessageSnapshot) -> Result<(), Error> {
+        self.insert_channel(deletion.channel_id, deletion.guild_id)
+            .await?;
+        self.insert_user(deletion.user_id).await?;
+        self.inner.lock().deletions.push(deletion.clone());
+        Ok(())
+    }
+
+    async fn insert_emote_usage(&self, usage: &EmoteUsage) -> Result<(), Error> {
+        self.insert_channel(usage.channel_id, Some(usage.guild_id))
+            .await?;
+        self.insert_user(usage.user_id).await?;
+
+        let mut inner = self.inner.lock();
+        let id = inner.emote_id(&usage.emote);
+        inner.emote_usage.push((id, usage.clone()));
+        Ok(())
+    }
+
+    async fn get_starboard(&self, message_id: MessageId) -> Result<StarboardMessage, Error> {
+        self.inner
+            .lock()
+            .starboard
+            .iter()
+            .find(|m| *m.message_id == message_id)
+            .cloned()
+            .ok_or_else(|| "Starboard message not found.".into())
+    }
+
+    async fn get_starboard_by_starboard_id(
+        &self,
+        starboard_message_id: MessageId,
+    ) -> Result<StarboardMessage, Error> {
+        self.inner
+            .lock()
+            .starboard
+            .iter()
+            .find(|m| *m.starboard_message_id == starboard_message_id)
+            .cloned()
+            .ok_or_else(|| "Starboard message not found.".into())
+    }
+
+    async fn get_all_starboard(&self) -> Result<Vec<StarboardMessage>, Error> {
+        Ok(self.inner.lock().starboard.clone())
+    }
+
+    async fn insert_starboard(&self, message: &StarboardMessage) -> Result<i32, Error> {
+        let mut inner = self.inner.lock();
+        if inner
+            .starboard
+            .iter()
+            .any(|m| m.message_id == message.message_id)
+        {
+            return Err("Starboard message already exists.".into());
+        }
+
+        let id = inner.starboard.iter().map(|m| m.id).max().unwrap_or(0) + 1;
+        let mut message = message.clone();
+        message.id = id;
+        inner.starboard.push(message);
+
+        Ok(id)
+    }
+
+    async fn update_star_count(&self, id: i32, count: i16) -> Result<(), Error> {
+        if let Some(m) = self.inner.lock().starboard.iter_mut().find(|m| m.id == id) {
+            m.star_count = count;
+        }
+        Ok(())
+    }
+
+    async fn approve_starboard(
+        &self,
+        starboard_message_id: MessageId,
+        new_message_id: MessageId,
+        new_channel_id: GenericChannelId,
+    ) -> Result<(), Error> {
+        if let Some(m) = self
+            .inner
+            .lock()
+            .starboard
+            .iter_mut()
+            .find(|m| *m.starboard_message_id == starboard_message_id)
+        {
+            m.starboard_status = StarboardStatus::Accepted;
+            m.starboard_message_id = MessageIdWrapper(new_message_id);
+            m.starboard_message_channel = ChannelIdWrapper(new_channel_id);
+        }
+        Ok(())
+    }
+
+    async fn deny_starboard(&self, starboard_message_id: MessageId) -> Result<(), Error> {
+        if let Some(m) = self
+            .inner
+            .lock()
+            .starboard
+            .iter_mut()
+            .find(|m| *m.starboard_message_id == starboard_message_id)
+        {
+            m.starboard_status = StarboardStatus::Denied;
+        }
+        Ok(())
+    }
+
+    async fn get_starboard_overrides(&self) -> Result<Vec<(GenericChannelId, u8)>, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .starboard_overrides
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .collect())
+    }
+
+    async fn set_starboard_override(
+        &self,
+        channel_id: GenericChannelId,
+        star_count: u8,
+    ) -> Result<(), Error> {
+        self.inner
+            .lock()
+            .starboard_overrides
+            .insert(channel_id, star_count);
+        Ok(())
+    }
+
+    async fn remove_starboard_override(&self, channel_id: GenericChannelId) -> Result<bool, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .starboard_overrides
+            .remove(&channel_id)
+            .is_some())
+    }
+
+    async fn get_modlog_configs(&self) -> Result<Vec<(GuildId, ModlogConfig)>, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .modlog
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .collect())
+    }
+
+    async fn set_modlog_config(
+        &self,
+        guild_id: GuildId,
+        config: ModlogConfig,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        inner.modlog.insert(guild_id, config);
+        Ok(())
+    }
+
+    async fn remove_modlog_config(&self, guild_id: GuildId) -> Result<bool, Error> {
+        Ok(self.inner.lock().modlog.remove(&guild_id).is_some())
+    }
+
+    async fn get_filter_words(&self) -> Result<Vec<(GuildId, WordList, String)>, Error> {
+        Ok(self.inner.lock().filter_words.iter().cloned().collect())
+    }
+
+    async fn add_filter_word(
+        &self,
+        guild_id: GuildId,
+        list: WordList,
+        word: &str,
+    ) -> Result<bool, Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        Ok(inner.filter_words.insert((guild_id, list, word.to_owned())))
+    }
+
+    async fn remove_filter_word(
+        &self,
+        guild_id: GuildId,
+        list: WordList,
+        word: &str,
+    ) -> Result<bool, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .filter_words
+            .remove(&(guild_id, list, word.to_owned())))
+    }
+
+    async fn get_filter_overrides(&self) -> Result<Vec<(GuildId, String, TrieOverride)>, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .filter_overrides
+            .iter()
+            .map(|((guild_id, word), kind)| (*guild_id, word.clone(), *kind))
+            .collect())
+    }
+
+    async fn set_filter_override(
+        &self,
+        guild_id: GuildId,
+        word: &str,
+        kind: TrieOverride,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        inner
+            .filter_overrides
+            .insert((guild_id, word.to_owned()), kind);
+        Ok(())
+    }
+
+    async fn remove_filter_override(&self, guild_id: GuildId, word: &str) -> Result<bool, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .filter_overrides
+            .remove(&(guild_id, word.to_owned()))
+            .is_some())
+    }
+
+    async fn get_automod_tiers(&self) -> Result<Vec<(GuildId, Tier, AutomodAction)>, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .automod_tiers
+            .iter()
+            .map(|((guild_id, tier), action)| (*guild_id, *tier, *action))
+            .collect())
+    }
+
+    async fn set_automod_tier(
+        &self,
+        guild_id: GuildId,
+        tier: Tier,
+        action: AutomodAction,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        inner.automod_tiers.insert((guild_id, tier), action);
+        Ok(())
+    }
+
+    async fn remove_automod_tier(&self, guild_id: GuildId, tier: Tier) -> Result<bool, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .automod_tiers
+            .remove(&(guild_id, tier))
+            .is_some())
+    }
+
+    async fn insert_automod_record(&self, record: &AutomodRecord) -> Result<i32, Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(record.guild_id);
+        inner.automod_records.push(record.clone());
+        // ids start at 1, like SERIAL.
+        Ok(inner.automod_records.len() as i32)
+    }
+
+    async fn get_automod_record(&self, id: i32) -> Result<Option<AutomodRecord>, Error> {
+        let index = usize::try_from(id).ok().and_then(|i| i.checked_sub(1));
+        Ok(index.and_then(|i| self.inner.lock().automod_records.get(i).cloned()))
+    }
+
+    async fn get_name_filters(&self) -> Result<Vec<(GuildId, NameFilterActions)>, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .name_filter
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .collect())
+    }
+
+    async fn set_name_filter(
+        &self,
+        guild_id: GuildId,
+        actions: NameFilterActions,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        inner.name_filter.insert(guild_id, actions);
+        Ok(())
+    }
+
+    async fn remove_name_filter(&self, guild_id: GuildId) -> Result<bool, Error> {
+        Ok(self.inner.lock().name_filter.remove(&guild_id).is_some())
+    }
+
+    async fn get_ocr_languages(&self) -> Result<Vec<(GuildId, String)>, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .ocr_languages
+            .iter()
+            .map(|(k, v)| (*k, v.clone()))
+            .collect())
+    }
+
+    async fn set_ocr_language(&self, guild_id: GuildId, language: &str) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        inner.ocr_languages.insert(guild_id, language.to_owned());
+        Ok(())
+    }
+
+    async fn remove_ocr_language(&self, guild_id: GuildId) -> Result<bool, Error> {
+        Ok(self.inner.lock().ocr_languages.remove(&guild_id).is_some())
+    }
+
+    async fn get_ocr_filters(&self) -> Result<Vec<GuildId>, Error> {
+        Ok(self.inner.lock().ocr_filter.iter().copied().collect())
+    }
+
+    async fn set_ocr_filter(&self, guild_id: GuildId) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        inner.ocr_filter.insert(guild_id);
+        Ok(())
+    }
+
+    async fn remove_ocr_filter(&self, guild_id: GuildId) -> Result<bool, Error> {
+        Ok(self.inner.lock().ocr_filter.remove(&guild_id))
+    }
+
+    async fn get_image_filters(&self) -> Result<Vec<(GuildId, ImageFilterConfig)>, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .image_filter
+            .iter()
+            .map(|(k, v)| (*k, *v))
+            .collect())
+    }
+
+    async fn set_image_filter(
+        &self,
+        guild_id: GuildId,
+        config: ImageFilterConfig,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        inner.image_filter.insert(guild_id, config);
+        Ok(())
+    }
+
+    async fn remove_image_filter(&self, guild_id: GuildId) -> Result<bool, Error> {
+        Ok(self.inner.lock().image_filter.remove(&guild_id).is_some())
+    }
+
+    async fn get_image_blocklist(&self) -> Result<Vec<(GuildId, BlockedImage)>, Error> {
+        Ok(self.inner.lock().image_blocklist.clone())
+    }
+
+    async fn insert_blocked_image(
+        &self,
+        guild_id: GuildId,
+        image: &BlockedImage,
+    ) -> Result<i32, Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+
+        if let Some((_, existing)) = inner
+            .image_blocklist
+            .iter_mut()
+            .find(|(g, i)| *g == guild_id && i.hash == image.hash)
+        {
+            existing.label.clone_from(&image.label);
+            return Ok(existing.id);
+        }
+
+        // ids start at 1, like SERIAL.
+        let id = inner
+            .image_blocklist
+            .iter()
+            .map(|(_, i)| i.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let mut image = image.clone();
+        image.id = id;
+        inner.image_blocklist.push((guild_id, image));
+        Ok(id)
+    }
+
+    async fn remove_blocked_image(&self, guild_id: GuildId, id: i32) -> Result<bool, Error> {
+        let mut inner = self.inner.lock();
+        let before = inner.image_blocklist.len();
+        inner
+            .image_blocklist
+            .retain(|(g, i)| !(*g == guild_id && i.id == id));
+        Ok(inner.image_blocklist.len() != before)
+    }
+
+    async fn insert_image_hash(
+        &self,
+        attachment_id: AttachmentId,
+        guild_id: GuildId,
+        hash: u64,
+    ) -> Result<(), Error> {
+        self.inner
+            .lock()
+            .image_hashes
+            .entry(attachment_id)
+            .or_insert((guild_id, hash));
+        Ok(())
+    }
+
+    async fn find_reposts(
+        &self,
+        guild_id: GuildId,
+        hash: u64,
+        max_distance: u32,
+        limit: i64,
+    ) -> Result<Vec<Repost>, Error> {
+        let inner = self.inner.lock();
+
+        let mut reposts = Vec::new();
+        for message in &inner.messages {
+            for attachment in &message.attachments {
+                let Some((guild, other)) = inner.image_hashes.get(&attachment.attachment_id) else {
+                    continue;
+                };
+                let distance = crate::phash::distance(hash, *other);
+                if *guild != guild_id || distance > max_distance {
+                    continue;
+                }
+
+                reposts.push(Repost {
+                    attachment_id: attachment.attachment_id,
+                    message_id: message.message_id,
+                    channel_id: message.channel_id,
+                    user_id: message.user_id,
+                    file_name: attachment.file_name.clone(),
+                    created_at: message.created_at,
+                    distance,
+                });
+            }
+        }
+
+        reposts.sort_by_key(|r| (r.distance, r.created_at));
+        reposts.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(reposts)
+    }
+
+    async fn get_ocr_cache(
+        &self,
+        hash: &[u8],
+        language: &str,
+        engine_version: i32,
+    ) -> Result<Option<String>, Error> {
+        Ok(self
+            .inner
+            .lock()
+            .ocr_cache
+            .get(&(hash.to_vec(), language.to_owned()))
+            .filter(|(version, ..)| *version == engine_version)
+            .map(|(_, text, _)| text.clone()))
+    }
+
+    async fn insert_ocr_cache(
+        &self,
+        hash: &[u8],
+        language: &str,
+        engine_version: i32,
+        text: &str,
+    ) -> Result<(), Error> {
+        self.inner.lock().ocr_cache.insert(
+            (hash.to_vec(), language.to_owned()),
+            (
+                engine_version,
+                text.to_owned(),
+                chrono::Utc::now().timestamp(),
+            ),
+        );
+        Ok(())
+    }
+
+    async fn count_ocr_cache(&self) -> Result<i64, Error> {
+        Ok(self.inner.lock().ocr_cache.len() as i64)
+    }
+
+    async fn purge_ocr_cache(&self) -> Result<u64, Error> {
+        let mut inner = self.inner.lock();
+        let count = inner.ocr_cache.len() as u64;
+        inner.ocr_cache.clear();
+        Ok(count)
+    }
+
+    async fn prune_ocr_cache(&self, before: i64, engine_version: i32) -> Result<u64, Error> {
+        let mut inner = self.inner.lock();
+        let count = inner.ocr_cache.len();
+        inner.ocr_cache.retain(|_, (version, _, created_at)| {
+            *created_at >= before && *version == engine_version
+        });
+        Ok((count - inner.ocr_cache.len()) as u64)
+    }
+
+    async fn get_regexes(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<Vec<(Option<GenericChannelId>, RegexData, u64)>, Error> {
+        let mut regexes = self
+            .inner
+            .lock()
+            .regexes
+            .values()
+            .filter(|(guild, ..)| *guild == guild_id)
+            .map(|(_, channel_id, regex, suppressed)| (*channel_id, regex.clone(), *suppressed))
+            .collect::<Vec<_>>();
+        regexes.sort_by_key(|(_, regex, _)| regex.id);
+        Ok(regexes)
+    }
+
+    async fn insert_regex(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<GenericChannelId>,
+        regex: &RegexData,
+    ) -> Result<i32, Error> {
+        let mut inner = self.inner.lock();
+        inner.guilds.insert(guild_id);
+        // ids start at 1, like SERIAL.
+        inner.next_regex_id += 1;
+        let id = inner.next_regex_id;
+
+        let mut regex = regex.clone();
+        regex.id = id;
+        inner.regexes.insert(id, (guild_id, channel_id, regex, 0));
+        Ok(id)
+    }
+
+    async fn update_regex(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<GenericChannelId>,
+        regex: &RegexData,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        let entry = inner.regexes.get_mut(&regex.id);
+        if let Some(entry) = entry.filter(|(guild, ..)| *guild == guild_id) {
+            entry.1 = channel_id;
+            entry.2 = regex.clone();
+        }
+        Ok(())
+    }
+
+    async fn delete_regex(
+        &self,
+        guild_id: GuildId,
+        id: i32,
+    ) -> Result<Option<Option<GenericChannelId>>, Error> {
+        let mut inner = self.inner.lock();
+        if inner
+            .regexes
+            .get(&id)
+            .is_none_or(|(guild, ..)| *guild != guild_id)
+        {
+            return Ok(None);
+        }
+
+        Ok(inner
+            .regexes
+            .remove(&id)
+            .map(|(_, channel_id, ..)| channel_id))
+    }
+
+    async fn add_suppressed(&self, counts: &[(i32, u64)]) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        for (id, count) in counts {
+            if let Some(entry) = inner.regexes.get_mut(id) {
+                entry.3 += count;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_dm_activity(&self, user_id: UserId) -> Result<Option<DmActivity>, Error> {
+        Ok(self.inner.lock().dm_activity.get(&user_id).copied())
+    }
+
+    async fn update_dm_activity(
+        &self,
+        user_id: UserId,
+        until: i64,
+        count: i16,
+    ) -> Result<(), Error> {
+        if let Some(activity) = self.inner.lock().dm_activity.get_mut(&user_id) {
+            activity.until = Some(until);
+            activity.count = count;
+        }
+        Ok(())
+    }
+
+    async fn upsert_dm_activity(&self, user_id: UserId, activity: DmActivity) -> Result<(), Error> {
+        self.inner.lock().dm_activity.insert(user_id, activity);
+        Ok(())
+    }
+
+    async fn clear_dm_activity_until(&self, user_id: UserId) -> Result<(), Error> {
+        if let Some(activity) = self.inner.lock().dm_activity.get_mut(&user_id) {
+            activity.until = None;
+        }
+        Ok(())
+    }
+
+    async fn get_banned_users(&self) -> Result<Vec<UserId>, Error> {
+        Ok(self.inner.lock().banned_users.iter().copied().collect())
+    }
+
+    async fn set_banned(&self, user_id: UserId, banned: bool) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        if banned {
+            inner.users.insert(user_id);
+            inner.banned_users.insert(user_id);
+        } else {
+            inner.banned_users.remove(&user_id);
+        }
+        Ok(())
+    }
+
+    async fn get_owner_access(&self) -> Result<Vec<OwnerAccess>, Error> {
+        Ok(self.inner.lock().owner_access.clone())
+    }
+
+    async fn insert_owner_access(
+        &self,
+        user_id: UserId,
+        command: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut inner = self.inner.lock();
+        inner.users.insert(user_id);
+        inner.owner_access.push(OwnerAccess {
+            user_id,
+            command: command.map(ToString::to_string),
+        });
+        Ok(())
+    }
+
+    async fn remove_owner_access(
+        &self,
+        user_id: UserId,
+        command: Option<&str>,
+    ) -> Result<(), Error> {
+        self.inner.lock().owner_access.retain(|access| {
+            access.user_id != user_id
+                || command.is_some_and(|c| access.command.as_deref() != Some(c))
+        });
+        Ok(())
+    }
+}
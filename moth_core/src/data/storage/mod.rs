@@ -0,0 +1,312 @@
+//! Storage backends for the data the bot persists.
+//!
+//! Everything the event handlers and the [`Database`](super::database::Database) caches need
+//! goes through [`Storage`], so the handlers can run against [`MemoryStorage`] without a live
+//! Postgres instance.
+
+mod memory;
+mod postgres;
+
+pub use memory::MemoryStorage;
+pub use postgres::PgStorage;
+
+use lumi::serenity_prelude as serenity;
+use serenity::{AttachmentId, EmojiId, GenericChannelId, GuildId, MessageId, StickerId, UserId};
+
+use crate::data::automod::{AutomodAction, AutomodRecord};
+use crate::data::database::{EmoteUsageType, StarboardMessage};
+use crate::data::images::{BlockedImage, ImageFilterConfig, Repost};
+use crate::data::modlog::ModlogConfig;
+use crate::data::names::NameFilterActions;
+use crate::data::responses::RegexData;
+use crate::data::structs::{DmActivity, Error};
+use moth_filter::{Tier, TrieOverride, WordList};
+
+/// A message as it gets written on creation.
+#[derive(Clone, Debug)]
+pub struct NewMessage {
+    pub message_id: MessageId,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: GenericChannelId,
+    pub user_id: UserId,
+    pub content: String,
+    pub created_at: i64,
+    /// Serialized embeds, only present when the message had any.
+    pub embeds: Option<serde_json::Value>,
+    pub attachments: Vec<NewAttachment>,
+    pub stickers: Vec<NewSticker>,
+    /// Custom and unicode emotes used in the content.
+    pub emotes: Vec<Emote>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewAttachment {
+    pub attachment_id: AttachmentId,
+    pub file_name: String,
+    pub file_size: u32,
+    pub file_url: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewSticker {
+    pub sticker_id: StickerId,
+    pub name: String,
+}
+
+/// An emote, `discord_id` is `None` for unicode emojis.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Emote {
+    pub name: String,
+    pub discord_id: Option<EmojiId>,
+}
+
+/// A single use of an emote outside of message content, such as a reaction.
+#[derive(Clone, Debug)]
+pub struct EmoteUsage {
+    pub emote: Emote,
+    pub message_id: MessageId,
+    pub user_id: UserId,
+    pub channel_id: GenericChannelId,
+    pub guild_id: GuildId,
+    pub used_at: i64,
+    pub usage_type: EmoteUsageType,
+}
+
+/// A snapshot of message content at the time of an edit or a deletion.
+#[derive(Clone, Debug)]
+pub struct MessageSnapshot {
+    pub message_id: MessageId,
+    pub channel_id: GenericChannelId,
+    pub guild_id: Option<GuildId>,
+    pub user_id: UserId,
+    pub content: String,
+    pub timestamp: i64,
+}
+
+/// A row of `owner_access`, `command` is `None` when access covers every owner command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnerAccess {
+    pub user_id: UserId,
+    pub command: Option<String>,
+}
+
+#[serenity::async_trait]
+pub trait Storage: Send + Sync {
+    async fn insert_user(&self, user_id: UserId) -> Result<(), Error>;
+    async fn insert_guild(&self, guild_id: GuildId) -> Result<(), Error>;
+    /// Inserts the channel, and the guild if one is provided.
+    async fn insert_channel(
+        &self,
+        channel_id: GenericChannelId,
+        guild_id: Option<GuildId>,
+    ) -> Result<(), Error>;
+
+    /// Inserts a message along with its embeds, attachments, stickers and emotes.
+    async fn insert_message(&self, message: &NewMessage) -> Result<(), Error>;
+    async fn insert_edit(&self, edit: &MessageSnapshot) -> Result<(), Error>;
+    async fn insert_deletion(&self, deletion: &MessageSnapshot) -> Result<(), Error>;
+
+    /// Inserts the emote if needed and records the usage.
+    async fn insert_emote_usage(&self, usage: &EmoteUsage) -> Result<(), Error>;
+
+    async fn get_starboard(&self, message_id: MessageId) -> Result<StarboardMessage, Error>;
+    async fn get_starboard_by_starboard_id(
+        &self,
+        starboard_message_id: MessageId,
+    ) -> Result<StarboardMessage, Error>;
+    async fn get_all_starboard(&self) -> Result<Vec<StarboardMessage>, Error>;
+    /// Inserts a starboard entry, returning its id.
+    async fn insert_starboard(&self, message: &StarboardMessage) -> Result<i32, Error>;
+    async fn update_star_count(&self, id: i32, count: i16) -> Result<(), Error>;
+    async fn approve_starboard(
+        &self,
+        starboard_message_id: MessageId,
+        new_message_id: MessageId,
+        new_channel_id: GenericChannelId,
+    ) -> Result<(), Error>;
+    async fn deny_starboard(&self, starboard_message_id: MessageId) -> Result<(), Error>;
+    async fn get_starboard_overrides(&self) -> Result<Vec<(GenericChannelId, u8)>, Error>;
+    async fn set_starboard_override(
+        &self,
+        channel_id: GenericChannelId,
+        star_count: u8,
+    ) -> Result<(), Error>;
+    /// Returns if an override was removed.
+    async fn remove_starboard_override(&self, channel_id: GenericChannelId) -> Result<bool, Error>;
+
+    async fn get_modlog_configs(&self) -> Result<Vec<(GuildId, ModlogConfig)>, Error>;
+    async fn set_modlog_config(&self, guild_id: GuildId, config: ModlogConfig)
+    -> Result<(), Error>;
+    /// Returns if the guild had a modlog channel.
+    async fn remove_modlog_config(&self, guild_id: GuildId) -> Result<bool, Error>;
+
+    async fn get_filter_words(&self) -> Result<Vec<(GuildId, WordList, String)>, Error>;
+    /// Returns if the word wasn't already on the list.
+    async fn add_filter_word(
+        &self,
+        guild_id: GuildId,
+        list: WordList,
+        word: &str,
+    ) -> Result<bool, Error>;
+    /// Returns if the word was on the list.
+    async fn remove_filter_word(
+        &self,
+        guild_id: GuildId,
+        list: WordList,
+        word: &str,
+    ) -> Result<bool, Error>;
+    async fn get_filter_overrides(&self) -> Result<Vec<(GuildId, String, TrieOverride)>, Error>;
+    async fn set_filter_override(
+        &self,
+        guild_id: GuildId,
+        word: &str,
+        kind: TrieOverride,
+    ) -> Result<(), Error>;
+    /// Returns if the word had an override.
+    async fn remove_filter_override(&self, guild_id: GuildId, word: &str) -> Result<bool, Error>;
+
+    async fn get_automod_tiers(&self) -> Result<Vec<(GuildId, Tier, AutomodAction)>, Error>;
+    async fn set_automod_tier(
+        &self,
+        guild_id: GuildId,
+        tier: Tier,
+        action: AutomodAction,
+    ) -> Result<(), Error>;
+    /// Returns if the tier had an action.
+    async fn remove_automod_tier(&self, guild_id: GuildId, tier: Tier) -> Result<bool, Error>;
+    /// Returns the id of the new row.
+    async fn insert_automod_record(&self, record: &AutomodRecord) -> Result<i32, Error>;
+    async fn get_automod_record(&self, id: i32) -> Result<Option<AutomodRecord>, Error>;
+
+    async fn get_name_filters(&self) -> Result<Vec<(GuildId, NameFilterActions)>, Error>;
+    async fn set_name_filter(
+        &self,
+        guild_id: GuildId,
+        actions: NameFilterActions,
+    ) -> Result<(), Error>;
+    /// Returns if the guild had the name filter enabled.
+    async fn remove_name_filter(&self, guild_id: GuildId) -> Result<bool, Error>;
+
+    async fn get_ocr_languages(&self) -> Result<Vec<(GuildId, String)>, Error>;
+    async fn set_ocr_language(&self, guild_id: GuildId, language: &str) -> Result<(), Error>;
+    /// Returns if the guild had a language set.
+    async fn remove_ocr_language(&self, guild_id: GuildId) -> Result<bool, Error>;
+
+    async fn get_ocr_filters(&self) -> Result<Vec<GuildId>, Error>;
+    async fn set_ocr_filter(&self, guild_id: GuildId) -> Result<(), Error>;
+    /// Returns if the guild had the OCR filter enabled.
+    async fn remove_ocr_filter(&self, guild_id: GuildId) -> Result<bool, Error>;
+
+    async fn get_image_filters(&self) -> Result<Vec<(GuildId, ImageFilterConfig)>, Error>;
+    async fn set_image_filter(
+        &self,
+        guild_id: GuildId,
+        config: ImageFilterConfig,
+    ) -> Result<(), Error>;
+    /// Returns if the guild had the image filter enabled.
+    async fn remove_image_filter(&self, guild_id: GuildId) -> Result<bool, Error>;
+
+    async fn get_image_blocklist(&self) -> Result<Vec<(GuildId, BlockedImage)>, Error>;
+    /// Adds the image, or relabels it if the guild already blocked the same hash, returning its
+    /// id.
+    async fn insert_blocked_image(
+        &self,
+        guild_id: GuildId,
+        image: &BlockedImage,
+    ) -> Result<i32, Error>;
+    /// Returns if the image was in the guild's blocklist.
+    async fn remove_blocked_image(&self, guild_id: GuildId, id: i32) -> Result<bool, Error>;
+
+    async fn insert_image_hash(
+        &self,
+        attachment_id: AttachmentId,
+        guild_id: GuildId,
+        hash: u64,
+    ) -> Result<(), Error>;
+    /// Logged attachments of the guild within `max_distance` of `hash`, closest first.
+    async fn find_reposts(
+        &self,
+        guild_id: GuildId,
+        hash: u64,
+        max_distance: u32,
+        limit: i64,
+    ) -> Result<Vec<Repost>, Error>;
+
+    /// The cached text of an image, if it was read by `engine_version`.
+    async fn get_ocr_cache(
+        &self,
+        hash: &[u8],
+        language: &str,
+        engine_version: i32,
+    ) -> Result<Option<String>, Error>;
+    async fn insert_ocr_cache(
+        &self,
+        hash: &[u8],
+        language: &str,
+        engine_version: i32,
+        text: &str,
+    ) -> Result<(), Error>;
+    async fn count_ocr_cache(&self) -> Result<i64, Error>;
+    /// Returns how many entries were removed.
+    async fn purge_ocr_cache(&self) -> Result<u64, Error>;
+    /// Removes entries created before `before`, or read by an engine other than `engine_version`.
+    /// Returns how many entries were removed.
+    async fn prune_ocr_cache(&self, before: i64, engine_version: i32) -> Result<u64, Error>;
+
+    /// Every regex response of the guild, with the channel or category it's scoped to and how
+    /// many of its matches were suppressed.
+    async fn get_regexes(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<Vec<(Option<GenericChannelId>, RegexData, u64)>, Error>;
+    /// Inserts the regex response along with its exceptions and response, returning its id.
+    async fn insert_regex(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<GenericChannelId>,
+        regex: &RegexData,
+    ) -> Result<i32, Error>;
+    /// Overwrites the regex response with the id of `regex`.
+    async fn update_regex(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<GenericChannelId>,
+        regex: &RegexData,
+    ) -> Result<(), Error>;
+    /// Returns the channel the removed regex response was scoped to, or `None` if the guild had
+    /// no regex response with this id.
+    async fn delete_regex(
+        &self,
+        guild_id: GuildId,
+        id: i32,
+    ) -> Result<Option<Option<GenericChannelId>>, Error>;
+    /// Adds each count to how many matches of the regex response with that id were suppressed.
+    async fn add_suppressed(&self, counts: &[(i32, u64)]) -> Result<(), Error>;
+
+    async fn get_dm_activity(&self, user_id: UserId) -> Result<Option<DmActivity>, Error>;
+    /// Updates `until` and `count` without touching `last_announced`.
+    async fn update_dm_activity(
+        &self,
+        user_id: UserId,
+        until: i64,
+        count: i16,
+    ) -> Result<(), Error>;
+    async fn upsert_dm_activity(&self, user_id: UserId, activity: DmActivity) -> Result<(), Error>;
+    async fn clear_dm_activity_until(&self, user_id: UserId) -> Result<(), Error>;
+
+    async fn get_banned_users(&self) -> Result<Vec<UserId>, Error>;
+    async fn set_banned(&self, user_id: UserId, banned: bool) -> Result<(), Error>;
+
+    async fn get_owner_access(&self) -> Result<Vec<OwnerAccess>, Error>;
+    async fn insert_owner_access(
+        &self,
+        user_id: UserId,
+        command: Option<&str>,
+    ) -> Result<(), Error>;
+    /// Removes access to `command`, or every row for the user when `command` is `None`.
+    async fn remove_owner_access(
+        &self,
+        user_id: UserId,
+        command: Option<&str>,
+    ) -> Result<(), Error>;
+}
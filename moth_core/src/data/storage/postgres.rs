@@ -0,0 +1,1508 @@
+use std::collections::{HashMap, HashSet};
+
+use lumi::serenity_prelude as serenity;
+use regex::Regex;
+use serenity::small_fixed_array::FixedString;
+use serenity::{AttachmentId, GenericChannelId, GuildId, MessageId, ReactionType, UserId};
+use sqlx::{PgPool, query};
+
+use super::{EmoteUsage, MessageSnapshot, NewMessage, OwnerAccess, Storage};
+use crate::data::automod::{AutomodAction, AutomodRecord};
+use crate::data::database::{EmoteUsageType, StarboardMessage, StarboardStatus};
+use crate::data::images::{BlockedImage, ImageAction, ImageFilterConfig, Repost};
+use crate::data::modlog::ModlogConfig;
+use crate::data::names::NameFilterActions;
+use crate::data::responses::{Cooldown, RegexData, ResponseType};
+use crate::data::structs::{DmActivity, Error};
+use moth_filter::{Tier, TrieOverride, WordList};
+
+/// The production backend.
+pub struct PgStorage {
+    pub(crate) pool: PgPool,
+}
+
+impl PgStorage {
+    #[must_use]
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[serenity::async_trait]
+impl Storage for PgStorage {
+    async fn insert_user(&self, user_id: UserId) -> Result<(), Error> {
+        query!(
+            "INSERT INTO users (user_id)
+            VALUES ($1)
+            ON CONFLICT (user_id) DO NOTHING",
+            user_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_guild(&self, guild_id: GuildId) -> Result<(), Error> {
+        query!(
+            "INSERT INTO guilds (guild_id)
+             VALUES ($1)
+             ON CONFLICT (guild_id) DO NOTHING",
+            guild_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_channel(
+        &self,
+        channel_id: GenericChannelId,
+        guild_id: Option<GuildId>,
+    ) -> Result<(), Error> {
+        if let Some(guild_id) = guild_id {
+            self.insert_guild(guild_id).await?;
+        }
+
+        query!(
+            "INSERT INTO channels (channel_id, guild_id)
+             VALUES ($1, $2)
+             ON CONFLICT (channel_id) DO NOTHING",
+            channel_id.get() as i64,
+            guild_id.map(|g| g.get() as i64),
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_message(&self, message: &NewMessage) -> Result<(), Error> {
+        let guild_id = message.guild_id.map(|g| g.get() as i64);
+        let channel_id = message.channel_id.get() as i64;
+        let user_id = message.user_id.get() as i64;
+        let message_id = message.message_id.get() as i64;
+
+        self.insert_channel(message.channel_id, message.guild_id)
+            .await?;
+        self.insert_user(message.user_id).await?;
+
+        let mut transaction = self.pool.begin().await?;
+
+        query!(
+            "INSERT INTO messages (message_id, guild_id, channel_id, user_id, content, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+            message_id,
+            guild_id,
+            channel_id,
+            user_id,
+            &message.content,
+            message.created_at
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        if let Some(embeds) = &message.embeds {
+            query!(
+                "INSERT INTO embeds (message_id, embed_data)
+             VALUES ($1, $2)
+             ON CONFLICT (message_id) DO NOTHING",
+                message_id,
+                embeds
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for attachment in &message.attachments {
+            query!(
+                "INSERT INTO attachments (attachment_id, message_id, file_name, file_size, file_url)
+             VALUES ($1, $2, $3, $4, $5)",
+                attachment.attachment_id.get() as i64,
+                message_id,
+                &attachment.file_name,
+                attachment.file_size as i32,
+                &attachment.file_url
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for sticker in &message.stickers {
+            let sticker_id = sticker.sticker_id.get() as i64;
+            query!(
+                "INSERT INTO stickers (sticker_id, sticker_name) VALUES ($1, $2) ON CONFLICT \
+                 (sticker_id) DO NOTHING",
+                sticker_id,
+                &sticker.name
+            )
+            .execute(&mut *transaction)
+            .await?;
+
+            query!(
+                "INSERT INTO sticker_usage (message_id, user_id, channel_id, guild_id, \
+                 sticker_id) VALUES ($1, $2, $3, $4, $5)",
+                message_id,
+                user_id,
+                channel_id,
+                guild_id,
+                sticker_id
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for emote in &message.emotes {
+            let id = if let Some(discord_id) = emote.discord_id {
+                query!(
+                    "INSERT INTO emotes (emote_name, discord_id) VALUES ($1, $2) ON CONFLICT \
+                     (emote_name, discord_id) DO UPDATE SET emote_name = EXCLUDED.emote_name \
+                     RETURNING id",
+                    &emote.name,
+                    discord_id.get() as i64
+                )
+                .fetch_one(&mut *transaction)
+                .await?
+                .id
+            } else {
+                query!(
+                    "INSERT INTO emotes (emote_name, discord_id)
+                 VALUES ($1, NULL)
+                 ON CONFLICT (emote_name) WHERE discord_id IS NULL
+                 DO UPDATE SET discord_id = emotes.discord_id
+                 RETURNING id",
+                    &emote.name
+                )
+                .fetch_one(&mut *transaction)
+                .await?
+                .id
+            };
+
+            query!(
+                "INSERT INTO emote_usage (message_id, emote_id, user_id, channel_id, guild_id,
+                 used_at, usage_type) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                message_id,
+                i64::from(id),
+                user_id,
+                channel_id,
+                guild_id,
+                message.created_at,
+                EmoteUsageType::Message as _,
+            )
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn insert_edit(&self, edit: &MessageSnapshot) -> Result<(), Error> {
+        self.insert_channel(edit.channel_id, edit.guild_id).await?;
+        self.insert_user(edit.user_id).await?;
+
+        query!(
+            "INSERT INTO message_edits (message_id, channel_id, guild_id, user_id, content, \
+             edited_at) VALUES ($1, $2, $3, $4, $5, $6)",
+            edit.message_id.get() as i64,
+            edit.channel_id.get() as i64,
+            edit.guild_id.map(|g| g.get() as i64),
+            edit.user_id.get() as i64,
+            &edit.content,
+            edit.timestamp
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_deletion(&self, deletion: &MessageSnapshot) -> Result<(), Error> {
+        self.insert_channel(deletion.channel_id, deletion.guild_id)
+            .await?;
+        self.insert_user(deletion.user_id).await?;
+
+        query!(
+            "INSERT INTO message_deletion (message_id, channel_id, guild_id, user_id, content, \
+             deleted_at) VALUES ($1, $2, $3, $4, $5, $6)",
+            deletion.message_id.get() as i64,
+            deletion.channel_id.get() as i64,
+            deletion.guild_id.map(|g| g.get() as i64),
+            deletion.user_id.get() as i64,
+            &deletion.content,
+            deletion.timestamp
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_emote_usage(&self, usage: &EmoteUsage) -> Result<(), Error> {
+        self.insert_channel(usage.channel_id, Some(usage.guild_id))
+            .await?;
+        self.insert_user(usage.user_id).await?;
+
+        let id = if let Some(id) = usage.emote.discord_id {
+            query!(
+                "INSERT INTO emotes (emote_name, discord_id) VALUES ($1, $2) ON CONFLICT \
+                 (discord_id) DO UPDATE SET emote_name = EXCLUDED.emote_name RETURNING id",
+                &usage.emote.name,
+                id.get() as i64
+            )
+            .fetch_one(&self.pool)
+            .await?
+            .id
+        } else {
+            query!(
+                "INSERT INTO emotes (emote_name)
+                     VALUES ($1)
+                     ON CONFLICT (emote_name) WHERE discord_id IS NULL
+                     DO UPDATE SET discord_id = emotes.discord_id
+                     RETURNING id",
+                &usage.emote.name,
+            )
+            .fetch_one(&self.pool)
+            .await?
+            .id
+        };
+
+        query!(
+            "INSERT INTO emote_usage (emote_id, message_id, user_id, channel_id, guild_id,
+    used_at, usage_type) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            i64::from(id),
+            usage.message_id.get() as i64,
+            usage.user_id.get() as i64,
+            usage.channel_id.get() as i64,
+            usage.guild_id.get() as i64,
+            usage.used_at,
+            usage.usage_type.clone() as _,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_starboard(&self, message_id: MessageId) -> Result<StarboardMessage, Error> {
+        Ok(sqlx::query_as!(StarboardMessage,
+        r#"
+        SELECT id, user_id, username, avatar_url, content, channel_id, message_id, attachment_urls, star_count, starboard_message_id, starboard_message_channel, starboard_status as "starboard_status: StarboardStatus", reply_message_id, forwarded, reply_username
+        FROM starboard
+        WHERE message_id = $1
+        "#, message_id.get() as i64)
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    async fn get_starboard_by_starboard_id(
+        &self,
+        starboard_message_id: MessageId,
+    ) -> Result<StarboardMessage, Error> {
+        Ok(sqlx::query_as!(StarboardMessage,
+        r#"
+        SELECT id, user_id, username, avatar_url, content, channel_id, message_id, attachment_urls, star_count, starboard_message_id, starboard_message_channel, starboard_status as "starboard_status: StarboardStatus", reply_message_id, forwarded, reply_username
+        FROM starboard
+        WHERE starboard_message_id = $1
+        "#, starboard_message_id.get() as i64)
+            .fetch_one(&self.pool)
+            .await?)
+    }
+
+    async fn get_all_starboard(&self) -> Result<Vec<StarboardMessage>, Error> {
+        Ok(sqlx::query_as!(StarboardMessage,
+            r#"
+            SELECT id, user_id, username, avatar_url, content, channel_id, message_id, attachment_urls, star_count, starboard_message_id, starboard_message_channel, starboard_status as "starboard_status: StarboardStatus", reply_message_id, forwarded, reply_username
+            FROM starboard"#)
+                .fetch_all(&self.pool)
+                .await?)
+    }
+
+    async fn insert_starboard(&self, m: &StarboardMessage) -> Result<i32, Error> {
+        let val = sqlx::query!(
+            r#"
+                INSERT INTO starboard (
+                    user_id, username, avatar_url, content, channel_id, message_id,
+                    attachment_urls, star_count, starboard_status,
+                    starboard_message_id, starboard_message_channel, forwarded, reply_message_id, reply_username
+                )
+                VALUES (
+                    $1, $2, $3, $4, $5, $6,
+                    $7, $8, $9, $10, $11,
+                    $12, $13, $14
+                ) RETURNING id
+                "#,
+            m.user_id.get() as i64,
+            m.username,
+            m.avatar_url,
+            m.content,
+            m.channel_id.get() as i64,
+            m.message_id.get() as i64,
+            &m.attachment_urls,
+            m.star_count,
+            m.starboard_status.clone() as _,
+            m.starboard_message_id.get() as i64,
+            m.starboard_message_channel.get() as i64,
+            m.forwarded,
+            m.reply_message_id.map(|m| m.get() as i64),
+            m.reply_username
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(val.id)
+    }
+
+    async fn update_star_count(&self, id: i32, count: i16) -> Result<(), Error> {
+        query!(
+            "UPDATE starboard SET star_count = $1 WHERE id = $2",
+            count,
+            id,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn approve_starboard(
+        &self,
+        starboard_message_id: MessageId,
+        new_message_id: MessageId,
+        new_channel_id: GenericChannelId,
+    ) -> Result<(), Error> {
+        let status = StarboardStatus::Accepted;
+
+        query!(
+            "UPDATE starboard SET starboard_status = $1, starboard_message_id = $2, \
+             starboard_message_channel = $3 WHERE starboard_message_id = $4",
+            status as _,
+            new_message_id.get() as i64,
+            new_channel_id.get() as i64,
+            starboard_message_id.get() as i64,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn deny_starboard(&self, starboard_message_id: MessageId) -> Result<(), Error> {
+        let status = StarboardStatus::Denied;
+
+        query!(
+            "UPDATE starboard SET starboard_status = $1 WHERE starboard_message_id = $2",
+            status as _,
+            starboard_message_id.get() as i64,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_starboard_overrides(&self) -> Result<Vec<(GenericChannelId, u8)>, Error> {
+        let results = sqlx::query!("SELECT * FROM starboard_overrides")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| {
+                (
+                    GenericChannelId::new(r.channel_id as u64),
+                    r.star_count as u8,
+                )
+            })
+            .collect())
+    }
+
+    async fn set_starboard_override(
+        &self,
+        channel_id: GenericChannelId,
+        star_count: u8,
+    ) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO starboard_overrides (channel_id, star_count)
+            VALUES ($1, $2)
+            ON CONFLICT (channel_id) DO UPDATE
+            SET star_count = EXCLUDED.star_count
+            "#,
+            channel_id.get() as i64,
+            i16::from(star_count)
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_starboard_override(&self, channel_id: GenericChannelId) -> Result<bool, Error> {
+        let result = sqlx::query!(
+            "DELETE FROM starboard_overrides WHERE channel_id = $1",
+            channel_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn get_modlog_configs(&self) -> Result<Vec<(GuildId, ModlogConfig)>, Error> {
+        let results = query!("SELECT guild_id, channel_id, events FROM modlog_channels")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| {
+                (
+                    GuildId::new(r.guild_id as u64),
+                    ModlogConfig {
+                        channel_id: GenericChannelId::new(r.channel_id as u64),
+                        events: (r.events as u32).into(),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    async fn set_modlog_config(
+        &self,
+        guild_id: GuildId,
+        config: ModlogConfig,
+    ) -> Result<(), Error> {
+        self.insert_guild(guild_id).await?;
+
+        query!(
+            r#"
+            INSERT INTO modlog_channels (guild_id, channel_id, events)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET channel_id = EXCLUDED.channel_id, events = EXCLUDED.events
+            "#,
+            guild_id.get() as i64,
+            config.channel_id.get() as i64,
+            config.events.bits() as i32
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_modlog_config(&self, guild_id: GuildId) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM modlog_channels WHERE guild_id = $1",
+            guild_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn get_filter_words(&self) -> Result<Vec<(GuildId, WordList, String)>, Error> {
+        let results = query!("SELECT guild_id, list, word FROM filter_words")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .filter_map(|r| {
+                let list = WordList::from_name(&r.list)?;
+                Some((GuildId::new(r.guild_id as u64), list, r.word))
+            })
+            .collect())
+    }
+
+    async fn add_filter_word(
+        &self,
+        guild_id: GuildId,
+        list: WordList,
+        word: &str,
+    ) -> Result<bool, Error> {
+        self.insert_guild(guild_id).await?;
+
+        let result = query!(
+            "INSERT INTO filter_words (guild_id, list, word)
+             VALUES ($1, $2, $3)
+             ON CONFLICT DO NOTHING",
+            guild_id.get() as i64,
+            list.name(),
+            word
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn remove_filter_word(
+        &self,
+        guild_id: GuildId,
+        list: WordList,
+        word: &str,
+    ) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM filter_words WHERE guild_id = $1 AND list = $2 AND word = $3",
+            guild_id.get() as i64,
+            list.name(),
+            word
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn get_filter_overrides(&self) -> Result<Vec<(GuildId, String, TrieOverride)>, Error> {
+        let results = query!("SELECT guild_id, word, kind FROM filter_overrides")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .filter_map(|r| {
+                let kind = TrieOverride::from_name(&r.kind)?;
+                Some((GuildId::new(r.guild_id as u64), r.word, kind))
+            })
+            .collect())
+    }
+
+    async fn set_filter_override(
+        &self,
+        guild_id: GuildId,
+        word: &str,
+        kind: TrieOverride,
+    ) -> Result<(), Error> {
+        self.insert_guild(guild_id).await?;
+
+        query!(
+            "INSERT INTO filter_overrides (guild_id, word, kind)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (guild_id, word) DO UPDATE SET kind = EXCLUDED.kind",
+            guild_id.get() as i64,
+            word,
+            kind.name()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_filter_override(&self, guild_id: GuildId, word: &str) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM filter_overrides WHERE guild_id = $1 AND word = $2",
+            guild_id.get() as i64,
+            word
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn get_automod_tiers(&self) -> Result<Vec<(GuildId, Tier, AutomodAction)>, Error> {
+        let results = query!("SELECT guild_id, tier, actions, timeout_secs FROM automod_tiers")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .filter_map(|r| {
+                let tier = Tier::from_name(&r.tier)?;
+                let action = AutomodAction::from_row(r.actions, r.timeout_secs);
+                Some((GuildId::new(r.guild_id as u64), tier, action))
+            })
+            .collect())
+    }
+
+    async fn set_automod_tier(
+        &self,
+        guild_id: GuildId,
+        tier: Tier,
+        action: AutomodAction,
+    ) -> Result<(), Error> {
+        self.insert_guild(guild_id).await?;
+
+        query!(
+            r#"
+            INSERT INTO automod_tiers (guild_id, tier, actions, timeout_secs)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (guild_id, tier) DO UPDATE
+            SET actions = EXCLUDED.actions, timeout_secs = EXCLUDED.timeout_secs
+            "#,
+            guild_id.get() as i64,
+            tier.name(),
+            action.flags.bits() as i32,
+            action.timeout_secs()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_automod_tier(&self, guild_id: GuildId, tier: Tier) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM automod_tiers WHERE guild_id = $1 AND tier = $2",
+            guild_id.get() as i64,
+            tier.name()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn insert_automod_record(&self, record: &AutomodRecord) -> Result<i32, Error> {
+        self.insert_guild(record.guild_id).await?;
+
+        let result = query!(
+            r#"
+            INSERT INTO automod_actions (guild_id, channel_id, message_id, user_id, tier, actions,
+                timeout_secs, words, content, analysis, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id
+            "#,
+            record.guild_id.get() as i64,
+            record.channel_id.get() as i64,
+            record.message_id.get() as i64,
+            record.user_id.get() as i64,
+            record.tier.name(),
+            record.action.flags.bits() as i32,
+            record.action.timeout_secs(),
+            &record.words,
+            record.content,
+            record.analysis,
+            record.created_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result.id)
+    }
+
+    async fn get_automod_record(&self, id: i32) -> Result<Option<AutomodRecord>, Error> {
+        let result = query!(
+            r#"
+            SELECT guild_id, channel_id, message_id, user_id, tier, actions, timeout_secs, words,
+                content, analysis, created_at
+            FROM automod_actions WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.and_then(|r| {
+            Some(AutomodRecord {
+                guild_id: GuildId::new(r.guild_id as u64),
+                channel_id: GenericChannelId::new(r.channel_id as u64),
+                message_id: MessageId::new(r.message_id as u64),
+                user_id: UserId::new(r.user_id as u64),
+                tier: Tier::from_name(&r.tier)?,
+                action: AutomodAction::from_row(r.actions, r.timeout_secs),
+                words: r.words,
+                content: r.content,
+                analysis: r.analysis,
+                created_at: r.created_at,
+            })
+        }))
+    }
+
+    async fn get_name_filters(&self) -> Result<Vec<(GuildId, NameFilterActions)>, Error> {
+        let results = query!("SELECT guild_id, actions FROM name_filter")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| (GuildId::new(r.guild_id as u64), (r.actions as u32).into()))
+            .collect())
+    }
+
+    async fn set_name_filter(
+        &self,
+        guild_id: GuildId,
+        actions: NameFilterActions,
+    ) -> Result<(), Error> {
+        self.insert_guild(guild_id).await?;
+
+        query!(
+            r#"
+            INSERT INTO name_filter (guild_id, actions)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET actions = EXCLUDED.actions
+            "#,
+            guild_id.get() as i64,
+            actions.bits() as i32
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_name_filter(&self, guild_id: GuildId) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM name_filter WHERE guild_id = $1",
+            guild_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn get_ocr_languages(&self) -> Result<Vec<(GuildId, String)>, Error> {
+        let results = query!("SELECT guild_id, language FROM ocr_languages")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| (GuildId::new(r.guild_id as u64), r.language))
+            .collect())
+    }
+
+    async fn set_ocr_language(&self, guild_id: GuildId, language: &str) -> Result<(), Error> {
+        self.insert_guild(guild_id).await?;
+
+        query!(
+            r#"
+            INSERT INTO ocr_languages (guild_id, language)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET language = EXCLUDED.language
+            "#,
+            guild_id.get() as i64,
+            language
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_ocr_language(&self, guild_id: GuildId) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM ocr_languages WHERE guild_id = $1",
+            guild_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn get_ocr_filters(&self) -> Result<Vec<GuildId>, Error> {
+        let results = query!("SELECT guild_id FROM ocr_filter")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| GuildId::new(r.guild_id as u64))
+            .collect())
+    }
+
+    async fn set_ocr_filter(&self, guild_id: GuildId) -> Result<(), Error> {
+        self.insert_guild(guild_id).await?;
+
+        query!(
+            "INSERT INTO ocr_filter (guild_id) VALUES ($1) ON CONFLICT DO NOTHING",
+            guild_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_ocr_filter(&self, guild_id: GuildId) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM ocr_filter WHERE guild_id = $1",
+            guild_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn get_image_filters(&self) -> Result<Vec<(GuildId, ImageFilterConfig)>, Error> {
+        let results = query!("SELECT guild_id, action, max_distance FROM image_filter")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results
+            .into_iter()
+            .filter_map(|r| {
+                let config = ImageFilterConfig {
+                    action: ImageAction::parse(&r.action)?,
+                    max_distance: r.max_distance as u32,
+                };
+                Some((GuildId::new(r.guild_id as u64), config))
+            })
+            .collect())
+    }
+
+    async fn set_image_filter(
+        &self,
+        guild_id: GuildId,
+        config: ImageFilterConfig,
+    ) -> Result<(), Error> {
+        self.insert_guild(guild_id).await?;
+
+        query!(
+            r#"
+            INSERT INTO image_filter (guild_id, action, max_distance)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET action = EXCLUDED.action, max_distance = EXCLUDED.max_distance
+            "#,
+            guild_id.get() as i64,
+            config.action.name(),
+            config.max_distance as i32
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove_image_filter(&self, guild_id: GuildId) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM image_filter WHERE guild_id = $1",
+            guild_id.get() as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn get_image_blocklist(&self) -> Result<Vec<(GuildId, BlockedImage)>, Error> {
+        let results =
+            query!("SELECT id, guild_id, hash, label, added_by, created_at FROM image_blocklist")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| {
+                let image = BlockedImage {
+                    id: r.id,
+                    hash: r.hash as u64,
+                    label: r.label,
+                    added_by: UserId::new(r.added_by as u64),
+                    created_at: r.created_at,
+                };
+                (GuildId::new(r.guild_id as u64), image)
+            })
+            .collect())
+    }
+
+    async fn insert_blocked_image(
+        &self,
+        guild_id: GuildId,
+        image: &BlockedImage,
+    ) -> Result<i32, Error> {
+        self.insert_guild(guild_id).await?;
+
+        let result = query!(
+            r#"
+            INSERT INTO image_blocklist (guild_id, hash, label, added_by, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (guild_id, hash) DO UPDATE
+            SET label = EXCLUDED.label
+            RETURNING id
+            "#,
+            guild_id.get() as i64,
+            image.hash as i64,
+            image.label,
+            image.added_by.get() as i64,
+            image.created_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result.id)
+    }
+
+    async fn remove_blocked_image(&self, guild_id: GuildId, id: i32) -> Result<bool, Error> {
+        let result = query!(
+            "DELETE FROM image_blocklist WHERE guild_id = $1 AND id = $2",
+            guild_id.get() as i64,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() != 0)
+    }
+
+    async fn insert_image_hash(
+        &self,
+        attachment_id: AttachmentId,
+        guild_id: GuildId,
+        hash: u64,
+    ) -> Result<(), Error> {
+        query!(
+            "INSERT INTO image_hashes (attachment_id, guild_id, hash) VALUES ($1, $2, $3) ON \
+             CONFLICT (attachment_id) DO NOTHING",
+            attachment_id.get() as i64,
+            guild_id.get() as i64,
+            hash as i64
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_reposts(
+        &self,
+        guild_id: GuildId,
+        hash: u64,
+        max_distance: u32,
+        limit: i64,
+    ) -> Result<Vec<Repost>, Error> {
+        let results = query!(
+            r#"
+            SELECT a.attachment_id, a.file_name, m.message_id, m.channel_id, m.user_id,
+                m.created_at, bit_count((h.hash # $2)::bit(64)) AS "distance!"
+            FROM image_hashes h
+            JOIN attachments a ON a.attachment_id = h.attachment_id
+            JOIN messages m ON m.message_id = a.message_id
+            WHERE h.guild_id = $1 AND bit_count((h.hash # $2)::bit(64)) <= $3
+            ORDER BY 7, m.created_at
+            LIMIT $4
+            "#,
+            guild_id.get() as i64,
+            hash as i64,
+            i64::from(max_distance),
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(results
+            .into_iter()
+            .filter_map(|r| {
+                Some(Repost {
+                    attachment_id: AttachmentId::new(r.attachment_id as u64),
+                    message_id: MessageId::new(r.message_id as u64),
+                    channel_id: GenericChannelId::new(r.channel_id? as u64),
+                    user_id: UserId::new(r.user_id? as u64),
+                    file_name: r.file_name.unwrap_or_default(),
+                    created_at: r.created_at.unwrap_or_default(),
+                    distance: r.distance as u32,
+                })
+            })
+            .collect())
+    }
+
+    async fn get_ocr_cache(
+        &self,
+        hash: &[u8],
+        language: &str,
+        engine_version: i32,
+    ) -> Result<Option<String>, Error> {
+        let result = query!(
+            "SELECT text FROM ocr_cache
+            WHERE hash = $1 AND language = $2 AND engine_version = $3",
+            hash,
+            language,
+            engine_version
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|r| r.text))
+    }
+
+    async fn insert_ocr_cache(
+        &self,
+        hash: &[u8],
+        language: &str,
+        engine_version: i32,
+        text: &str,
+    ) -> Result<(), Error> {
+        query!(
+            r#"
+            INSERT INTO ocr_cache (hash, language, engine_version, text, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (hash, language) DO UPDATE
+            SET engine_version = EXCLUDED.engine_version, text = EXCLUDED.text,
+                created_at = EXCLUDED.created_at
+            "#,
+            hash,
+            language,
+            engine_version,
+            text,
+            chrono::Utc::now().timestamp()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn count_ocr_cache(&self) -> Result<i64, Error> {
+        let result = query!("SELECT COUNT(*) AS count FROM ocr_cache")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(result.count.unwrap_or(0))
+    }
+
+    async fn purge_ocr_cache(&self) -> Result<u64, Error> {
+        let result = query!("DELETE FROM ocr_cache").execute(&self.pool).await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn prune_ocr_cache(&self, before: i64, engine_version: i32) -> Result<u64, Error> {
+        let result = query!(
+            "DELETE FROM ocr_cache WHERE created_at < $1 OR engine_version <> $2",
+            before,
+            engine_version
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_regexes(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<Vec<(Option<GenericChannelId>, RegexData, u64)>, Error> {
+        let records = query!(
+            "SELECT
+                r.id AS regex_id, r.channel_id, r.pattern,
+                r.recurse_channels, r.recurse_threads, r.detection_type, r.ocr_language,
+                r.cooldown_scope, r.cooldown_seconds, r.max_per_minute, r.suppressed,
+                resp.message, resp.emote_id
+            FROM regexes r
+            LEFT JOIN responses resp ON r.id = resp.regex_id
+            WHERE r.guild_id = $1",
+            guild_id.get() as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut regexes = Vec::with_capacity(records.len());
+        let mut emoji_cache: HashMap<i32, ReactionType> = HashMap::new();
+
+        for record in records {
+            let exceptions = query!(
+                "SELECT channel_id FROM regex_exceptions WHERE regex_id = $1",
+                record.regex_id
+            )
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(|row| GenericChannelId::new(row.channel_id as u64))
+            .collect::<HashSet<_>>();
+
+            let response = if let Some(message) = record.message {
+                ResponseType::Message(message)
+            } else if let Some(emote_id) = record.emote_id {
+                let reaction = if let Some(cached_reaction) = emoji_cache.get(&emote_id) {
+                    cached_reaction.clone()
+                } else {
+                    let emote = query!(
+                        "SELECT emote_name, discord_id FROM emotes WHERE id = $1",
+                        emote_id
+                    )
+                    .fetch_one(&self.pool)
+                    .await?;
+
+                    let reaction = if let Some(discord_id) = emote.discord_id {
+                        ReactionType::Custom {
+                            animated: false,
+                            id: serenity::EmojiId::new(discord_id as u64),
+                            name: Some(FixedString::from_static_trunc("_")),
+                        }
+                    } else {
+                        ReactionType::Unicode(FixedString::from_string_trunc(emote.emote_name))
+                    };
+
+                    emoji_cache.insert(emote_id, reaction.clone());
+
+                    reaction
+                };
+
+                ResponseType::Emoji(reaction)
+            } else {
+                ResponseType::Message("Default response".to_string())
+            };
+
+            let regex = RegexData {
+                id: record.regex_id,
+                pattern: Regex::new(&record.pattern)?,
+                recurse_channels: record.recurse_channels,
+                recurse_threads: record.recurse_threads,
+                response,
+                exceptions,
+                detection_type: (record.detection_type as u8).into(),
+                ocr_language: record.ocr_language,
+                cooldown: Cooldown {
+                    scope: record.cooldown_scope.into(),
+                    seconds: record.cooldown_seconds as u32,
+                    max_per_minute: record.max_per_minute.map(|m| m as u32),
+                },
+            };
+
+            let channel_id = record.channel_id.map(|c| GenericChannelId::new(c as u64));
+            regexes.push((channel_id, regex, record.suppressed as u64));
+        }
+
+        Ok(regexes)
+    }
+
+    async fn insert_regex(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<GenericChannelId>,
+        regex: &RegexData,
+    ) -> Result<i32, Error> {
+        let mut transaction = self.pool.begin().await?;
+        let id = query!(
+            "INSERT INTO regexes (guild_id, channel_id, pattern, recurse_channels, \
+             recurse_threads, detection_type, ocr_language, cooldown_scope, cooldown_seconds, \
+             max_per_minute)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             RETURNING id",
+            guild_id.get() as i64,
+            channel_id.map(|c| c.get() as i64),
+            regex.pattern.as_str(),
+            regex.recurse_channels,
+            regex.recurse_threads,
+            i16::from(regex.detection_type.bits()),
+            regex.ocr_language,
+            i16::from(regex.cooldown.scope),
+            regex.cooldown.seconds as i32,
+            regex.cooldown.max_per_minute.map(|m| m as i32)
+        )
+        .fetch_one(&mut *transaction)
+        .await?
+        .id;
+
+        insert_regex_details(&mut transaction, id, regex).await?;
+        transaction.commit().await?;
+
+        Ok(id)
+    }
+
+    async fn update_regex(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<GenericChannelId>,
+        regex: &RegexData,
+    ) -> Result<(), Error> {
+        let mut transaction = self.pool.begin().await?;
+        query!(
+            "UPDATE regexes SET channel_id = $3, pattern = $4, recurse_channels = $5, \
+             recurse_threads = $6, detection_type = $7, ocr_language = $8, cooldown_scope = $9, \
+             cooldown_seconds = $10, max_per_minute = $11
+             WHERE guild_id = $1 AND id = $2",
+            guild_id.get() as i64,
+            regex.id,
+            channel_id.map(|c| c.get() as i64),
+            regex.pattern.as_str(),
+            regex.recurse_channels,
+            regex.recurse_threads,
+            i16::from(regex.detection_type.bits()),
+            regex.ocr_language,
+            i16::from(regex.cooldown.scope),
+            regex.cooldown.seconds as i32,
+            regex.cooldown.max_per_minute.map(|m| m as i32)
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        query!("DELETE FROM regex_exceptions WHERE regex_id = $1", regex.id)
+            .execute(&mut *transaction)
+            .await?;
+        query!("DELETE FROM responses WHERE regex_id = $1", regex.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        insert_regex_details(&mut transaction, regex.id, regex).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_regex(
+        &self,
+        guild_id: GuildId,
+        id: i32,
+    ) -> Result<Option<Option<GenericChannelId>>, Error> {
+        let removed = query!(
+            "DELETE FROM regexes WHERE guild_id = $1 AND id = $2 RETURNING channel_id",
+            guild_id.get() as i64,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(removed.map(|r| r.channel_id.map(|c| GenericChannelId::new(c as u64))))
+    }
+
+    async fn add_suppressed(&self, counts: &[(i32, u64)]) -> Result<(), Error> {
+        let (ids, counts): (Vec<i32>, Vec<i64>) = counts
+            .iter()
+            .map(|(id, count)| (*id, *count as i64))
+            .unzip();
+
+        query!(
+            "UPDATE regexes SET suppressed = regexes.suppressed + c.count FROM UNNEST($1::INT4[], \
+             $2::INT8[]) AS c(id, count) WHERE regexes.id = c.id",
+            &ids,
+            &counts
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_dm_activity(&self, user_id: UserId) -> Result<Option<DmActivity>, Error> {
+        let result = sqlx::query!(
+            "SELECT last_announced, until, count FROM dm_activity WHERE user_id = $1",
+            i64::from(user_id)
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result.map(|record| {
+            DmActivity::new(
+                record.last_announced.unwrap(),
+                record.until,
+                record.count.unwrap(),
+            )
+        }))
+    }
+
+    async fn update_dm_activity(
+        &self,
+        user_id: UserId,
+        until: i64,
+        count: i16,
+    ) -> Result<(), Error> {
+        sqlx::query!(
+            "UPDATE dm_activity SET until = $1, count = $2 WHERE user_id = $3",
+            until,
+            count,
+            i64::from(user_id)
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn upsert_dm_activity(&self, user_id: UserId, activity: DmActivity) -> Result<(), Error> {
+        sqlx::query!(
+            "INSERT INTO dm_activity (user_id, last_announced, until, count)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id) DO UPDATE
+            SET last_announced = $2, until = $3, count = $4",
+            i64::from(user_id),
+            activity.last_announced,
+            activity.until,
+            activity.count
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn clear_dm_activity_until(&self, user_id: UserId) -> Result<(), Error> {
+        sqlx::query!(
+            "UPDATE dm_activity SET until = NULL WHERE user_id = $1",
+            i64::from(user_id)
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_banned_users(&self) -> Result<Vec<UserId>, Error> {
+        let user_ids = query!("SELECT user_id FROM banned_users")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(user_ids
+            .iter()
+            .map(|r| UserId::new(r.user_id as u64))
+            .collect())
+    }
+
+    async fn set_banned(&self, user_id: UserId, banned: bool) -> Result<(), Error> {
+        if banned {
+            self.insert_user(user_id).await?;
+            query!(
+                "INSERT INTO banned_users (user_id) VALUES ($1)",
+                user_id.get() as i64
+            )
+            .execute(&self.pool)
+            .await?;
+        } else {
+            query!(
+                "DELETE FROM banned_users WHERE user_id = $1",
+                user_id.get() as i64
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn get_owner_access(&self) -> Result<Vec<OwnerAccess>, Error> {
+        let db_checks = query!("SELECT * FROM owner_access")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(db_checks
+            .into_iter()
+            .map(|check| OwnerAccess {
+                user_id: UserId::new(check.user_id as u64),
+                command: check.command_name,
+            })
+            .collect())
+    }
+
+    async fn insert_owner_access(
+        &self,
+        user_id: UserId,
+        command: Option<&str>,
+    ) -> Result<(), Error> {
+        self.insert_user(user_id).await?;
+
+        if let Some(command) = command {
+            query!(
+                "INSERT INTO owner_access (user_id, command_name) VALUES ($1, $2)",
+                user_id.get() as i64,
+                command
+            )
+            .execute(&self.pool)
+            .await?;
+        } else {
+            query!(
+                "INSERT INTO owner_access (user_id) VALUES ($1)",
+                user_id.get() as i64
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn remove_owner_access(
+        &self,
+        user_id: UserId,
+        command: Option<&str>,
+    ) -> Result<(), Error> {
+        if let Some(command) = command {
+            query!(
+                "DELETE FROM owner_access WHERE user_id = $1 AND command_name = $2",
+                user_id.get() as i64,
+                command
+            )
+            .execute(&self.pool)
+            .await?;
+        } else {
+            query!(
+                "DELETE FROM owner_access WHERE user_id = $1",
+                user_id.get() as i64
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the exceptions and response of a regex, which live in their own tables.
+async fn insert_regex_details(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    id: i32,
+    regex: &RegexData,
+) -> Result<(), Error> {
+    for channel_id in &regex.exceptions {
+        query!(
+            "INSERT INTO regex_exceptions (regex_id, channel_id) VALUES ($1, $2)",
+            id,
+            channel_id.get() as i64
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+
+    match &regex.response {
+        ResponseType::Message(message) => {
+            query!(
+                "INSERT INTO responses (regex_id, message) VALUES ($1, $2)",
+                id,
+                message
+            )
+            .execute(&mut **transaction)
+            .await?;
+        }
+        ResponseType::Emoji(reaction) => {
+            let emote_id =
+                match reaction {
+                    ReactionType::Custom { id, name, .. } => query!(
+                        "INSERT INTO emotes (emote_name, discord_id) VALUES ($1, $2) ON CONFLICT \
+                         (discord_id) DO UPDATE SET emote_name = EXCLUDED.emote_name RETURNING id",
+                        name.as_deref().unwrap_or("_"),
+                        id.get() as i64
+                    )
+                    .fetch_one(&mut **transaction)
+                    .await?
+                    .id,
+                    ReactionType::Unicode(name) => {
+                        query!(
+                            "INSERT INTO emotes (emote_name, discord_id)
+                         VALUES ($1, NULL)
+                         ON CONFLICT (emote_name) WHERE discord_id IS NULL
+                         DO UPDATE SET discord_id = emotes.discord_id
+                         RETURNING id",
+                            name.as_str()
+                        )
+                        .fetch_one(&mut **transaction)
+                        .await?
+                        .id
+                    }
+                    _ => return Err("Unsupported reaction type.".into()),
+                };
+
+            query!(
+                "INSERT INTO responses (regex_id, emote_id) VALUES ($1, $2)",
+                id,
+                emote_id
+            )
+            .execute(&mut **transaction)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
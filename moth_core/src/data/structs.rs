@@ -33,6 +33,12 @@ pub struct Data {
     pub ocr_engine: crate::ocr::OcrEngine,
     /// ugh
     pub new_join_vc: DashMap<UserId, Fuck>,
+    /// Structured JSON Lines copy of the console event output.
+    pub event_log: crate::event_log::EventLog,
+    /// Records waiting to be posted to modlog channels.
+    pub modlog_queue: crate::data::modlog::ModlogQueue,
+    /// Member names the name filter has already seen.
+    pub checked_names: crate::data::names::CheckedNames,
 }
 
 #[derive(Clone, Debug)]
@@ -62,7 +68,44 @@ pub struct StarboardConfig {
     pub threshold: u8,
 }
 
+macro_rules! get_env_or_default {
+    ($var_name:expr, $kind:ty, $default:expr) => {
+        std::env::var($var_name)
+            .ok()
+            .and_then(|val| val.parse::<$kind>().ok())
+            .unwrap_or_else(|| <$kind>::new($default))
+    };
+}
+
 impl StarboardConfig {
+    /// Reads the configuration from the `STARBOARD_*` environment variables.
+    #[must_use]
+    pub fn from_env() -> Self {
+        StarboardConfig {
+            active: std::env::var("STARBOARD_ACTIVE")
+                .map(|e| e.parse::<bool>().unwrap())
+                .unwrap_or(true),
+
+            queue_channel: get_env_or_default!(
+                "STARBOARD_QUEUE",
+                GenericChannelId,
+                1324543000600383549
+            ),
+            post_channel: get_env_or_default!(
+                "STARBOARD_CHANNEL",
+                GenericChannelId,
+                1324437745854316564
+            ),
+            guild_id: get_env_or_default!("STARBOARD_GUILD", GuildId, 98226572468690944),
+            allowed_role: get_env_or_default!("STARBOARD_ROLE", RoleId, 98459030455853056),
+            star_emoji: std::env::var("STARBOARD_EMOJI").unwrap_or("⭐".to_owned()),
+            threshold: std::env::var("STARBOARD_THRESHOLD")
+                .ok()
+                .and_then(|val| val.parse::<u8>().ok())
+                .unwrap_or(5),
+        }
+    }
+
     #[must_use]
     pub fn emoji_fixed_string(&self) -> serenity::small_fixed_array::FixedString {
         serenity::small_fixed_array::FixedString::from_str_trunc(&self.star_emoji)
@@ -132,31 +175,16 @@ impl Data {
         if let Some(cached) = cached {
             Some(*cached)
         } else {
-            self.get_activity_check_psql(user_id).await
+            self.get_activity_check_storage(user_id).await
         }
     }
 
-    async fn get_activity_check_psql(&self, user_id: UserId) -> Option<DmActivity> {
-        let result = sqlx::query!(
-            "SELECT last_announced, until, count FROM dm_activity WHERE user_id = $1",
-            i64::from(user_id)
-        )
-        .fetch_one(&self.database.db)
-        .await;
-
-        match result {
-            Ok(record) => Some(DmActivity::new(
-                record.last_announced.unwrap(),
-                record.until,
-                record.count.unwrap(),
-            )),
+    async fn get_activity_check_storage(&self, user_id: UserId) -> Option<DmActivity> {
+        match self.database.storage().get_dm_activity(user_id).await {
+            Ok(activity) => activity,
             Err(err) => {
-                if let sqlx::Error::RowNotFound = err {
-                    None
-                } else {
-                    tracing::warn!("Error when attempting to find row: {err}");
-                    None
-                }
+                tracing::warn!("Error when attempting to find row: {err}");
+                None
             }
         }
     }
@@ -169,14 +197,11 @@ impl Data {
         count: i16,
     ) {
         // count will have already been incremented.
-        let _ = sqlx::query!(
-            "UPDATE dm_activity SET until = $1, count = $2 WHERE user_id = $3",
-            until,
-            count,
-            i64::from(user_id)
-        )
-        .execute(&self.database.db)
-        .await;
+        let _ = self
+            .database
+            .storage()
+            .update_dm_activity(user_id, until, count)
+            .await;
 
         self.update_user_cache(user_id, announced, until, count);
     }
@@ -189,18 +214,14 @@ impl Data {
         count: Option<i16>,
     ) {
         // If this is an update, count will have already been supplied and incremented.
-        let _ = sqlx::query!(
-            "INSERT INTO dm_activity (user_id, last_announced, until, count)
-            VALUES ($1, $2, $3, $4)
-            ON CONFLICT (user_id) DO UPDATE
-            SET last_announced = $2, until = $3, count = $4",
-            i64::from(user_id),
-            announced,
-            until,
-            count.unwrap_or(0)
-        )
-        .execute(&self.database.db)
-        .await;
+        let _ = self
+            .database
+            .storage()
+            .upsert_dm_activity(
+                user_id,
+                DmActivity::new(announced, Some(until), count.unwrap_or(0)),
+            )
+            .await;
 
         self.update_user_cache(user_id, announced, until, count.unwrap_or(0));
     }
@@ -217,11 +238,10 @@ impl Data {
 
     pub async fn remove_until(&self, user_id: UserId) {
         self.remove_dm_activity_cache(user_id);
-        let _ = sqlx::query!(
-            "UPDATE dm_activity SET until = NULL WHERE user_id = $1",
-            i64::from(user_id)
-        )
-        .execute(&self.database.db)
-        .await;
+        let _ = self
+            .database
+            .storage()
+            .clear_dm_activity_until(user_id)
+            .await;
     }
 }
@@ -0,0 +1,395 @@
+//! Structured JSON Lines log of everything the event handler prints.
+//!
+//! Enabled by setting `MOTH_EVENT_LOG` to a file path. Once the file reaches
+//! `MOTH_EVENT_LOG_MAX_BYTES` (default 50MB) it is rotated to `<path>.1`, shifting older files
+//! up until `MOTH_EVENT_LOG_FILES` (default 5) are kept.
+//!
+//! Records are written by a [`BackgroundWriter`], so emitting one never waits on the disk.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use chrono::{SecondsFormat, Utc};
+use lumi::serenity_prelude::{GenericChannelId, GuildId, MessageId, RoleId, UserId};
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::data::structs::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    MessageCreate,
+    MessageEdit,
+    MessageDelete,
+    ReactionAdd,
+    ReactionRemove,
+    ChannelCreate,
+    ChannelUpdate,
+    ChannelDelete,
+    ThreadCreate,
+    ThreadUpdate,
+    ThreadDelete,
+    RoleCreate,
+    RoleUpdate,
+    RoleDelete,
+    MemberJoin,
+    MemberLeave,
+    MemberUpdate,
+    MemberRoleUpdate,
+    VoiceJoin,
+    VoiceLeave,
+    VoiceMove,
+}
+
+/// A single line of the event log.
+#[derive(Clone, Debug, Serialize)]
+pub struct LogRecord {
+    /// RFC 3339 timestamp of when the event was handled.
+    pub timestamp: String,
+    pub kind: EventKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<GuildId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<GenericChannelId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<UserId>,
+    /// Who caused the event, when it is known and differs from `user_id`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor_id: Option<UserId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<MessageId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_id: Option<RoleId>,
+    /// The state before the event, such as old message content or an old channel name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// The state after the event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Line-by-line changes for update events, without any ANSI styling.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<String>,
+}
+
+impl LogRecord {
+    #[must_use]
+    pub fn new(kind: EventKind) -> Self {
+        Self {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            kind,
+            guild_id: None,
+            channel_id: None,
+            user_id: None,
+            actor_id: None,
+            message_id: None,
+            role_id: None,
+            before: None,
+            after: None,
+            changes: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn guild(mut self, guild_id: impl Into<Option<GuildId>>) -> Self {
+        self.guild_id = guild_id.into();
+        self
+    }
+
+    #[must_use]
+    pub fn channel(mut self, channel_id: impl Into<Option<GenericChannelId>>) -> Self {
+        self.channel_id = channel_id.into();
+        self
+    }
+
+    #[must_use]
+    pub fn user(mut self, user_id: impl Into<Option<UserId>>) -> Self {
+        self.user_id = user_id.into();
+        self
+    }
+
+    #[must_use]
+    pub fn actor(mut self, actor_id: impl Into<Option<UserId>>) -> Self {
+        self.actor_id = actor_id.into();
+        self
+    }
+
+    #[must_use]
+    pub fn message(mut self, message_id: MessageId) -> Self {
+        self.message_id = Some(message_id);
+        self
+    }
+
+    #[must_use]
+    pub fn role(mut self, role_id: RoleId) -> Self {
+        self.role_id = Some(role_id);
+        self
+    }
+
+    #[must_use]
+    pub fn before(mut self, before: impl Into<String>) -> Self {
+        self.before = Some(before.into());
+        self
+    }
+
+    #[must_use]
+    pub fn after(mut self, after: impl Into<String>) -> Self {
+        self.after = Some(after.into());
+        self
+    }
+
+    /// Splits a multi-line diff into [`LogRecord::changes`], skipping empty lines.
+    #[must_use]
+    pub fn changes(mut self, diff: &str) -> Self {
+        self.changes = diff
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(ToString::to_string)
+            .collect();
+        self
+    }
+}
+
+/// How often a [`BackgroundWriter`] flushes what it has written.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many records can be queued for a [`BackgroundWriter`] before new ones are dropped.
+const QUEUE_CAPACITY: usize = 4096;
+
+/// Where a [`BackgroundWriter`] puts its records.
+pub trait RecordSink<T>: Send + 'static {
+    /// Writes a record, it doesn't need to reach the disk until [`RecordSink::flush`].
+    fn write(&mut self, record: &T) -> Result<(), Error>;
+
+    fn flush(&mut self) -> Result<(), Error>;
+}
+
+/// Hands records to a dedicated thread that writes them to a [`RecordSink`], flushing every
+/// [`FLUSH_INTERVAL`] instead of after every record.
+///
+/// If the thread falls [`QUEUE_CAPACITY`] records behind, new records are dropped rather than
+/// blocking the caller. Dropping the writer waits for the queued records to be flushed.
+pub struct BackgroundWriter<T> {
+    name: String,
+    tx: Option<mpsc::SyncSender<T>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> BackgroundWriter<T> {
+    pub fn spawn(name: &str, mut sink: impl RecordSink<T>) -> Result<Self, Error> {
+        let (tx, rx) = mpsc::sync_channel::<T>(QUEUE_CAPACITY);
+
+        let thread_name = name.to_string();
+        let thread = std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let mut last_flush = Instant::now();
+                loop {
+                    let disconnected = match rx.recv_timeout(FLUSH_INTERVAL) {
+                        Ok(record) => {
+                            if let Err(e) = sink.write(&record) {
+                                tracing::warn!("{thread_name} failed to write a record: {e}");
+                            }
+
+                            if last_flush.elapsed() < FLUSH_INTERVAL {
+                                continue;
+                            }
+                            false
+                        }
+                        Err(mpsc::RecvTimeoutError::Timeout) => false,
+                        Err(mpsc::RecvTimeoutError::Disconnected) => true,
+                    };
+
+                    if let Err(e) = sink.flush() {
+                        tracing::warn!("{thread_name} failed to flush: {e}");
+                    }
+                    last_flush = Instant::now();
+
+                    if disconnected {
+                        return;
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            name: name.to_string(),
+            tx: Some(tx),
+            thread: Some(thread),
+        })
+    }
+
+    /// Queues a record for writing.
+    pub fn send(&self, record: T) {
+        let Some(tx) = &self.tx else {
+            return;
+        };
+
+        if let Err(mpsc::TrySendError::Full(_)) = tx.try_send(record) {
+            tracing::warn!("{} is falling behind, dropped a record", self.name);
+        }
+    }
+}
+
+impl<T> Drop for BackgroundWriter<T> {
+    fn drop(&mut self) {
+        // Closing the channel makes the thread flush and exit once it is drained.
+        drop(self.tx.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct RotatingWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    written: u64,
+    file: BufWriter<File>,
+}
+
+impl RotatingWriter {
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> Result<Self, Error> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            written,
+            file: BufWriter::new(file),
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> Result<(), Error> {
+        self.file.flush()?;
+
+        if self.max_files == 0 {
+            // Nothing is kept, just start over.
+            self.file = BufWriter::new(File::create(&self.path)?);
+            self.written = 0;
+            return Ok(());
+        }
+
+        let _ = fs::remove_file(self.rotated_path(self.max_files));
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = BufWriter::new(File::create(&self.path)?);
+        self.written = 0;
+
+        Ok(())
+    }
+}
+
+impl RecordSink<LogRecord> for RotatingWriter {
+    fn write(&mut self, record: &LogRecord) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        if self.written > 0 && self.written + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        self.file.write_all(&line)?;
+        self.written += line.len() as u64;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// The event log, only writing to a file when enabled.
+///
+/// Records are also handed to every [`EventLog::subscribe`]r, whether or not the file is enabled.
+#[derive(Default)]
+pub struct EventLog {
+    writer: Option<BackgroundWriter<LogRecord>>,
+    subscribers: Mutex<Vec<mpsc::Sender<LogRecord>>>,
+}
+
+impl EventLog {
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> Result<Self, Error> {
+        Ok(Self {
+            writer: Some(BackgroundWriter::spawn(
+                "event-log",
+                RotatingWriter::open(path.into(), max_bytes, max_files)?,
+            )?),
+            subscribers: Mutex::default(),
+        })
+    }
+
+    /// Reads the `MOTH_EVENT_LOG*` variables, returning a disabled log if unset or unusable.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let Ok(path) = std::env::var("MOTH_EVENT_LOG") else {
+            return Self::disabled();
+        };
+
+        let max_bytes = std::env::var("MOTH_EVENT_LOG_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(50 * 1024 * 1024);
+        let max_files = std::env::var("MOTH_EVENT_LOG_FILES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(5);
+
+        match Self::new(&path, max_bytes, max_files) {
+            Ok(log) => log,
+            Err(e) => {
+                eprintln!("Failed to open event log at {path}: {e}");
+                Self::disabled()
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Receives every record emitted from now on, until the receiver is dropped.
+    pub fn subscribe(&self) -> mpsc::Receiver<LogRecord> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().push(tx);
+        rx
+    }
+
+    pub fn emit(&self, record: &LogRecord) {
+        if let Some(writer) = &self.writer {
+            writer.send(record.clone());
+        }
+
+        let mut subscribers = self.subscribers.lock();
+        if !subscribers.is_empty() {
+            subscribers.retain(|tx| tx.send(record.clone()).is_ok());
+        }
+    }
+}
@@ -13,4 +13,6 @@
 pub mod config;
 pub mod data;
 pub mod emojis;
+pub mod event_log;
 pub mod ocr;
+pub mod phash;
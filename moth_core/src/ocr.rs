@@ -1,21 +1,285 @@
+use std::collections::{HashMap, VecDeque, hash_map::Entry};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
 use leptess::LepTess;
-use tokio::sync::{mpsc, oneshot};
-use tokio::task;
+use lru::LruCache;
+use parking_lot::{Condvar, Mutex};
+use sha2::{Digest, Sha256};
+use tokio::sync::oneshot;
+
+use crate::data::database::Database;
+
+/// The language used when neither the regex nor the guild picked one.
+pub const DEFAULT_LANGUAGE: &str = "eng";
+
+/// Bumped whenever how images are read changes, so text cached by older versions is redone.
+pub const ENGINE_VERSION: i32 = 1;
+
+/// How long text read from an image stays cached in the database.
+pub const CACHE_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// How much of a single message gets read, so a message full of large GIFs can't tie up every
+/// worker.
+#[derive(Clone, Copy, Debug)]
+pub struct OcrBudget {
+    /// Bytes downloaded across every image of the message.
+    pub max_bytes: usize,
+    /// Images read across the message, each sampled GIF frame counting as one.
+    pub max_frames: usize,
+    /// Frames sampled from a single animated GIF.
+    pub frames_per_gif: usize,
+}
+
+impl Default for OcrBudget {
+    fn default() -> Self {
+        Self {
+            max_bytes: 25_000_000,
+            max_frames: 8,
+            frames_per_gif: 3,
+        }
+    }
+}
+
+/// Frames decoded from a GIF before giving up on reaching the end, as the frame count isn't known
+/// up front.
+const MAX_DECODED_FRAMES: usize = 200;
+
+/// The largest width or height of a GIF that gets sampled.
+const MAX_GIF_DIMENSION: u32 = 4096;
+
+/// How much the GIF decoder may allocate for a single frame.
+const MAX_GIF_ALLOC: u64 = 64 * 1024 * 1024;
+
+/// If `bytes` look like a GIF.
+#[must_use]
+pub fn is_gif(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a")
+}
+
+/// If `url` is served by Discord's CDN or media proxy, the only hosts images are downloaded from
+/// so links can't point the bot at internal addresses.
+#[must_use]
+pub fn is_discord_cdn(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+
+    url.scheme() == "https"
+        && url.port().is_none()
+        && url.host_str().is_some_and(|host| {
+            matches!(host, "cdn.discordapp.com" | "media.discordapp.net")
+                // the proxy for embed images, like images-ext-1.discordapp.net.
+                || host
+                    .strip_prefix("images-ext-")
+                    .and_then(|h| h.strip_suffix(".discordapp.net"))
+                    .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+}
+
+fn gif_decoder(
+    bytes: &[u8],
+) -> Result<image::codecs::gif::GifDecoder<std::io::Cursor<&[u8]>>, OCRError> {
+    use image::ImageDecoder;
+
+    let mut decoder = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(bytes))
+        .map_err(OCRError::Decode)?;
+
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(MAX_GIF_DIMENSION);
+    limits.max_image_height = Some(MAX_GIF_DIMENSION);
+    limits.max_alloc = Some(MAX_GIF_ALLOC);
+    decoder.set_limits(limits).map_err(OCRError::Decode)?;
+
+    Ok(decoder)
+}
+
+/// Up to `count` frames spread evenly across an animated GIF, each encoded as a PNG.
+///
+/// This decodes the GIF twice, once to count the frames and once to keep the sampled ones, so it
+/// should be run on a blocking thread.
+pub fn sample_gif_frames(bytes: &[u8], count: usize) -> Result<Vec<Vec<u8>>, OCRError> {
+    use image::AnimationDecoder;
+
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut total = 0;
+    for frame in gif_decoder(bytes)?.into_frames().take(MAX_DECODED_FRAMES) {
+        frame.map_err(OCRError::Decode)?;
+        total += 1;
+    }
+
+    if total == 0 {
+        return Ok(Vec::new());
+    }
+
+    let count = count.min(total);
+    // first and last frames included, as captions tend to sit at either end.
+    let mut indices = (0..count)
+        .map(|i| {
+            if count == 1 {
+                0
+            } else {
+                i * (total - 1) / (count - 1)
+            }
+        })
+        .peekable();
+
+    let mut sampled = Vec::with_capacity(count);
+    for (index, frame) in gif_decoder(bytes)?.into_frames().enumerate() {
+        let Some(&next) = indices.peek() else {
+            break;
+        };
+        // every frame is still decoded, as later ones are drawn over the earlier ones.
+        let frame = frame.map_err(OCRError::Decode)?;
+        if index != next {
+            continue;
+        }
+        indices.next();
+
+        let image = image::DynamicImage::ImageRgba8(frame.into_buffer());
+        let mut png = std::io::Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageFormat::Png)
+            .map_err(OCRError::Decode)?;
+        sampled.push(png.into_inner());
+    }
+
+    Ok(sampled)
+}
+
+/// Which requests workers pick up first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OcrPriority {
+    /// Someone is waiting on the result, like a command.
+    High,
+    /// Background work like matching response regexes.
+    Normal,
+}
+
+/// Text read from an image, with how sure Tesseract was of it.
+#[derive(Clone, Debug)]
+pub struct OcrText {
+    pub text: String,
+    /// The mean confidence of the recognised words from 0 to 100, if any were found.
+    pub confidence: Option<i32>,
+}
 
 struct OcrRequest {
     image_data: Vec<u8>,
-    response: oneshot::Sender<Result<String, OCRError>>,
+    language: String,
+    queued_at: Instant,
+    response: oneshot::Sender<Result<OcrText, OCRError>>,
+}
+
+#[derive(Default)]
+struct Queue {
+    high: VecDeque<OcrRequest>,
+    normal: VecDeque<OcrRequest>,
+    closed: bool,
 }
 
+impl Queue {
+    fn len(&self) -> usize {
+        self.high.len() + self.normal.len()
+    }
+
+    fn pop(&mut self) -> Option<OcrRequest> {
+        self.high.pop_front().or_else(|| self.normal.pop_front())
+    }
+}
+
+#[derive(Default)]
+struct Metrics {
+    busy: AtomicUsize,
+    processed: AtomicU64,
+    failed: AtomicU64,
+    timed_out: AtomicU64,
+    rejected: AtomicU64,
+    /// Summed from being queued to finishing, for the average.
+    total_latency_ms: AtomicU64,
+    max_latency_ms: AtomicU64,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    available: Condvar,
+    metrics: Metrics,
+}
+
+/// A snapshot of how the OCR workers are doing, for `stats`.
+#[derive(Clone, Copy, Debug)]
+pub struct OcrStats {
+    pub workers: usize,
+    pub busy: usize,
+    pub queue_depth: usize,
+    pub processed: u64,
+    pub failed: u64,
+    pub timed_out: u64,
+    /// Turned away because the queue was full.
+    pub rejected: u64,
+    pub average_latency: Duration,
+    pub max_latency: Duration,
+}
+
+/// Recently read images, keyed by the hash of their bytes and the language.
+struct OcrCache {
+    memory: Mutex<LruCache<([u8; 32], String), String>>,
+    memory_hits: AtomicU64,
+    database_hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// How well the cache is doing, for the `ocr-cache` command.
+#[derive(Clone, Copy, Debug)]
+pub struct OcrCacheStats {
+    pub memory_entries: usize,
+    pub memory_capacity: usize,
+    pub memory_hits: u64,
+    pub database_hits: u64,
+    pub misses: u64,
+}
+
+impl OcrCacheStats {
+    /// The fraction of lookups that skipped Tesseract.
+    #[must_use]
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.memory_hits + self.database_hits;
+        let total = hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            hits as f64 / total as f64
+        }
+    }
+}
+
+/// A pool of blocking Tesseract workers, so a large image only holds up its own worker instead of
+/// the async runtime.
 pub struct OcrEngine {
-    sender: mpsc::Sender<OcrRequest>,
-    shutdown: Option<oneshot::Sender<()>>,
+    shared: Arc<Shared>,
+    cache: OcrCache,
+    pub budget: OcrBudget,
+    workers: usize,
+    timeout: Duration,
+    max_queue: usize,
 }
 
 #[derive(Debug)]
 pub enum OCRError {
     UTFError(std::str::Utf8Error),
     PixError(leptess::leptonica::PixError),
+    /// Tesseract couldn't load the language, usually because its traineddata isn't installed.
+    Language(String),
+    /// An animated image couldn't be split into frames.
+    Decode(image::ImageError),
+    /// The request wasn't done within the timeout, including time spent queued.
+    Timeout,
+    QueueFull,
     WorkerMissing,
 }
 
@@ -24,6 +288,12 @@ impl std::fmt::Display for OCRError {
         match *self {
             OCRError::UTFError(ref e) => write!(f, "{e}"),
             OCRError::PixError(ref e) => write!(f, "{e}"),
+            OCRError::Decode(ref e) => write!(f, "{e}"),
+            OCRError::Language(ref language) => {
+                write!(f, "Tesseract could not load the language `{language}`.")
+            }
+            OCRError::Timeout => write!(f, "OCR request took too long and was abandoned."),
+            OCRError::QueueFull => write!(f, "Too many OCR requests are queued."),
             OCRError::WorkerMissing => write!(f, "Worker died, cannot process OCR request."),
         }
     }
@@ -31,56 +301,297 @@ impl std::fmt::Display for OCRError {
 
 impl std::error::Error for OCRError {}
 
+/// If `language` looks like a Tesseract language, like `eng`, `chi_sim` or `eng+jpn`.
+#[must_use]
+pub fn is_valid_language(language: &str) -> bool {
+    !language.is_empty()
+        && language.split('+').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        })
+}
+
+fn recognise(
+    engines: &mut HashMap<String, LepTess>,
+    language: &str,
+    image_data: &[u8],
+) -> Result<OcrText, OCRError> {
+    let tess = match engines.entry(language.to_owned()) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(
+            LepTess::new(None, language).map_err(|_| OCRError::Language(language.to_owned()))?,
+        ),
+    };
+
+    tess.set_image_from_mem(image_data)
+        .map_err(OCRError::PixError)?;
+    let text = tess.get_utf8_text().map_err(OCRError::UTFError)?;
+
+    // tesseract reports a confidence even when nothing was found, which means nothing.
+    let confidence = if text.trim().is_empty() {
+        None
+    } else {
+        Some(tess.mean_text_conf())
+    };
+
+    Ok(OcrText { text, confidence })
+}
+
+fn worker(shared: &Shared) {
+    // loaded on first use, as each language costs a fair bit of memory.
+    let mut engines = HashMap::new();
+
+    loop {
+        let request = {
+            let mut queue = shared.queue.lock();
+            loop {
+                if queue.closed {
+                    return;
+                }
+                if let Some(request) = queue.pop() {
+                    break request;
+                }
+                shared.available.wait(&mut queue);
+            }
+        };
+
+        // the caller already timed out.
+        if request.response.is_closed() {
+            continue;
+        }
+
+        let metrics = &shared.metrics;
+        metrics.busy.fetch_add(1, Ordering::Relaxed);
+        let result = recognise(&mut engines, &request.language, &request.image_data);
+        metrics.busy.fetch_sub(1, Ordering::Relaxed);
+
+        let latency = request.queued_at.elapsed().as_millis() as u64;
+        metrics
+            .total_latency_ms
+            .fetch_add(latency, Ordering::Relaxed);
+        metrics.max_latency_ms.fetch_max(latency, Ordering::Relaxed);
+        if result.is_ok() {
+            metrics.processed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            metrics.failed.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let _ = request.response.send(result);
+    }
+}
+
 impl OcrEngine {
-    /// Creates a new OCR engine with an internal worker.
+    /// Creates a new OCR engine configured by `MOTH_OCR_WORKERS`, `MOTH_OCR_TIMEOUT_SECS`,
+    /// `MOTH_OCR_QUEUE` and `MOTH_OCR_CACHE_SIZE`, with the per-message budget from
+    /// `MOTH_OCR_MAX_BYTES`, `MOTH_OCR_MAX_FRAMES` and `MOTH_OCR_GIF_FRAMES`.
     #[must_use]
     pub fn new() -> Self {
-        let (tx, mut rx) = mpsc::channel(10);
-        let (shutdown_tx, shutdown_rx) = oneshot::channel();
-
-        task::spawn(async move {
-            let mut tess = LepTess::new(None, "eng").expect("Failed to initialize Tesseract");
-
-            tokio::select! {
-                () = async {
-                    while let Some(OcrRequest { image_data, response }) = rx.recv().await {
-                        let ocr_text = match tess.set_image_from_mem(&image_data) {
-                            Ok(())=> tess.get_utf8_text().map_err(OCRError::UTFError),
-                            Err(e) => Err(OCRError::PixError(e)),
-                        };
-
-                        let _ = response.send(ocr_text);
-                    }
-                } => {}
-
-                _ = shutdown_rx => {
-                    println!("OCR Worker shutting down...");
-                }
-            }
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        }
+
+        let mut engine = Self::with_options(
+            var("MOTH_OCR_WORKERS", 2),
+            Duration::from_secs(var("MOTH_OCR_TIMEOUT_SECS", 30)),
+            var("MOTH_OCR_QUEUE", 32),
+            var("MOTH_OCR_CACHE_SIZE", 512),
+        );
+
+        let default = OcrBudget::default();
+        engine.budget = OcrBudget {
+            max_bytes: var("MOTH_OCR_MAX_BYTES", default.max_bytes),
+            max_frames: var("MOTH_OCR_MAX_FRAMES", default.max_frames),
+            frames_per_gif: var("MOTH_OCR_GIF_FRAMES", default.frames_per_gif),
+        };
+
+        engine
+    }
+
+    /// Spawns `workers` threads, each request given `timeout` to finish and at most `max_queue`
+    /// waiting at once, remembering the text of the last `cache_size` images.
+    #[must_use]
+    pub fn with_options(
+        workers: usize,
+        timeout: Duration,
+        max_queue: usize,
+        cache_size: usize,
+    ) -> Self {
+        let workers = workers.max(1);
+        let shared = Arc::new(Shared {
+            queue: Mutex::default(),
+            available: Condvar::new(),
+            metrics: Metrics::default(),
         });
 
+        for i in 0..workers {
+            let shared = Arc::clone(&shared);
+            std::thread::Builder::new()
+                .name(format!("ocr-worker-{i}"))
+                .spawn(move || worker(&shared))
+                .expect("Failed to spawn OCR worker");
+        }
+
+        let cache_size = NonZeroUsize::new(cache_size).unwrap_or(NonZeroUsize::MIN);
         OcrEngine {
-            sender: tx,
-            shutdown: Some(shutdown_tx),
+            shared,
+            cache: OcrCache {
+                memory: Mutex::new(LruCache::new(cache_size)),
+                memory_hits: AtomicU64::new(0),
+                database_hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            },
+            budget: OcrBudget::default(),
+            workers,
+            timeout,
+            max_queue,
         }
     }
 
-    /// Sends an image to be processed asynchronously and awaits the result.
-    pub async fn process(&self, image_data: Vec<u8>) -> Result<String, OCRError> {
+    /// Queues an image to be read in `language` and awaits the result.
+    pub async fn process(
+        &self,
+        image_data: Vec<u8>,
+        language: &str,
+        priority: OcrPriority,
+    ) -> Result<String, OCRError> {
+        self.process_with_confidence(image_data, language, priority)
+            .await
+            .map(|ocr| ocr.text)
+    }
+
+    /// Like [`OcrEngine::process`], but also returns the confidence of the result.
+    pub async fn process_with_confidence(
+        &self,
+        image_data: Vec<u8>,
+        language: &str,
+        priority: OcrPriority,
+    ) -> Result<OcrText, OCRError> {
         let (response_tx, response_rx) = oneshot::channel();
 
-        let request = OcrRequest {
-            image_data,
-            response: response_tx,
-        };
+        {
+            let mut queue = self.shared.queue.lock();
+            if queue.closed {
+                return Err(OCRError::WorkerMissing);
+            }
+            if queue.len() >= self.max_queue {
+                self.shared.metrics.rejected.fetch_add(1, Ordering::Relaxed);
+                return Err(OCRError::QueueFull);
+            }
+
+            let request = OcrRequest {
+                image_data,
+                language: language.to_owned(),
+                queued_at: Instant::now(),
+                response: response_tx,
+            };
+            match priority {
+                OcrPriority::High => queue.high.push_back(request),
+                OcrPriority::Normal => queue.normal.push_back(request),
+            }
+        }
+        self.shared.available.notify_one();
+
+        match tokio::time::timeout(self.timeout, response_rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(OCRError::WorkerMissing),
+            Err(_) => {
+                self.shared
+                    .metrics
+                    .timed_out
+                    .fetch_add(1, Ordering::Relaxed);
+                Err(OCRError::Timeout)
+            }
+        }
+    }
+
+    /// Like [`OcrEngine::process`], but reuses the text of images that were read before, either
+    /// recently or stored in the database.
+    pub async fn process_cached(
+        &self,
+        database: &Database,
+        image_data: Vec<u8>,
+        language: &str,
+        priority: OcrPriority,
+    ) -> Result<String, OCRError> {
+        let hash: [u8; 32] = Sha256::digest(&image_data).into();
+        let key = (hash, language.to_owned());
+
+        if let Some(text) = self.cache.memory.lock().get(&key) {
+            self.cache.memory_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(text.clone());
+        }
+
+        match database
+            .get_cached_ocr(&hash, language, ENGINE_VERSION)
+            .await
+        {
+            Ok(Some(text)) => {
+                self.cache.database_hits.fetch_add(1, Ordering::Relaxed);
+                self.cache.memory.lock().put(key, text.clone());
+                return Ok(text);
+            }
+            Ok(None) => {}
+            Err(e) => println!("Failed to look up cached OCR text: {e}"),
+        }
+
+        self.cache.misses.fetch_add(1, Ordering::Relaxed);
+        let text = self.process(image_data, language, priority).await?;
+
+        self.cache.memory.lock().put(key, text.clone());
+        if let Err(e) = database
+            .insert_cached_ocr(&hash, language, ENGINE_VERSION, &text)
+            .await
+        {
+            println!("Failed to cache OCR text: {e}");
+        }
+
+        Ok(text)
+    }
 
-        if self.sender.send(request).await.is_err() {
-            return Err(OCRError::WorkerMissing);
+    #[must_use]
+    pub fn cache_stats(&self) -> OcrCacheStats {
+        let memory = self.cache.memory.lock();
+        OcrCacheStats {
+            memory_entries: memory.len(),
+            memory_capacity: memory.cap().get(),
+            memory_hits: self.cache.memory_hits.load(Ordering::Relaxed),
+            database_hits: self.cache.database_hits.load(Ordering::Relaxed),
+            misses: self.cache.misses.load(Ordering::Relaxed),
         }
+    }
+
+    /// Empties the in-memory cache and resets its counters, the database is left to the caller.
+    pub fn clear_cache(&self) {
+        self.cache.memory.lock().clear();
+        self.cache.memory_hits.store(0, Ordering::Relaxed);
+        self.cache.database_hits.store(0, Ordering::Relaxed);
+        self.cache.misses.store(0, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> OcrStats {
+        let metrics = &self.shared.metrics;
+        let processed = metrics.processed.load(Ordering::Relaxed);
+        let failed = metrics.failed.load(Ordering::Relaxed);
+        let total_latency = metrics.total_latency_ms.load(Ordering::Relaxed);
 
-        match response_rx.await {
-            Ok(result) => result,
-            Err(_) => Err(OCRError::WorkerMissing),
+        OcrStats {
+            workers: self.workers,
+            busy: metrics.busy.load(Ordering::Relaxed),
+            queue_depth: self.shared.queue.lock().len(),
+            processed,
+            failed,
+            timed_out: metrics.timed_out.load(Ordering::Relaxed),
+            rejected: metrics.rejected.load(Ordering::Relaxed),
+            average_latency: Duration::from_millis(
+                total_latency.checked_div(processed + failed).unwrap_or(0),
+            ),
+            max_latency: Duration::from_millis(metrics.max_latency_ms.load(Ordering::Relaxed)),
         }
     }
 }
@@ -93,8 +604,8 @@ impl Default for OcrEngine {
 
 impl Drop for OcrEngine {
     fn drop(&mut self) {
-        if let Some(shutdown) = self.shutdown.take() {
-            let _ = shutdown.send(());
-        }
+        self.shared.queue.lock().closed = true;
+        self.shared.available.notify_all();
+        println!("OCR workers shutting down...");
     }
 }
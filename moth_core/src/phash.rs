@@ -0,0 +1,31 @@
+//! Perceptual hashes, which stay close for images that look the same after resizing,
+//! recompression or small edits, unlike hashes of the bytes.
+
+use image::imageops::FilterType;
+
+/// The difference hash of an image: each bit is whether a pixel is brighter than its right
+/// neighbour, on a 9x8 greyscale thumbnail.
+///
+/// This decodes the image, so it should be run on a blocking thread.
+pub fn dhash(bytes: &[u8]) -> Result<u64, image::ImageError> {
+    let thumbnail = image::load_from_memory(bytes)?
+        .resize_exact(9, 8, FilterType::Triangle)
+        .into_luma8();
+
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = thumbnail.get_pixel(x, y)[0];
+            let right = thumbnail.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+
+    Ok(hash)
+}
+
+/// How many bits two hashes differ by, lower is more alike.
+#[must_use]
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
@@ -0,0 +1,352 @@
+//! Acts on filtered messages in guilds that opted in, see `automod` in the moderation commands.
+
+use std::sync::Arc;
+
+use lumi::serenity_prelude::{
+    self as serenity, Colour, CreateActionRow, CreateAllowedMentions, CreateButton,
+    CreateComponent, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditMember, Message,
+};
+use moth_ansi::{Style, StyledText, HI_BLACK, RED, RESET};
+use moth_core::data::automod::AutomodRecord;
+use moth_filter::{Tier, Verdict};
+
+use crate::helper::get_guild_name_override;
+use crate::modlog::truncate;
+use crate::ocr::{filter_texts, MessageFrames};
+use crate::{Data, Error};
+
+/// Prefix of the explain button's custom id, followed by the record id.
+const EXPLAIN_ID: &str = "automod_explain:";
+
+/// Where the text automod looked at came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origin {
+    /// The content of the message.
+    Message,
+    /// Text read from one of the message's images.
+    Image,
+}
+
+/// Picks the strongest action configured for the tiers that the content of `msg` or the text in
+/// its images fall into, and carries it out once for the whole message.
+///
+/// Flagged images are still posted to the modlog when no action applies, as moderators can't
+/// see them in the console highlighting like message text.
+pub async fn automod(
+    ctx: &serenity::Context,
+    data: &Arc<Data>,
+    msg: &Message,
+    content: &Verdict<'_>,
+    frames: &MessageFrames<'_>,
+) -> Result<(), Error> {
+    let Some(guild_id) = msg.guild_id else {
+        return Ok(());
+    };
+
+    if msg.author.bot() {
+        return Ok(());
+    }
+
+    let texts = filter_texts(data, msg, frames).await;
+    let images = {
+        let guild_filter = data.database.get_filter(guild_id);
+        let config = &data.config.read().events;
+        texts
+            .iter()
+            .map(|text| {
+                moth_filter::check(
+                    text,
+                    moth_filter::Lists {
+                        badlist: &config.badlist,
+                        fixlist: &config.fixlist,
+                        leet: &config.leet,
+                        guild: guild_filter.as_deref(),
+                    },
+                )
+            })
+            .filter(|verdict| verdict.flagged().next().is_some())
+            .collect::<Vec<_>>()
+    };
+
+    if !images.is_empty() {
+        let guild_name = get_guild_name_override(ctx, data, Some(guild_id));
+        for verdict in &images {
+            println!(
+                "{RED}[{guild_name}] OCR filter: image by {}: {} ({:?}){RESET}",
+                msg.author.tag(),
+                verdict.words().join(", "),
+                verdict.kind
+            );
+        }
+    }
+
+    let config = data.database.get_automod(guild_id);
+    // the content goes last so it wins ties, as that is what moderators can see.
+    let verdicts = images
+        .iter()
+        .map(|verdict| (Origin::Image, verdict))
+        .chain([(Origin::Message, content)]);
+    let Some((origin, verdict, tier, action)) = config.as_ref().and_then(|config| {
+        verdicts
+            .flat_map(|(origin, verdict)| {
+                verdict.tiers().into_iter().filter_map(move |tier| {
+                    config
+                        .get(&tier)
+                        .map(|action| (origin, verdict, tier, *action))
+                })
+            })
+            .max_by_key(|(_, _, tier, action)| (action.strength(), *tier))
+    }) else {
+        if !images.is_empty() {
+            alert_images(ctx, data, msg, &images).await;
+        }
+        return Ok(());
+    };
+
+    let record = AutomodRecord {
+        guild_id,
+        channel_id: msg.channel_id,
+        message_id: msg.id,
+        user_id: msg.author.id,
+        tier,
+        action,
+        words: verdict
+            .words()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect(),
+        content: verdict.content.to_string(),
+        analysis: match origin {
+            Origin::Message => format!("{:?}", verdict.kind),
+            Origin::Image => format!("{:?}, read from an image", verdict.kind),
+        },
+        created_at: msg.id.created_at().unix_timestamp(),
+    };
+
+    let reason = format!("Automod: {} content", tier.name());
+    if action.deletes() {
+        data.modlog_queue.mark_own_deletions([msg.id]);
+        if let Err(e) = msg.delete(&ctx.http, Some(&reason)).await {
+            println!("{HI_BLACK}Automod failed to delete {}: {e}{RESET}", msg.id);
+        }
+    }
+
+    if action.warns() {
+        let warning = if action.deletes() {
+            "your message was removed for breaking this server's filter."
+        } else {
+            "your message breaks this server's filter."
+        };
+        let builder = CreateMessage::new()
+            .content(format!("<@{}>, {warning}", msg.author.id))
+            .allowed_mentions(CreateAllowedMentions::new().users(vec![msg.author.id]));
+        let _ = msg.channel_id.send_message(&ctx.http, builder).await;
+    }
+
+    if let Some(duration) = action.timeout {
+        let until = serenity::Timestamp::now().unix_timestamp() + duration.as_secs() as i64;
+        if let Ok(until) = serenity::Timestamp::from_unix_timestamp(until) {
+            let builder = EditMember::new()
+                .disable_communication_until(until)
+                .audit_log_reason(&reason);
+            if let Err(e) = guild_id
+                .edit_member(&ctx.http, msg.author.id, builder)
+                .await
+            {
+                println!(
+                    "{HI_BLACK}Automod failed to time out {}: {e}{RESET}",
+                    msg.author.id
+                );
+            }
+        }
+    }
+
+    let id = data.database.insert_automod_record(&record).await?;
+
+    let guild_name = get_guild_name_override(ctx, data, Some(guild_id));
+    let from = match origin {
+        Origin::Message => "",
+        Origin::Image => " in an image",
+    };
+    println!(
+        "{RED}[{guild_name}] Automod: {action} for {} ({} tier{from}): {}{RESET}",
+        msg.author.tag(),
+        tier.name(),
+        record.words.join(", ")
+    );
+
+    if let Some(modlog) = data.database.get_modlog(guild_id) {
+        let button = CreateButton::new(format!("{EXPLAIN_ID}{id}")).label("Explain");
+        let builder = CreateMessage::new()
+            .embed(render(&record, origin))
+            .components(vec![CreateComponent::ActionRow(CreateActionRow::buttons(
+                vec![button],
+            ))]);
+
+        if let Err(e) = modlog.channel_id.send_message(&ctx.http, builder).await {
+            println!(
+                "Failed to post to modlog channel {}: {e}",
+                modlog.channel_id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn render(record: &AutomodRecord, origin: Origin) -> CreateEmbed<'static> {
+    let colour = match record.tier {
+        Tier::Mild => Colour::GOLD,
+        Tier::Moderate | Tier::Evasive | Tier::Spam => Colour::ORANGE,
+        Tier::Severe => Colour::RED,
+    };
+
+    let description = format!(
+        "**User:** <@{}>\n**Channel:** <#{}>\n**Tier:** {}\n**Action:** {}",
+        record.user_id,
+        record.channel_id,
+        record.tier.name(),
+        record.action
+    );
+
+    let words = if record.words.is_empty() {
+        String::from("*none highlighted*")
+    } else {
+        record
+            .words
+            .iter()
+            .map(|w| format!("`{w}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let title = match origin {
+        Origin::Message => "Automod",
+        Origin::Image => "Automod (image)",
+    };
+
+    CreateEmbed::new()
+        .title(title)
+        .description(description)
+        .field("Matched", truncate(&words, 1024), false)
+        .colour(colour)
+        .footer(CreateEmbedFooter::new(format!(
+            "Message ID: {}",
+            record.message_id
+        )))
+        .timestamp(serenity::Timestamp::now())
+}
+
+/// Posts flagged images to the modlog channel when automod has nothing to do about them.
+async fn alert_images(
+    ctx: &serenity::Context,
+    data: &Arc<Data>,
+    msg: &Message,
+    verdicts: &[Verdict<'_>],
+) {
+    let Some(modlog) = msg.guild_id.and_then(|g| data.database.get_modlog(g)) else {
+        return;
+    };
+
+    let words = verdicts
+        .iter()
+        .flat_map(Verdict::words)
+        .map(|w| format!("`{w}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let text = verdicts
+        .iter()
+        .map(|v| v.content)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let embed = CreateEmbed::new()
+        .title("Flagged image")
+        .description(format!(
+            "**User:** <@{}>\n**Message:** {}",
+            msg.author.id,
+            msg.link()
+        ))
+        .field("Matched", truncate(&words, 1024), false)
+        .field("Text", truncate(&text, 1024), false)
+        .colour(Colour::ORANGE)
+        .footer(CreateEmbedFooter::new(format!("Message ID: {}", msg.id)))
+        .timestamp(serenity::Timestamp::now());
+
+    let builder = CreateMessage::new().embed(embed);
+    if let Err(e) = modlog.channel_id.send_message(&ctx.http, builder).await {
+        println!(
+            "Failed to post to modlog channel {}: {e}",
+            modlog.channel_id
+        );
+    }
+}
+
+/// Answers the explain button on automod log entries, only for moderators.
+pub async fn handle_component(
+    ctx: &serenity::Context,
+    data: Arc<Data>,
+    interaction: &serenity::ComponentInteraction,
+) -> Result<(), Error> {
+    let Some(id) = interaction.data.custom_id.strip_prefix(EXPLAIN_ID) else {
+        return Ok(());
+    };
+
+    let is_mod = interaction
+        .member
+        .as_ref()
+        .and_then(|m| m.permissions)
+        .is_some_and(|p| p.moderate_members() || p.manage_messages());
+
+    let response = if !is_mod {
+        CreateInteractionResponseMessage::new().content("You are not allowed to do this.")
+    } else {
+        match data.database.get_automod_record(id.parse()?).await? {
+            Some(record) if Some(record.guild_id) == interaction.guild_id => {
+                CreateInteractionResponseMessage::new().embed(explain(&record))
+            }
+            _ => CreateInteractionResponseMessage::new().content("This action no longer exists."),
+        }
+    };
+
+    interaction
+        .create_response(
+            &ctx.http,
+            CreateInteractionResponse::Message(response.ephemeral(true)),
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn explain(record: &AutomodRecord) -> CreateEmbed<'static> {
+    let mut content = StyledText::new();
+    let flagged = Style::new().fg(moth_ansi::Color::RED).bold();
+    for cap in moth_filter::WHITESPACE.captures_iter(&record.content) {
+        content.push(&cap[1], Style::new());
+
+        let word = &cap[2];
+        if record.words.iter().any(|w| w == word) {
+            content.push(word, flagged);
+        } else {
+            content.push(word, Style::new());
+        }
+    }
+
+    CreateEmbed::new()
+        .title("Automod explanation")
+        .description(format!(
+            "The message fell into the **{}** tier, which this server set to **{}**.",
+            record.tier.name(),
+            record.action
+        ))
+        .field("Message", content.render_discord(1024), false)
+        .field(
+            "Analysis",
+            format!("`{}`", truncate(&record.analysis, 1000)),
+            false,
+        )
+        .field("Sent", format!("<t:{}:f>", record.created_at), false)
+        .colour(Colour::BLUE)
+}
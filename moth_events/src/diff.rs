@@ -0,0 +1,262 @@
+//! Word and character level diffs, for showing what actually changed in an edit.
+
+use moth_ansi::{Color, Style, StyledText};
+
+/// Past this many token comparisons the diff gives up and reports a full replacement, the
+/// table is `old * new` in size.
+const MAX_CELLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Granularity {
+    /// Runs of whitespace and runs of everything else.
+    Word,
+    Char,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change<'a> {
+    pub kind: ChangeKind,
+    pub text: &'a str,
+}
+
+fn tokenize(text: &str, granularity: Granularity) -> Vec<&str> {
+    match granularity {
+        Granularity::Char => text
+            .char_indices()
+            .map(|(i, c)| &text[i..i + c.len_utf8()])
+            .collect(),
+        Granularity::Word => {
+            let mut tokens = Vec::new();
+            let mut start = 0;
+            let mut last_space = None;
+
+            for (i, c) in text.char_indices() {
+                let space = c.is_whitespace();
+                if last_space.is_some_and(|l| l != space) {
+                    tokens.push(&text[start..i]);
+                    start = i;
+                }
+                last_space = Some(space);
+            }
+
+            if start < text.len() {
+                tokens.push(&text[start..]);
+            }
+            tokens
+        }
+    }
+}
+
+/// Pushes `text` onto the last change if it has the same kind, so runs stay together.
+fn push<'a>(changes: &mut Vec<Change<'a>>, source: &'a str, kind: ChangeKind, text: &'a str) {
+    if let Some(last) = changes.last_mut() {
+        if last.kind == kind {
+            // tokens are adjacent slices of `source`, so the run can just be widened.
+            let start = last.text.as_ptr() as usize - source.as_ptr() as usize;
+            let end = text.as_ptr() as usize - source.as_ptr() as usize + text.len();
+            last.text = &source[start..end];
+            return;
+        }
+    }
+
+    changes.push(Change { kind, text });
+}
+
+/// Diffs `old` against `new` using the longest common subsequence of their tokens.
+#[must_use]
+pub fn diff<'a>(old: &'a str, new: &'a str, granularity: Granularity) -> Vec<Change<'a>> {
+    let a = tokenize(old, granularity);
+    let b = tokenize(new, granularity);
+
+    // strip the common prefix and suffix first, edits usually only touch the middle.
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let mid_a = &a[prefix..a.len() - suffix];
+    let mid_b = &b[prefix..b.len() - suffix];
+
+    let mut changes = Vec::new();
+    for token in &a[..prefix] {
+        push(&mut changes, old, ChangeKind::Equal, token);
+    }
+
+    if (mid_a.len() + 1) * (mid_b.len() + 1) > MAX_CELLS {
+        for token in mid_a {
+            push(&mut changes, old, ChangeKind::Delete, token);
+        }
+        for token in mid_b {
+            push(&mut changes, new, ChangeKind::Insert, token);
+        }
+    } else {
+        // lcs[i][j] is the LCS length of mid_a[i..] and mid_b[j..].
+        let width = mid_b.len() + 1;
+        let mut lcs = vec![0u32; (mid_a.len() + 1) * width];
+        for i in (0..mid_a.len()).rev() {
+            for j in (0..mid_b.len()).rev() {
+                lcs[i * width + j] = if mid_a[i] == mid_b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < mid_a.len() && j < mid_b.len() {
+            if mid_a[i] == mid_b[j] {
+                push(&mut changes, old, ChangeKind::Equal, mid_a[i]);
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+                push(&mut changes, old, ChangeKind::Delete, mid_a[i]);
+                i += 1;
+            } else {
+                push(&mut changes, new, ChangeKind::Insert, mid_b[j]);
+                j += 1;
+            }
+        }
+        for token in &mid_a[i..] {
+            push(&mut changes, old, ChangeKind::Delete, token);
+        }
+        for token in &mid_b[j..] {
+            push(&mut changes, new, ChangeKind::Insert, token);
+        }
+    }
+
+    for token in &a[a.len() - suffix..] {
+        push(&mut changes, old, ChangeKind::Equal, token);
+    }
+
+    changes
+}
+
+/// Deletions are struck through in red and insertions underlined in green, Discord doesn't
+/// support strikethrough so there deletions are only red.
+#[must_use]
+pub fn styled(changes: &[Change<'_>]) -> StyledText {
+    let mut text = StyledText::new();
+
+    for change in changes {
+        let style = match change.kind {
+            ChangeKind::Equal => Style::new(),
+            ChangeKind::Delete => Style::new().fg(Color::RED).strikethrough(),
+            ChangeKind::Insert => Style::new().fg(Color::GREEN).underline(),
+        };
+        text.push(change.text, style);
+    }
+
+    text
+}
+
+/// Renders for the console.
+#[must_use]
+pub fn render_ansi(changes: &[Change<'_>]) -> String {
+    styled(changes).render_console()
+}
+
+/// Renders as a Discord `ansi` code block no longer than `max_len`, changes that don't fit are
+/// cut off with an ellipsis.
+#[must_use]
+pub fn render_discord(changes: &[Change<'_>], max_len: usize) -> String {
+    styled(changes).render_discord(max_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(kind: ChangeKind, text: &str) -> Change<'_> {
+        Change { kind, text }
+    }
+
+    #[test]
+    fn identical_text_is_one_equal_run() {
+        assert_eq!(
+            diff("same text", "same text", Granularity::Word),
+            vec![change(ChangeKind::Equal, "same text")]
+        );
+        assert!(diff("", "", Granularity::Word).is_empty());
+    }
+
+    #[test]
+    fn replaced_word_keeps_the_surroundings() {
+        assert_eq!(
+            diff(
+                "the quick brown fox",
+                "the slow brown fox",
+                Granularity::Word
+            ),
+            vec![
+                change(ChangeKind::Equal, "the "),
+                change(ChangeKind::Delete, "quick"),
+                change(ChangeKind::Insert, "slow"),
+                change(ChangeKind::Equal, " brown fox"),
+            ]
+        );
+    }
+
+    #[test]
+    fn common_middle_is_found_between_changed_ends() {
+        assert_eq!(
+            diff("a b c d", "x b c y", Granularity::Word),
+            vec![
+                change(ChangeKind::Delete, "a"),
+                change(ChangeKind::Insert, "x"),
+                change(ChangeKind::Equal, " b c "),
+                change(ChangeKind::Delete, "d"),
+                change(ChangeKind::Insert, "y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn char_insertion() {
+        assert_eq!(
+            diff("abc", "abxc", Granularity::Char),
+            vec![
+                change(ChangeKind::Equal, "ab"),
+                change(ChangeKind::Insert, "x"),
+                change(ChangeKind::Equal, "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn chars_are_not_split_inside_code_points() {
+        assert_eq!(
+            diff("héllo", "hællo", Granularity::Char),
+            vec![
+                change(ChangeKind::Equal, "h"),
+                change(ChangeKind::Delete, "é"),
+                change(ChangeKind::Insert, "æ"),
+                change(ChangeKind::Equal, "llo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn oversized_diffs_are_a_full_replacement() {
+        let old = "a".repeat(2001);
+        let new = "b".repeat(2001);
+
+        assert_eq!(
+            diff(&old, &new, Granularity::Char),
+            vec![
+                change(ChangeKind::Delete, &old),
+                change(ChangeKind::Insert, &new),
+            ]
+        );
+    }
+}
@@ -4,6 +4,7 @@ use crate::helper::{
     sort_order_to_string,
 };
 
+use crate::modlog::log_event;
 use crate::{Data, Error};
 
 use lumi::serenity_prelude::audit_log::Action::VoiceChannelStatus;
@@ -12,6 +13,7 @@ use lumi::serenity_prelude::{
     GuildId, GuildThread, PartialGuildThread, UserId, VoiceChannelStatusAction,
 };
 use moth_ansi::{BLUE, HI_BLUE, RESET};
+use moth_core::event_log::{EventKind, LogRecord};
 
 use std::fmt::Write;
 use std::sync::Arc;
@@ -29,6 +31,14 @@ pub async fn channel_create(
         "{BLUE}[{}] #{} ({}) was created!{RESET}",
         guild_name, channel.base.name, kind
     );
+
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::ChannelCreate)
+            .guild(channel.base.guild_id)
+            .channel(channel.id.widen())
+            .after(channel.base.name.to_string()),
+    );
     Ok(())
 }
 
@@ -277,6 +287,16 @@ pub async fn channel_update(
     diff = diff.trim_end_matches('\n').to_string();
     if !diff.is_empty() {
         println!("{BLUE}[{guild_name}] #{channel_name} was updated! ({kind}){RESET}\n{diff}");
+
+        let mut record = LogRecord::new(EventKind::ChannelUpdate)
+            .guild(new.base.guild_id)
+            .channel(new.id.widen())
+            .after(new.base.name.to_string())
+            .changes(&moth_ansi::strip(&diff));
+        if let Some(old) = old {
+            record = record.before(old.base.name.to_string());
+        }
+        log_event(ctx, record);
     }
     Ok(())
 }
@@ -294,6 +314,14 @@ pub async fn channel_delete(
         guild_name, channel.base.name, kind
     );
 
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::ChannelDelete)
+            .guild(channel.base.guild_id)
+            .channel(channel.id.widen())
+            .before(channel.base.name.to_string()),
+    );
+
     Ok(())
 }
 
@@ -321,6 +349,15 @@ pub async fn thread_create(
         guild_name, thread.base.name, kind, parent_channel_name
     );
 
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::ThreadCreate)
+            .guild(guild_id)
+            .channel(thread.id.widen())
+            .user(thread.owner_id)
+            .after(thread.base.name.to_string()),
+    );
+
     Ok(())
 }
 
@@ -396,6 +433,16 @@ pub async fn thread_update(
              ({kind}){RESET}\n{diff}",
             new.base.name
         );
+
+        let mut record = LogRecord::new(EventKind::ThreadUpdate)
+            .guild(guild_id)
+            .channel(new.id.widen())
+            .after(new.base.name.to_string())
+            .changes(&diff);
+        if let Some(old) = old {
+            record = record.before(old.base.name.to_string());
+        }
+        log_event(ctx, record);
     }
 
     Ok(())
@@ -429,6 +476,14 @@ pub async fn thread_delete(
              #{parent_channel_name}!{RESET}"
         );
     }
+
+    let mut record = LogRecord::new(EventKind::ThreadDelete)
+        .guild(guild_id)
+        .channel(thread.id.widen());
+    if !channel_name.is_empty() {
+        record = record.before(channel_name);
+    }
+    log_event(ctx, record);
     Ok(())
 }
 
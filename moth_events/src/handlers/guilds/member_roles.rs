@@ -2,11 +2,13 @@ use std::borrow::Cow;
 
 use aformat::ToArrayString;
 use moth_ansi::MAGENTA;
+use moth_core::event_log::{EventKind, LogRecord};
 use serenity::all::audit_log::Action;
 use serenity::all::{AffectedRole, AuditLogEntry, Context, GuildId, MemberAction, UserId};
 use small_fixed_array::{FixedArray, FixedString};
 
 use crate::helper::{get_guild_name_override, get_user};
+use crate::modlog::log_event;
 
 pub(super) async fn handle(ctx: &Context, entry: &AuditLogEntry, guild_id: GuildId) {
     if let Action::Member(member_action) = &entry.action {
@@ -59,6 +61,26 @@ async fn log_role_change(
             return;
         }
 
+        let sign = if action == "added" { '+' } else { '-' };
+        let changes = roles
+            .iter()
+            .map(|r| format!("{sign} {} ({})", r.name, r.id))
+            .collect::<Vec<_>>()
+            .join("\n");
+        // the target is the member whose roles changed, the entry user is who changed them.
+        let target = entry
+            .target_id
+            .map(|t| UserId::new(t.get()))
+            .or(entry.user_id);
+        log_event(
+            ctx,
+            LogRecord::new(EventKind::MemberRoleUpdate)
+                .guild(guild_id)
+                .user(target)
+                .actor(entry.user_id)
+                .changes(&changes),
+        );
+
         let mod_name = get_user(ctx, guild_id, entry.user_id.unwrap())
             .await
             .map_or(Cow::Borrowed("UNKNOWN_USER"), |u| {
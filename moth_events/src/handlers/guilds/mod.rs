@@ -5,6 +5,7 @@ pub(crate) mod roles;
 
 use crate::{
     helper::{get_channel_name, get_guild_name_override, get_user},
+    modlog::log_event,
     Data, Error,
 };
 use lumi::serenity_prelude::{
@@ -13,6 +14,7 @@ use lumi::serenity_prelude::{
 };
 
 use moth_ansi::{RESET, YELLOW};
+use moth_core::event_log::{EventKind, LogRecord};
 
 use ::serenity::all::GenericChannelId;
 use moth_core::data::structs::Fuck;
@@ -60,6 +62,16 @@ pub async fn guild_member_addition(
         new_member.user.tag(),
         joined_user_id
     );
+
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::MemberJoin)
+            .guild(guild_id)
+            .user(joined_user_id)
+            .after(new_member.user.tag()),
+    );
+
+    crate::names::check_member(ctx, &data, None, new_member).await?;
     Ok(())
 }
 
@@ -78,6 +90,15 @@ pub async fn guild_member_removal(
         user.id
     );
 
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::MemberLeave)
+            .guild(*guild_id)
+            .user(user.id)
+            .before(user.tag()),
+    );
+
+    data.checked_names.remove(*guild_id, user.id);
     Ok(())
 }
 
@@ -1,5 +1,6 @@
-use crate::{helper::get_guild_name_override, Error};
+use crate::{helper::get_guild_name_override, modlog::log_event, Error};
 use moth_ansi::{HI_GREEN, MAGENTA, RED, RESET};
+use moth_core::event_log::{EventKind, LogRecord};
 use serenity::all::{Context, GuildId, Permissions, Role, RoleId};
 
 use std::fmt::Write;
@@ -14,6 +15,14 @@ pub(crate) async fn role_create(ctx: &Context, role: &Role) -> Result<(), Error>
         role.name
     );
 
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::RoleCreate)
+            .guild(role.guild_id)
+            .role(role.id)
+            .after(role.name.to_string()),
+    );
+
     Ok(())
 }
 
@@ -36,6 +45,14 @@ pub(crate) async fn role_delete(
         );
     }
 
+    let mut record = LogRecord::new(EventKind::RoleDelete)
+        .guild(guild_id)
+        .role(role_id);
+    if let Some(role) = role {
+        record = record.before(role.name.to_string());
+    }
+    log_event(ctx, record);
+
     Ok(())
 }
 
@@ -169,6 +186,18 @@ pub(crate) async fn role_update(
     if modified {
         string.strip_suffix('\n').unwrap_or(&string);
         println!("{string}");
+
+        // The first line is the header, the rest is the diff.
+        let diff = moth_ansi::strip(string.split_once('\n').map_or("", |(_, diff)| diff));
+        log_event(
+            ctx,
+            LogRecord::new(EventKind::RoleUpdate)
+                .guild(role.guild_id)
+                .role(role.id)
+                .before(old_role.name.to_string())
+                .after(role.name.to_string())
+                .changes(&diff),
+        );
     }
 
     Ok(())
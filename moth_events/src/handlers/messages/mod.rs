@@ -1,4 +1,3 @@
-use std::fmt::Write;
 use std::sync::Arc;
 
 mod anti_delete;
@@ -7,12 +6,16 @@ use ::serenity::all::GenericChannelId;
 pub use database::EMOJI_REGEX;
 use invites::moderate_invites;
 pub mod invites;
-mod responses;
+pub mod responses;
 
+use crate::diff::{self, ChangeKind, Granularity};
 use crate::helper::{get_channel_name, get_guild_name, get_guild_name_override};
+use crate::modlog::log_event;
+use crate::ocr::MessageFrames;
 use crate::{Data, Error};
 
-use moth_ansi::{CYAN, DIM, HI_BLACK, HI_RED, RESET};
+use moth_ansi::{CYAN, DIM, GREEN, HI_BLACK, HI_RED, RED, RESET};
+use moth_core::event_log::{EventKind, LogRecord};
 
 use database::{insert_deletion, insert_edit, insert_message};
 use lumi::serenity_prelude::{
@@ -22,7 +25,8 @@ use lumi::serenity_prelude::{
 
 pub async fn message(ctx: &serenity::Context, msg: &Message, data: Arc<Data>) -> Result<(), Error> {
     let mut dont_print = false;
-    let (content, patterns) = {
+    let guild_filter = msg.guild_id.and_then(|g| data.database.get_filter(g));
+    let (verdict, patterns) = {
         let config = &data.config.read().events;
 
         if should_skip_msg(
@@ -33,11 +37,19 @@ pub async fn message(ctx: &serenity::Context, msg: &Message, data: Arc<Data>) ->
             dont_print = true;
         }
 
-        let maybe_flagged =
-            moth_filter::filter_content(&msg.content, &config.badlist, &config.fixlist);
+        let verdict = moth_filter::check(
+            &msg.content,
+            moth_filter::Lists {
+                badlist: &config.badlist,
+                fixlist: &config.fixlist,
+                leet: &config.leet,
+                guild: guild_filter.as_deref(),
+            },
+        );
 
-        (maybe_flagged, config.regex.clone())
+        (verdict, config.regex.clone())
     };
+    let content = moth_filter::highlight(&msg.content, &verdict);
 
     let guild_id = msg.guild_id;
     let guild_name = get_guild_name_override(ctx, &data, guild_id);
@@ -54,15 +66,29 @@ pub async fn message(ctx: &serenity::Context, msg: &Message, data: Arc<Data>) ->
             attachments.as_deref().unwrap_or(""),
             embeds.as_deref().unwrap_or("")
         );
+
+        log_event(
+            ctx,
+            LogRecord::new(EventKind::MessageCreate)
+                .guild(guild_id)
+                .channel(msg.channel_id)
+                .user(msg.author.id)
+                .message(msg.id)
+                .after(message_fmt(msg)),
+        );
     }
 
     let guild_name = get_guild_name(ctx, guild_id);
+    // shared so images are only downloaded once between responses, automod and the image filter.
+    let frames = MessageFrames::new(&data, msg);
     let _ = tokio::join!(
         check_event_dm_regex(ctx, msg, &guild_name, patterns.as_deref()),
         handle_dm(ctx, msg),
-        insert_message(&data.database, msg),
+        insert_message(data.database.storage(), msg),
         moderate_invites(ctx, &data, msg),
-        responses::response_handler(ctx, msg)
+        responses::response_handler(ctx, msg, &frames),
+        crate::automod::automod(ctx, &data, msg, &verdict, &frames),
+        crate::images::check_images(ctx, &data, msg, &frames)
     );
 
     Ok(())
@@ -84,29 +110,65 @@ pub async fn message_edit(
             return Ok(());
         }
 
-        if old_message.content != new_message.content {
-            let (attachments, embeds) = attachments_embed_fmt(new_message);
+        let attachment_changes = attachment_changes(old_message, new_message);
+
+        if old_message.content != new_message.content || !attachment_changes.is_empty() {
+            let (_, embeds) = attachments_embed_fmt(new_message);
 
             println!(
-                "{CYAN}[{}] [#{}] A message by {RESET}{}{CYAN} was edited:",
+                "{CYAN}[{}] [#{}] A message by {RESET}{}{CYAN} was edited:{RESET}",
                 guild_name,
                 channel_name,
                 new_message.author.tag()
             );
-            println!(
-                "BEFORE: {}: {}",
-                new_message.author.tag(),
-                old_message.content
-            ); // potentially check old attachments in the future.
-            println!(
-                "AFTER: {}: {}{}{}{RESET}",
-                new_message.author.tag(),
-                new_message.content,
-                attachments.as_deref().unwrap_or(""),
-                embeds.as_deref().unwrap_or("")
+
+            if old_message.content != new_message.content {
+                let changes = diff::diff(
+                    &old_message.content,
+                    &new_message.content,
+                    Granularity::Word,
+                );
+                println!(
+                    "EDIT: {}: {}{CYAN}{}{RESET}",
+                    new_message.author.tag(),
+                    diff::render_ansi(&changes),
+                    embeds.as_deref().unwrap_or("")
+                );
+            }
+
+            if !attachment_changes.is_empty() {
+                let formatted = attachment_changes
+                    .iter()
+                    .map(|(kind, name)| match kind {
+                        ChangeKind::Delete => format!("{RED}- {name}{RESET}"),
+                        _ => format!("{GREEN}+ {name}{RESET}"),
+                    })
+                    .collect::<Vec<_>>();
+                println!("ATTACHMENTS: {}", formatted.join(", "));
+            }
+
+            let attachment_lines = attachment_changes
+                .iter()
+                .map(|(kind, name)| match kind {
+                    ChangeKind::Delete => format!("- attachment: {name}"),
+                    _ => format!("+ attachment: {name}"),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            log_event(
+                ctx,
+                LogRecord::new(EventKind::MessageEdit)
+                    .guild(guild_id)
+                    .channel(new_message.channel_id)
+                    .user(new_message.author.id)
+                    .message(new_message.id)
+                    .before(old_message.content.to_string())
+                    .after(new_message.content.to_string())
+                    .changes(&attachment_lines),
             );
 
-            let _ = insert_edit(&data.database, new_message).await;
+            let _ = insert_edit(data.database.storage(), new_message).await;
         }
     } else {
         println!(
@@ -152,12 +214,30 @@ pub async fn message_delete(
             embeds_fmt.as_deref().unwrap_or("")
         );
 
-        let _ = insert_deletion(&data.database, &message).await;
+        log_event(
+            ctx,
+            LogRecord::new(EventKind::MessageDelete)
+                .guild(guild_id)
+                .channel(channel_id)
+                .user(message.author.id)
+                .message(deleted_message_id)
+                .before(message_fmt(&message)),
+        );
+
+        let _ = insert_deletion(data.database.storage(), &message).await;
     } else {
         println!(
             "{HI_RED}{DIM}A message (ID:{deleted_message_id}) was deleted but was not in \
              cache{RESET}"
         );
+
+        log_event(
+            ctx,
+            LogRecord::new(EventKind::MessageDelete)
+                .guild(guild_id)
+                .channel(channel_id)
+                .message(deleted_message_id),
+        );
     }
 
     if let Some(guild_id) = guild_id {
@@ -324,6 +404,35 @@ pub fn attachments_embed_fmt(new_message: &Message) -> (Option<String>, Option<S
     (attachments_fmt, embeds_fmt)
 }
 
+/// Attachments that were removed or added by an edit, by filename.
+fn attachment_changes(old: &Message, new: &Message) -> Vec<(ChangeKind, String)> {
+    let removed = old
+        .attachments
+        .iter()
+        .filter(|a| !new.attachments.iter().any(|n| n.id == a.id))
+        .map(|a| (ChangeKind::Delete, a.filename.to_string()));
+    let added = new
+        .attachments
+        .iter()
+        .filter(|a| !old.attachments.iter().any(|o| o.id == a.id))
+        .map(|a| (ChangeKind::Insert, a.filename.to_string()));
+
+    removed.chain(added).collect()
+}
+
+/// Message content followed by the same attachment and embed summary the console prints.
+#[must_use]
+pub fn message_fmt(msg: &Message) -> String {
+    let (attachments, embeds) = attachments_embed_fmt(msg);
+
+    format!(
+        "{}{}{}",
+        msg.content,
+        attachments.as_deref().unwrap_or(""),
+        embeds.as_deref().unwrap_or("")
+    )
+}
+
 #[must_use]
 pub fn author_string(ctx: &serenity::Context, msg: &Message) -> String {
     // No member meaning no roles.
@@ -335,9 +444,20 @@ pub fn author_string(ctx: &serenity::Context, msg: &Message) -> String {
 
     let guild = msg.guild(&ctx.cache).unwrap();
 
+    let mut prefix = String::new();
+    if let Some(c) = role_colour(&guild, &member.roles).and_then(|c| moth_ansi::from_colour(c.0)) {
+        prefix.push_str(&c);
+    }
+
+    format!("{prefix}{username}{RESET}")
+}
+
+/// The colour of the highest coloured role out of `roles`, which is what the client shows.
+#[must_use]
+pub fn role_colour(guild: &serenity::Guild, roles: &[serenity::RoleId]) -> Option<Colour> {
     let mut highest: Option<&serenity::Role> = None;
 
-    for role_id in &member.roles {
+    for role_id in roles {
         if let Some(role) = guild.roles.get(role_id) {
             if role.colour.0 == 000000 {
                 continue;
@@ -356,13 +476,5 @@ pub fn author_string(ctx: &serenity::Context, msg: &Message) -> String {
         }
     }
 
-    let mut prefix = String::new();
-    if let Some(hr) = highest {
-        let c = hr.colour;
-        if hr.colour.0 != 0 {
-            write!(prefix, "\x1B[38;2;{};{};{}m", c.r(), c.g(), c.b()).unwrap();
-        }
-    }
-
-    format!("{prefix}{username}{RESET}")
+    highest.map(|r| r.colour)
 }
@@ -1,9 +1,156 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{borrow::Cow, collections::HashSet, sync::Arc, time::Duration};
+
+use lumi::serenity_prelude::{self as serenity, GenericChannelId};
+use moth_ansi::{RED, RESET};
+use moth_core::data::responses::{
+    render_template, DetectionType, GuildCache, RegexData, ResponseType, TemplateContext,
+};
+use moth_core::ocr::OcrPriority;
+
+use crate::ocr::MessageFrames;
+use crate::Data;
+
+/// How often suppressed regex responses are counted in the database.
+const SUPPRESSED_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where a message was sent, as far as the scope of a regex is concerned.
+#[derive(Clone, Copy, Debug)]
+pub struct Location {
+    pub channel_id: GenericChannelId,
+    /// The channel the thread is in, when sent in a thread.
+    pub thread_parent: Option<GenericChannelId>,
+    pub category: Option<GenericChannelId>,
+}
+
+impl Location {
+    #[must_use]
+    pub fn in_thread(&self) -> bool {
+        self.thread_parent.is_some()
+    }
+}
 
-use lumi::serenity_prelude as serenity;
-use moth_core::data::responses::DetectionType;
+/// Why a regex doesn't apply to a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Skip {
+    /// Scoped to a channel or category the message isn't in.
+    OutOfScope,
+    /// The channel, thread or category is one of the regex's exceptions.
+    Exception(GenericChannelId),
+    /// Sent in a thread, and the regex doesn't recurse into threads.
+    RecurseThreads,
+    /// Sent in a channel of a category regex that doesn't recurse into channels.
+    RecurseChannels,
+}
+
+impl std::fmt::Display for Skip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Skip::OutOfScope => write!(f, "out of scope"),
+            Skip::Exception(channel_id) => write!(f, "<#{channel_id}> is an exception"),
+            Skip::RecurseThreads => write!(f, "doesn't recurse into threads"),
+            Skip::RecurseChannels => write!(f, "doesn't recurse into the category's channels"),
+        }
+    }
+}
+
+/// Finds the thread parent and category of `channel_id` in the cache.
+#[must_use]
+pub fn locate(
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    channel_id: GenericChannelId,
+) -> Location {
+    let mut location = Location {
+        channel_id,
+        thread_parent: None,
+        category: None,
+    };
 
-pub async fn response_handler(ctx: &serenity::Context, msg: &serenity::Message) {
+    let Some(guild) = ctx.cache.guild(guild_id) else {
+        return location;
+    };
+
+    if let Some(channel) = guild.channels.get(&channel_id.expect_channel()) {
+        location.category = channel.parent_id.map(serenity::ChannelId::widen);
+    } else if let Some(thread) = guild
+        .threads
+        .iter()
+        .find(|t| t.id == channel_id.expect_thread())
+    {
+        location.thread_parent = Some(thread.parent_id.widen());
+        location.category = guild
+            .channels
+            .get(&thread.parent_id)
+            .and_then(|c| c.parent_id)
+            .map(serenity::ChannelId::widen);
+    }
+
+    location
+}
+
+/// Every regex of the guild, with the channel or category it's scoped to.
+pub fn scoped(
+    regexes: &GuildCache,
+) -> impl Iterator<Item = (Option<GenericChannelId>, &RegexData)> {
+    let global = regexes.global.iter().map(|r| (None, r));
+    let channel = regexes
+        .channel
+        .iter()
+        .flat_map(|(channel_id, r)| r.iter().map(move |r| (Some(*channel_id), r)));
+
+    global.chain(channel)
+}
+
+/// If a regex scoped to `scope` applies to a message sent at `location`.
+pub fn check_scope(
+    scope: Option<GenericChannelId>,
+    regex: &RegexData,
+    location: &Location,
+) -> Result<(), Skip> {
+    if let Some(scope) = scope {
+        if scope == location.channel_id {
+            return Ok(());
+        }
+
+        if Some(scope) == location.thread_parent {
+            if !regex.recurse_threads {
+                return Err(Skip::RecurseThreads);
+            }
+        } else if Some(scope) == location.category {
+            if !regex.recurse_channels {
+                return Err(Skip::RecurseChannels);
+            }
+            if location.in_thread() && !regex.recurse_threads {
+                return Err(Skip::RecurseThreads);
+            }
+        } else {
+            return Err(Skip::OutOfScope);
+        }
+    } else if location.in_thread() && !regex.recurse_threads {
+        return Err(Skip::RecurseThreads);
+    }
+
+    let within = [
+        Some(location.channel_id),
+        location.thread_parent,
+        location.category,
+    ];
+    if let Some(exception) = within
+        .into_iter()
+        .flatten()
+        .find(|c| regex.exceptions.contains(c))
+    {
+        return Err(Skip::Exception(exception));
+    }
+
+    Ok(())
+}
+
+pub async fn response_handler(
+    ctx: &serenity::Context,
+    msg: &serenity::Message,
+    frames: &MessageFrames<'_>,
+) {
     if msg.author.id == ctx.cache.current_user().id {
         return;
     }
@@ -19,119 +166,101 @@ pub async fn response_handler(ctx: &serenity::Context, msg: &serenity::Message)
     let mut text_response = Vec::new();
     let mut emoji_responses = Vec::new();
 
-    let mut requires_ocr = false;
-
-    // Check if OCR is needed
-    if let Some(channel_regexes) = regexes.channel.get(&msg.channel_id) {
-        for regex in channel_regexes {
-            if regex.detection_type.contains(DetectionType::OCR) {
-                requires_ocr = true;
-                break;
-            }
-        }
-    }
-
-    for regex in &regexes.global {
-        if regex.detection_type.contains(DetectionType::OCR) {
-            requires_ocr = true;
-            break;
-        }
-    }
+    let location = locate(ctx, guild_id, msg.channel_id);
+    let applicable = scoped(&regexes)
+        .filter(|(scope, regex)| check_scope(*scope, regex, &location).is_ok())
+        .map(|(_, regex)| regex)
+        .collect::<Vec<_>>();
+
+    // the languages OCR regexes want images read in.
+    let guild_language = data.database.get_ocr_language(guild_id);
+    let ocr_language = |regex: &RegexData| {
+        regex
+            .ocr_language
+            .clone()
+            .unwrap_or_else(|| guild_language.clone())
+    };
 
-    // Process OCR once if needed
-    let mut ocr_texts = Vec::new();
-    if requires_ocr {
-        ocr_texts = process_ocr_attachments(&data, msg).await;
-    }
+    let languages = applicable
+        .iter()
+        .filter(|regex| regex.detection_type.contains(DetectionType::OCR))
+        .map(|regex| ocr_language(regex))
+        .collect::<HashSet<_>>();
+
+    // Process OCR once per language if needed
+    let ocr_texts = frames.read(&languages, OcrPriority::Normal).await;
+
+    let guild_name = ctx
+        .cache
+        .guild(guild_id)
+        .map(|g| g.name.to_string())
+        .unwrap_or_default();
+    let template_context = TemplateContext {
+        user_id: msg.author.id,
+        user_name: msg.author.display_name(),
+        channel_id: msg.channel_id,
+        guild_name: &guild_name,
+    };
 
-    // Process text-based detection
-    if let Some(channel_regexes) = regexes.channel.get(&msg.channel_id) {
-        for regex in channel_regexes {
-            if regex.detection_type.contains(DetectionType::CONTENT) {
-                let content = extract_message_content(msg);
-                if regex.pattern.is_match(&content) {
-                    match &regex.response {
-                        moth_core::data::responses::ResponseType::Message(msg) => {
-                            text_response.push(msg.clone());
-                        }
-                        moth_core::data::responses::ResponseType::Emoji(reaction_type) => {
-                            emoji_responses.push(reaction_type.clone());
-                        }
-                    }
-                }
-            }
+    let content = extract_message_content(msg);
+    for regex in applicable {
+        let mut captures = if regex.detection_type.contains(DetectionType::CONTENT) {
+            regex.pattern.captures(&content)
+        } else {
+            None
+        };
 
-            if regex.detection_type.contains(DetectionType::OCR) {
-                for ocr_text in &ocr_texts {
-                    if regex.pattern.is_match(ocr_text) {
-                        match &regex.response {
-                            moth_core::data::responses::ResponseType::Message(msg) => {
-                                text_response.push(msg.clone());
-                            }
-                            moth_core::data::responses::ResponseType::Emoji(reaction_type) => {
-                                emoji_responses.push(reaction_type.clone());
-                            }
-                        }
-                    }
-                }
-            }
+        if captures.is_none() && regex.detection_type.contains(DetectionType::OCR) {
+            let language = ocr_language(regex);
+            captures = ocr_texts
+                .iter()
+                .filter(|(l, _)| *l == language)
+                .find_map(|(_, ocr_text)| regex.pattern.captures(ocr_text));
         }
-    }
 
-    for regex in &regexes.global {
-        if regex.exceptions.contains(&msg.channel_id) {
+        let Some(captures) = captures else {
             continue;
-        }
-
-        let (is_channel, category_id) = is_channel(ctx, msg);
+        };
 
-        if !is_channel && !regex.recurse_threads {
+        if !data
+            .database
+            .trigger_regex(regex, msg.channel_id, msg.author.id)
+        {
             continue;
         }
 
-        if let Some(category_id) = category_id {
-            if regex.exceptions.contains(&category_id.widen()) {
-                continue;
+        match &regex.response {
+            ResponseType::Message(template) => {
+                text_response.push(render_template(
+                    template,
+                    &template_context,
+                    Some(&captures),
+                ));
             }
-        }
-
-        if regex.detection_type.contains(DetectionType::CONTENT) {
-            let content = extract_message_content(msg);
-            if regex.pattern.is_match(&content) {
-                match &regex.response {
-                    moth_core::data::responses::ResponseType::Message(msg) => {
-                        text_response.push(msg.clone());
-                    }
-                    moth_core::data::responses::ResponseType::Emoji(reaction_type) => {
-                        emoji_responses.push(reaction_type.clone());
-                    }
-                }
+            ResponseType::Emoji(reaction_type) => {
+                emoji_responses.push(reaction_type.clone());
             }
         }
+    }
 
-        if regex.detection_type.contains(DetectionType::OCR) {
-            for ocr_text in &ocr_texts {
-                if regex.pattern.is_match(ocr_text) {
-                    match &regex.response {
-                        moth_core::data::responses::ResponseType::Message(msg) => {
-                            text_response.push(msg.clone());
-                        }
-                        moth_core::data::responses::ResponseType::Emoji(reaction_type) => {
-                            emoji_responses.push(reaction_type.clone());
-                        }
-                    }
-                }
-            }
-        }
+    if text_response.is_empty() && emoji_responses.is_empty() {
+        return;
     }
 
     if let Ok(perms) = prefix_bot_perms(ctx, guild_id, msg.channel_id) {
-        if perms.send_messages() {
+        if perms.send_messages() && !text_response.is_empty() {
             let response = text_response.join("\n\n");
 
-            let _ = msg
-                .reply(&ctx.http, response.chars().take(2000).collect::<String>())
-                .await;
+            // only the author can be pinged, whatever the template or their message contains.
+            let mentions = serenity::CreateAllowedMentions::new()
+                .users(vec![msg.author.id])
+                .replied_user(true);
+
+            let builder = serenity::CreateMessage::new()
+                .content(response.chars().take(2000).collect::<String>())
+                .reference_message(msg)
+                .allowed_mentions(mentions);
+            let _ = msg.channel_id.send_message(&ctx.http, builder).await;
         }
 
         if perms.add_reactions() {
@@ -189,7 +318,8 @@ pub fn prefix_bot_perms(
 }
 
 /// Extracts the message content while handling possible snapshot data.
-fn extract_message_content(msg: &serenity::Message) -> Cow<'_, str> {
+#[must_use]
+pub fn extract_message_content(msg: &serenity::Message) -> Cow<'_, str> {
     if let Some(snapshot) = msg.message_snapshots.first() {
         Cow::Borrowed(snapshot.content.as_str())
     } else {
@@ -197,71 +327,16 @@ fn extract_message_content(msg: &serenity::Message) -> Cow<'_, str> {
     }
 }
 
-/// Handles OCR processing for image attachments ONCE and returns the extracted text.
-async fn process_ocr_attachments(data: &Arc<crate::Data>, msg: &serenity::Message) -> Vec<String> {
-    let mut extracted_texts = Vec::new();
+/// Saves the suppressed counts of regex responses every [`SUPPRESSED_FLUSH_INTERVAL`].
+pub fn spawn_suppressed_flusher(data: Arc<Data>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SUPPRESSED_FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
 
-    for attachment in &msg.attachments {
-        if attachment.size >= 10_000_000 {
-            continue; // Skip large files
-        }
-
-        if let Some(content_type) = &attachment.content_type {
-            if matches!(
-                content_type.as_str(),
-                "image/jpeg" | "image/jpg" | "image/png" | "image/webp"
-            ) {
-                if let Ok(bytes) = attachment.download().await {
-                    match data.ocr_engine.process(bytes).await {
-                        Ok(string) => {
-                            extracted_texts.push(string);
-                        }
-                        Err(e) => println!("Failed to process image with OCR engine: {e}"),
-                    }
-                }
+            if let Err(e) = data.database.flush_suppressed().await {
+                println!("{RED}Failed to save suppressed response counts: {e}{RESET}");
             }
         }
-    }
-
-    extracted_texts
-}
-
-/// returns true for channel, otherwise false, second bool is if in category (only matters if first is true)
-fn is_channel(
-    ctx: &serenity::Context,
-    msg: &serenity::Message,
-) -> (bool, Option<serenity::ChannelId>) {
-    let Some(guild) = ctx.cache.guild(msg.guild_id.unwrap()) else {
-        return (true, None);
-    };
-
-    if let Some(channel) = guild
-        .channels
-        .iter()
-        .find(|c| c.id == msg.channel_id.expect_channel())
-    {
-        return (true, channel.parent_id);
-    }
-
-    (false, None)
-}
-
-/* fn effective_channel(
-    ctx: &serenity::Context,
-    msg: &serenity::Message,
-) -> Option<serenity::ChannelId> {
-    let Some(guild) = ctx.cache.guild(msg.guild_id.unwrap()) else {
-        return None
-    };
-
-    if let Some(channel) = guild.channels.iter().find(|c| c.id == msg.channel_id) {
-        return channel.parent_id
-    }
-
-    if guild.threads.iter().find(|t| t.id == msg.channel_id).is_some() {
-        Some(msg.channel_id)
-    } else {
-        None
-    }
+    });
 }
- */
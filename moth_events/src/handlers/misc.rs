@@ -18,14 +18,18 @@ pub async fn ready(ctx: &serenity::Context, ready: &Ready, data: Arc<Data>) -> R
     let is_last_shard = (ctx.shard_id.0 + 1) == shard_count.get();
 
     if is_last_shard && !data.has_started.swap(true, Ordering::SeqCst) {
-        finalize_start(&data);
+        finalize_start(ctx, &data);
         println!("Logged in as {}", ready.user.tag());
     }
 
     Ok(())
 }
 
-fn finalize_start(data: &Arc<Data>) {
+fn finalize_start(ctx: &serenity::Context, data: &Arc<Data>) {
+    crate::modlog::spawn_flusher(ctx.clone(), data.clone());
+    crate::ocr::spawn_cache_pruner(data.clone());
+    crate::handlers::messages::responses::spawn_suppressed_flusher(data.clone());
+
     let data_clone = data.clone();
 
     tokio::spawn(async move {
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use crate::helper::{get_channel_name, get_guild_name_override, get_user};
+use crate::modlog::log_event;
 use crate::{Data, Error};
 
 mod database;
@@ -8,6 +9,7 @@ mod database;
 use database::*;
 
 use moth_ansi::{HI_MAGENTA, RESET};
+use moth_core::event_log::{EventKind, LogRecord};
 
 use lumi::serenity_prelude::{self as serenity, Reaction};
 
@@ -45,7 +47,23 @@ pub async fn reaction_add(
         guild_name, channel_name, user_name, add_reaction.emoji
     );
 
-    let _ = insert_addition(&data.database, guild_id.unwrap(), user_id, add_reaction).await;
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::ReactionAdd)
+            .guild(guild_id)
+            .channel(add_reaction.channel_id)
+            .user(user_id)
+            .message(add_reaction.message_id)
+            .after(add_reaction.emoji.to_string()),
+    );
+
+    let _ = insert_addition(
+        data.database.storage(),
+        guild_id.unwrap(),
+        user_id,
+        add_reaction,
+    )
+    .await;
 
     if add_reaction.guild_id == Some(data.starboard_config.guild_id) {
         if let serenity::ReactionType::Unicode(ref unicode) = add_reaction.emoji {
@@ -88,7 +106,23 @@ pub async fn reaction_remove(
         guild_name, channel_name, user_name, removed_reaction.emoji
     );
 
-    insert_removal(&data.database, guild_id.unwrap(), user_id, removed_reaction).await?;
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::ReactionRemove)
+            .guild(guild_id)
+            .channel(removed_reaction.channel_id)
+            .user(user_id)
+            .message(removed_reaction.message_id)
+            .after(removed_reaction.emoji.to_string()),
+    );
+
+    insert_removal(
+        data.database.storage(),
+        guild_id.unwrap(),
+        user_id,
+        removed_reaction,
+    )
+    .await?;
 
     if removed_reaction.guild_id == Some(data.starboard_config.guild_id) {
         if let serenity::ReactionType::Unicode(ref unicode) = removed_reaction.emoji {
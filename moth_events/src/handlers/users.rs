@@ -6,11 +6,12 @@ use lumi::serenity_prelude::{
     Member,
 };
 use moth_ansi::{HI_GREEN, RESET};
+use moth_core::event_log::{EventKind, LogRecord};
 
 use ::serenity::all::GenericChannelId;
 use small_fixed_array::FixedString;
 
-use crate::{helper::get_guild_name_override, Data, Error};
+use crate::{helper::get_guild_name_override, modlog::log_event, Data, Error};
 
 pub async fn guild_member_update(
     ctx: &serenity::Context,
@@ -22,6 +23,10 @@ pub async fn guild_member_update(
     let guild_id = event.guild_id;
     let guild_name = get_guild_name_override(ctx, &data, Some(guild_id));
 
+    if let Some(new_member) = new {
+        crate::names::check_member(ctx, &data, old_if_available.as_ref(), new_member).await?;
+    }
+
     if let Some(old_member) = old_if_available {
         if let Some(new_member) = new {
             let old_nickname = old_member.nick.as_deref().unwrap_or("None");
@@ -36,6 +41,16 @@ pub async fn guild_member_update(
                     new_nickname,
                     new_member.user.id
                 );
+
+                log_event(
+                    ctx,
+                    LogRecord::new(EventKind::MemberUpdate)
+                        .guild(guild_id)
+                        .user(new_member.user.id)
+                        .before(old_nickname)
+                        .after(new_nickname)
+                        .changes("nickname"),
+                );
             }
 
             if old_member.user.tag() != new_member.user.tag() {
@@ -45,6 +60,16 @@ pub async fn guild_member_update(
                     new_member.user.tag(),
                     new_member.user.id
                 );
+
+                log_event(
+                    ctx,
+                    LogRecord::new(EventKind::MemberUpdate)
+                        .guild(guild_id)
+                        .user(new_member.user.id)
+                        .before(old_member.user.tag())
+                        .after(new_member.user.tag())
+                        .changes("username"),
+                );
             }
             if old_member.user.global_name != new_member.user.global_name {
                 println!(
@@ -62,6 +87,16 @@ pub async fn guild_member_update(
                         .unwrap_or(&FixedString::from_str_trunc("None")),
                     new_member.user.id
                 );
+
+                log_event(
+                    ctx,
+                    LogRecord::new(EventKind::MemberUpdate)
+                        .guild(guild_id)
+                        .user(new_member.user.id)
+                        .before(old_member.user.global_name.as_deref().unwrap_or("None"))
+                        .after(new_member.user.global_name.as_deref().unwrap_or("None"))
+                        .changes("display_name"),
+                );
             }
         }
 
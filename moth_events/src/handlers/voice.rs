@@ -4,11 +4,13 @@ use std::borrow::Cow;
 
 use crate::{
     helper::{get_guild_name_override, get_user},
+    modlog::log_event,
     Error,
 };
 use lumi::serenity_prelude::{self as serenity, VoiceState};
 use moth_ansi::{GREEN, RESET};
 use moth_core::data::structs::Data;
+use moth_core::event_log::{EventKind, LogRecord};
 
 pub async fn voice_state_update(
     ctx: &serenity::Context,
@@ -77,6 +79,16 @@ async fn handle_switch(
             "{GREEN}[{guild_name}] {user_name}: {old_name} (ID:{old_id}) -> {new_name} \
              (ID:{new_id}){RESET}"
         );
+
+        log_event(
+            ctx,
+            LogRecord::new(EventKind::VoiceMove)
+                .guild(new.guild_id)
+                .channel(new_id.widen())
+                .user(new.user_id)
+                .before(old_name.to_string())
+                .after(new_name.to_string()),
+        );
     }
 
     maybe_handle(ctx, new).await?;
@@ -110,6 +122,15 @@ async fn handle_leave(
     let guild_name = get_guild_name_override(ctx, &ctx.data(), new.guild_id);
 
     println!("{GREEN}[{guild_name}] {user_name} left {channel_name} (ID:{channel_id}){RESET}");
+
+    log_event(
+        ctx,
+        LogRecord::new(EventKind::VoiceLeave)
+            .guild(new.guild_id)
+            .channel(channel_id.widen())
+            .user(new.user_id)
+            .before(channel_name),
+    );
     Ok(())
 }
 async fn handle_joins(ctx: &serenity::Context, new: &VoiceState) -> Result<(), Error> {
@@ -139,6 +160,15 @@ async fn handle_joins(ctx: &serenity::Context, new: &VoiceState) -> Result<(), E
         println!(
             "{GREEN}[{guild_name}] {user_name} joined {channel_name} (ID:{channel_id}){RESET}"
         );
+
+        log_event(
+            ctx,
+            LogRecord::new(EventKind::VoiceJoin)
+                .guild(new.guild_id)
+                .channel(channel_id.widen())
+                .user(new.user_id)
+                .after(channel_name.to_string()),
+        );
     }
 
     maybe_handle(ctx, new).await?;
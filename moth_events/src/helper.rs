@@ -75,7 +75,7 @@ pub async fn get_channel_name(
         return "None".to_string();
     }
 
-    let name = retrieve_cached_name(ctx, guild_id.unwrap(), channel_id);
+    let name = cached_channel_name(&ctx.cache, guild_id.unwrap(), channel_id);
 
     if let Some(name) = name {
         return name;
@@ -91,12 +91,12 @@ pub async fn get_channel_name(
 }
 
 /// get the name from the guild cache if available.
-fn retrieve_cached_name(
-    ctx: &serenity::Context,
+pub fn cached_channel_name(
+    cache: &serenity::Cache,
     guild_id: GuildId,
     channel_id: GenericChannelId,
 ) -> Option<String> {
-    let guild_cache = ctx.cache.guild(guild_id)?;
+    let guild_cache = cache.guild(guild_id)?;
 
     if let Some(channel) = guild_cache.channels.get(&channel_id.expect_channel()) {
         Some(channel.base.name.to_string())
@@ -0,0 +1,150 @@
+//! Hashes image attachments in guilds that enabled the image filter, checking them against the
+//! guild's blocklist and keeping the hashes for repost lookups.
+
+use std::sync::Arc;
+
+use lumi::serenity_prelude::{
+    self as serenity, Colour, CreateEmbed, CreateEmbedFooter, CreateMessage, Message,
+};
+use moth_ansi::{HI_BLACK, RED, RESET};
+use moth_core::data::images::{BlockedImage, ImageAction};
+
+use crate::helper::get_guild_name_override;
+use crate::ocr::{download, MessageFrames};
+use crate::{Data, Error};
+
+/// Images larger than this aren't hashed.
+const MAX_HASHED_SIZE: usize = 10_000_000;
+
+/// Downloads the image at `url` and returns its perceptual hash.
+pub async fn hash_image(data: &Arc<Data>, url: &str) -> Result<u64, Error> {
+    let bytes = download(data, url, MAX_HASHED_SIZE).await?;
+    let hash = tokio::task::spawn_blocking(move || moth_core::phash::dhash(&bytes)).await??;
+
+    Ok(hash)
+}
+
+/// Hashes the image attachments of `msg`, from the shared downloads where they fit in them,
+/// checking them against the blocklist.
+pub async fn check_images(
+    ctx: &serenity::Context,
+    data: &Arc<Data>,
+    msg: &Message,
+    frames: &MessageFrames<'_>,
+) -> Result<(), Error> {
+    let Some(guild_id) = msg.guild_id else {
+        return Ok(());
+    };
+    let Some(config) = data.database.get_image_filter(guild_id) else {
+        return Ok(());
+    };
+
+    if msg.author.bot() {
+        return Ok(());
+    }
+
+    let mut hashes = Vec::new();
+    for (attachment_id, bytes) in frames.attachments().await {
+        let bytes = bytes.to_vec();
+        match tokio::task::spawn_blocking(move || moth_core::phash::dhash(&bytes)).await {
+            Ok(Ok(hash)) => hashes.push((attachment_id, hash)),
+            Ok(Err(e)) => {
+                println!("{HI_BLACK}Failed to hash attachment {attachment_id}: {e}{RESET}");
+            }
+            Err(e) => {
+                println!("{HI_BLACK}Hashing attachment {attachment_id} failed: {e}{RESET}");
+            }
+        }
+    }
+
+    // the OCR budget is shared by the whole message, so the attachments past it are downloaded
+    // on their own rather than going unhashed.
+    for attachment in frames.skipped_attachments().await {
+        match hash_image(data, &attachment.url).await {
+            Ok(hash) => hashes.push((attachment.id, hash)),
+            Err(e) => println!(
+                "{HI_BLACK}Failed to hash attachment {}: {e}{RESET}",
+                attachment.id
+            ),
+        }
+    }
+
+    for (attachment_id, hash) in hashes {
+        data.database
+            .insert_image_hash(attachment_id, guild_id, hash)
+            .await?;
+
+        let Some((blocked, distance)) =
+            data.database
+                .match_blocked_image(guild_id, hash, config.max_distance)
+        else {
+            continue;
+        };
+
+        let mut deleted = false;
+        if config.action == ImageAction::Delete {
+            let reason = format!("Blocked image: {}", blocked.label);
+            data.modlog_queue.mark_own_deletions([msg.id]);
+            match msg.delete(&ctx.http, Some(&reason)).await {
+                Ok(()) => deleted = true,
+                Err(e) => println!("{HI_BLACK}Failed to delete {}: {e}{RESET}", msg.id),
+            }
+        }
+
+        alert(ctx, data, msg, &blocked, distance, deleted).await;
+
+        // the rest of the message doesn't matter once it's gone.
+        if deleted {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+async fn alert(
+    ctx: &serenity::Context,
+    data: &Arc<Data>,
+    msg: &Message,
+    blocked: &BlockedImage,
+    distance: u32,
+    deleted: bool,
+) {
+    let guild_name = get_guild_name_override(ctx, data, msg.guild_id);
+    println!(
+        "{RED}[{guild_name}] Blocked image #{} ({}) posted by {}, {distance} bits apart{RESET}",
+        blocked.id,
+        blocked.label,
+        msg.author.tag()
+    );
+
+    let Some(modlog) = msg.guild_id.and_then(|g| data.database.get_modlog(g)) else {
+        return;
+    };
+
+    let action = if deleted { "Deleted" } else { "Alerted" };
+    let embed = CreateEmbed::new()
+        .title("Blocked image")
+        .description(format!(
+            "**User:** <@{}>\n**Message:** {}\n**Matched:** #{} {}\n**Distance:** {distance} \
+             bits\n**Action:** {action}",
+            msg.author.id,
+            msg.link(),
+            blocked.id,
+            blocked.label
+        ))
+        .colour(Colour::RED)
+        .footer(CreateEmbedFooter::new(format!(
+            "User ID: {}",
+            msg.author.id
+        )))
+        .timestamp(serenity::Timestamp::now());
+
+    let builder = CreateMessage::new().embed(embed);
+    if let Err(e) = modlog.channel_id.send_message(&ctx.http, builder).await {
+        println!(
+            "Failed to post to modlog channel {}: {e}",
+            modlog.channel_id
+        );
+    }
+}
@@ -16,16 +16,45 @@
 use lumi::serenity_prelude::{self as serenity, FullEvent};
 use moth_core::data::structs::{Data, Error};
 
+pub mod automod;
+pub mod diff;
 pub mod helper;
+pub mod images;
+pub mod modlog;
+pub mod names;
+pub mod ocr;
+pub mod recorder;
 
 pub mod handlers;
 use handlers::*;
 
-pub struct Handler;
+pub struct Handler {
+    recorder: Option<recorder::EventRecorder>,
+}
+
+impl Handler {
+    /// Creates the handler, recording events if `MOTH_RECORD_EVENTS` is set.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            recorder: recorder::EventRecorder::from_env(),
+        }
+    }
+}
+
+impl Default for Handler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[serenity::async_trait]
 impl serenity::EventHandler for Handler {
     async fn dispatch(&self, ctx: &serenity::Context, event: &serenity::FullEvent) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(event);
+        }
+
         let _ = event_handler(ctx, event).await;
     }
 }
@@ -67,6 +96,12 @@ pub async fn event_handler(
         FullEvent::GuildCreate { guild, is_new, .. } => {
             guilds::guild_create(ctx, guild, is_new).await?;
         }
+        FullEvent::GuildDelete { incomplete, .. } => {
+            // unavailable guilds are outages, the bot is still in them.
+            if !incomplete.unavailable {
+                data.checked_names.remove_guild(incomplete.id);
+            }
+        }
         FullEvent::GuildMemberAddition { new_member, .. } => {
             guilds::guild_member_addition(ctx, data, new_member).await?;
         }
@@ -157,6 +192,11 @@ pub async fn event_handler(
         FullEvent::Ready { data_about_bot, .. } => {
             misc::ready(ctx, data_about_bot, data).await?;
         }
+        FullEvent::PresenceUpdate {
+            old_data, new_data, ..
+        } => {
+            names::check_presence(ctx, &data, old_data.as_ref(), new_data).await?;
+        }
         FullEvent::GuildMembersChunk { chunk, .. } => {
             println!(
                 "Chunk recieved containing {} members: {}/{}",
@@ -167,6 +207,7 @@ pub async fn event_handler(
         }
         FullEvent::InteractionCreate { interaction, .. } => {
             if let Some(component) = interaction.as_message_component() {
+                automod::handle_component(ctx, data.clone(), component).await?;
                 moth_starboard::handle_component(ctx, data, component).await?;
             }
         }
@@ -0,0 +1,334 @@
+//! Mirrors [`LogRecord`]s to each guild's modlog channel as embeds.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use lumi::serenity_prelude::audit_log::{Action, ChannelAction, RoleAction, ThreadAction};
+use lumi::serenity_prelude::{
+    self as serenity, AuditLogs, Colour, CreateEmbed, CreateEmbedFooter, GuildId,
+};
+use moth_core::data::modlog::ModlogEvents;
+use moth_core::event_log::{EventKind, LogRecord};
+
+use crate::diff::{self, Granularity};
+use crate::Data;
+
+/// How often queued embeds are posted.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+/// Discord allows at most 10 embeds per message.
+const EMBEDS_PER_MESSAGE: usize = 10;
+/// Discord allows at most 6000 characters across the embeds of a message.
+const CHARACTERS_PER_MESSAGE: usize = 6000;
+/// How many audit log entries are searched for the actor of an event.
+const AUDIT_LOG_LIMIT: u8 = 25;
+
+/// Writes `record` to the event log and queues it for the guild's modlog channel.
+pub fn log_event(ctx: &serenity::Context, record: LogRecord) {
+    let data = ctx.data::<Data>();
+    data.event_log.emit(&record);
+
+    let Some(guild_id) = record.guild_id else {
+        return;
+    };
+
+    let Some(config) = data.database.get_modlog(guild_id) else {
+        return;
+    };
+
+    if !ModlogEvents::for_kind(record.kind).is_some_and(|e| config.events.contains(e)) {
+        return;
+    }
+
+    // Don't mirror what the bot did itself, or edits and deletions of its own messages.
+    // Channel, thread and role events are checked again once the flusher knows their actor.
+    let bot_id = ctx.cache.current_user().id;
+    let own_message = matches!(
+        record.kind,
+        EventKind::MessageEdit | EventKind::MessageDelete
+    ) && record.user_id == Some(bot_id);
+    let deleted_by_bot = record.kind == EventKind::MessageDelete
+        && record
+            .message_id
+            .is_some_and(|id| data.modlog_queue.is_own_deletion(id));
+    if own_message || deleted_by_bot || record.actor_id == Some(bot_id) {
+        return;
+    }
+
+    data.modlog_queue.push(config.channel_id, record);
+}
+
+/// Posts queued embeds every few seconds, grouping as many as fit into one message per channel.
+pub fn spawn_flusher(ctx: serenity::Context, data: Arc<Data>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let bot_id = ctx.cache.current_user().id;
+            let mut audit_logs = HashMap::new();
+
+            for (channel_id, mut records) in data.modlog_queue.drain() {
+                resolve_actors(&ctx, &mut audit_logs, &mut records).await;
+
+                let embeds = records
+                    .iter()
+                    .filter(|r| r.actor_id != Some(bot_id))
+                    .map(render)
+                    .collect::<Vec<_>>();
+
+                for chunk in chunk_embeds(embeds) {
+                    let count = chunk.len();
+                    let builder = serenity::CreateMessage::new().embeds(chunk);
+                    if let Err(e) = channel_id.send_message(&ctx.http, builder).await {
+                        println!(
+                            "Failed to post {count} embeds to modlog channel {channel_id}: {e}"
+                        );
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Groups embeds into messages, each within Discord's limits on embed count and characters.
+fn chunk_embeds<T>(embeds: Vec<(T, usize)>) -> Vec<Vec<T>> {
+    let mut chunks: Vec<Vec<T>> = Vec::new();
+    let mut length = 0;
+
+    for (embed, embed_length) in embeds {
+        match chunks.last_mut() {
+            Some(chunk)
+                if chunk.len() < EMBEDS_PER_MESSAGE
+                    && length + embed_length <= CHARACTERS_PER_MESSAGE =>
+            {
+                chunk.push(embed);
+                length += embed_length;
+            }
+            _ => {
+                chunks.push(vec![embed]);
+                length = embed_length;
+            }
+        }
+    }
+
+    chunks
+}
+
+/// The audit log action recording who caused `kind`, for kinds the gateway doesn't say.
+fn audit_action(kind: EventKind) -> Option<Action> {
+    Some(match kind {
+        EventKind::ChannelCreate => Action::Channel(ChannelAction::Create),
+        EventKind::ChannelUpdate => Action::Channel(ChannelAction::Update),
+        EventKind::ChannelDelete => Action::Channel(ChannelAction::Delete),
+        EventKind::ThreadCreate => Action::Thread(ThreadAction::Create),
+        EventKind::ThreadUpdate => Action::Thread(ThreadAction::Update),
+        EventKind::ThreadDelete => Action::Thread(ThreadAction::Delete),
+        EventKind::RoleCreate => Action::Role(RoleAction::Create),
+        EventKind::RoleUpdate => Action::Role(RoleAction::Update),
+        EventKind::RoleDelete => Action::Role(RoleAction::Delete),
+        _ => return None,
+    })
+}
+
+/// Fills in the actor of channel, thread and role records from the audit log.
+///
+/// `audit_logs` caches the entries per guild and kind, so a flush fetches each at most once.
+/// Records stay without an actor if the bot can't view the audit log.
+async fn resolve_actors(
+    ctx: &serenity::Context,
+    audit_logs: &mut HashMap<(GuildId, EventKind), Option<AuditLogs>>,
+    records: &mut [LogRecord],
+) {
+    for record in records.iter_mut().filter(|r| r.actor_id.is_none()) {
+        let (Some(guild_id), Some(action)) = (record.guild_id, audit_action(record.kind)) else {
+            continue;
+        };
+
+        let target = match record.kind {
+            EventKind::RoleCreate | EventKind::RoleUpdate | EventKind::RoleDelete => {
+                record.role_id.map(|r| r.get())
+            }
+            _ => record.channel_id.map(|c| c.get()),
+        };
+        let Some(target) = target else {
+            continue;
+        };
+
+        let logs = match audit_logs.entry((guild_id, record.kind)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let logs = guild_id
+                    .audit_logs(
+                        &ctx.http,
+                        Some(action),
+                        None,
+                        None,
+                        Some(nonmax::NonMaxU8::new(AUDIT_LOG_LIMIT).unwrap()),
+                    )
+                    .await
+                    .ok();
+                entry.insert(logs)
+            }
+        };
+
+        // entries are newest first, so this is the latest action on the target.
+        record.actor_id = logs.as_ref().and_then(|logs| {
+            logs.entries
+                .iter()
+                .find(|e| e.target_id.is_some_and(|t| t.get() == target))
+                .and_then(|e| e.user_id)
+        });
+    }
+}
+
+/// Renders `record` as an embed, along with how many characters Discord counts in it.
+fn render(record: &LogRecord) -> (CreateEmbed<'static>, usize) {
+    let (title, colour) = match record.kind {
+        EventKind::MessageEdit => ("Message edited", Colour::BLUE),
+        EventKind::MessageDelete => ("Message deleted", Colour::RED),
+        EventKind::ChannelCreate => ("Channel created", Colour::DARK_GREEN),
+        EventKind::ChannelUpdate => ("Channel updated", Colour::GOLD),
+        EventKind::ChannelDelete => ("Channel deleted", Colour::DARK_RED),
+        EventKind::ThreadCreate => ("Thread created", Colour::DARK_GREEN),
+        EventKind::ThreadUpdate => ("Thread updated", Colour::GOLD),
+        EventKind::ThreadDelete => ("Thread deleted", Colour::DARK_RED),
+        EventKind::RoleCreate => ("Role created", Colour::DARK_GREEN),
+        EventKind::RoleUpdate => ("Role updated", Colour::GOLD),
+        EventKind::RoleDelete => ("Role deleted", Colour::DARK_RED),
+        EventKind::MemberRoleUpdate => ("Member roles updated", Colour::PURPLE),
+        _ => ("Event", Colour::LIGHT_GREY),
+    };
+
+    let mut description = String::new();
+    if let Some(user_id) = record.user_id {
+        description.push_str(&format!("**User:** <@{user_id}>\n"));
+    }
+    if let Some(actor_id) = record.actor_id.filter(|a| Some(*a) != record.user_id) {
+        description.push_str(&format!("**By:** <@{actor_id}>\n"));
+    }
+    if let Some(channel_id) = record.channel_id {
+        description.push_str(&format!("**Channel:** <#{channel_id}>\n"));
+    }
+    if let Some(role_id) = record.role_id {
+        description.push_str(&format!("**Role:** <@&{role_id}>\n"));
+    }
+    if let (Some(guild_id), Some(channel_id), Some(message_id)) =
+        (record.guild_id, record.channel_id, record.message_id)
+    {
+        description.push_str(&format!(
+            "[Jump to message](https://discord.com/channels/{guild_id}/{channel_id}/{message_id})\n"
+        ));
+    }
+
+    let mut embed = CreateEmbed::new()
+        .title(title)
+        .colour(colour)
+        .timestamp(serenity::Timestamp::now());
+    let mut length = title.chars().count();
+
+    if !description.is_empty() {
+        length += description.chars().count();
+        embed = embed.description(description);
+    }
+
+    if record.kind == EventKind::MessageEdit {
+        if let (Some(old), Some(new)) = (record.before.as_deref(), record.after.as_deref()) {
+            if old != new {
+                let changes = diff::diff(old, new, Granularity::Word);
+                let value = diff::render_discord(&changes, 1024);
+                embed = field(embed, &mut length, "Diff", value);
+            }
+        }
+    } else {
+        embed = before_after_fields(embed, &mut length, record);
+    }
+
+    if !record.changes.is_empty() {
+        let changes = truncate(&record.changes.join("\n"), 1000);
+        embed = field(
+            embed,
+            &mut length,
+            "Changes",
+            format!("```\n{changes}\n```"),
+        );
+    }
+
+    if let Some(message_id) = record.message_id {
+        let footer = format!("Message ID: {message_id}");
+        length += footer.chars().count();
+        embed = embed.footer(CreateEmbedFooter::new(footer));
+    }
+
+    (embed, length)
+}
+
+/// Adds a field to `embed`, counting its characters towards `length`.
+fn field(
+    embed: CreateEmbed<'static>,
+    length: &mut usize,
+    name: &'static str,
+    value: String,
+) -> CreateEmbed<'static> {
+    *length += name.chars().count() + value.chars().count();
+    embed.field(name, value, false)
+}
+
+fn before_after_fields(
+    mut embed: CreateEmbed<'static>,
+    length: &mut usize,
+    record: &LogRecord,
+) -> CreateEmbed<'static> {
+    let (before, after) = match record.kind {
+        EventKind::MessageDelete => ("Content", "After"),
+        _ => ("Old name", "Name"),
+    };
+
+    // names are only interesting on updates when they changed, otherwise one is enough.
+    if let Some(value) = record.before.as_deref() {
+        if record.after.is_none() || record.before != record.after {
+            embed = field(embed, length, before, truncate(value, 1024));
+        }
+    }
+    if let Some(value) = record.after.as_deref() {
+        embed = field(embed, length, after, truncate(value, 1024));
+    }
+
+    embed
+}
+
+pub(crate) fn truncate(text: &str, max: usize) -> String {
+    if text.is_empty() {
+        return String::from("*empty*");
+    }
+
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    let mut out: String = text.chars().take(max - 1).collect();
+    out.push('…');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_by_count() {
+        let embeds = (0..25).map(|i| (i, 10)).collect();
+        let chunks = chunk_embeds(embeds);
+        assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), [10, 10, 5]);
+    }
+
+    #[test]
+    fn chunks_by_length() {
+        let embeds = vec![(0, 3000), (1, 2500), (2, 1000), (3, 6000), (4, 10)];
+        assert_eq!(
+            chunk_embeds(embeds),
+            [vec![0, 1], vec![2], vec![3], vec![4]]
+        );
+    }
+}
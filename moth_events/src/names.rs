@@ -0,0 +1,281 @@
+//! Runs the filter over member names and custom statuses in guilds that opted in, see
+//! `namefilter` in the moderation commands.
+
+use std::sync::Arc;
+
+use lumi::serenity_prelude::{
+    self as serenity, ActivityType, Colour, CreateEmbed, CreateEmbedFooter, CreateMessage,
+    EditMember, GuildId, Member, Presence, UserId,
+};
+use moth_ansi::{HI_BLACK, RED, RESET};
+use moth_core::data::names::{dehoist, NameFilterActions, MODERATED_NICK};
+
+use crate::helper::get_guild_name_override;
+use crate::modlog::truncate;
+use crate::{Data, Error};
+
+/// A name or status the filter flagged.
+struct Flagged {
+    field: &'static str,
+    value: String,
+    words: Vec<String>,
+}
+
+/// The words of `text` flagged by the global lists and the guild's own.
+fn flagged_words(data: &Arc<Data>, guild_id: GuildId, text: &str) -> Vec<String> {
+    let guild_filter = data.database.get_filter(guild_id);
+    let config = &data.config.read().events;
+
+    let verdict = moth_filter::check(
+        text,
+        moth_filter::Lists {
+            badlist: &config.badlist,
+            fixlist: &config.fixlist,
+            leet: &config.leet,
+            guild: guild_filter.as_deref(),
+        },
+    );
+
+    verdict
+        .words()
+        .into_iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn names(member: &Member) -> Vec<(&'static str, String)> {
+    let mut names = vec![("Username", member.user.name.to_string())];
+    if let Some(global_name) = &member.user.global_name {
+        names.push(("Display name", global_name.to_string()));
+    }
+    if let Some(nick) = &member.nick {
+        names.push(("Nickname", nick.to_string()));
+    }
+
+    names
+}
+
+/// Checks the names of `member` that changed since `old`, or since they were last checked
+/// without it.
+pub async fn check_member(
+    ctx: &serenity::Context,
+    data: &Arc<Data>,
+    old: Option<&Member>,
+    member: &Member,
+) -> Result<(), Error> {
+    let guild_id = member.guild_id;
+    let Some(actions) = data.database.get_name_filter(guild_id) else {
+        return Ok(());
+    };
+
+    if member.user.bot() {
+        return Ok(());
+    }
+
+    // without the old member every update looks like a change, so fall back to the names seen
+    // last.
+    let current = names(member);
+    let previous = data
+        .checked_names
+        .replace(guild_id, member.user.id, current.clone());
+    let old_names = old.map(names).or(previous);
+    if old_names.as_ref() == Some(&current) {
+        return Ok(());
+    }
+
+    let mut flagged = Vec::new();
+    for (field, value) in current {
+        if old_names
+            .as_ref()
+            .is_some_and(|old| old.iter().any(|(f, v)| *f == field && *v == value))
+        {
+            continue;
+        }
+
+        let words = flagged_words(data, guild_id, &value);
+        if !words.is_empty() {
+            flagged.push(Flagged {
+                field,
+                value,
+                words,
+            });
+        }
+    }
+
+    let mut new_nick = None;
+    if actions.contains(NameFilterActions::RESET_NICK) && !flagged.is_empty() {
+        // the name shown once there is no nickname.
+        let underlying = member
+            .user
+            .global_name
+            .as_deref()
+            .unwrap_or(&member.user.name);
+        let nick_flagged = flagged.iter().any(|f| f.field == "Nickname");
+
+        if nick_flagged && flagged_words(data, guild_id, underlying).is_empty() {
+            new_nick = Some(String::new());
+        } else if nick_flagged || member.nick.is_none() {
+            new_nick = Some(MODERATED_NICK.to_owned());
+        }
+    }
+
+    if new_nick.is_none() && actions.contains(NameFilterActions::DEHOIST) {
+        if let Some(stripped) = dehoist(member.display_name()) {
+            if stripped.is_empty() {
+                new_nick = Some(MODERATED_NICK.to_owned());
+            } else {
+                new_nick = Some(stripped.to_owned());
+            }
+        }
+    }
+
+    let mut taken = Vec::new();
+    if let Some(nick) = new_nick.filter(|_| can_edit(ctx, member)) {
+        let builder = EditMember::new()
+            .nickname(&nick)
+            .audit_log_reason("Name filter");
+        match guild_id
+            .edit_member(&ctx.http, member.user.id, builder)
+            .await
+        {
+            Ok(_) if nick.is_empty() => taken.push("Reset nickname".to_owned()),
+            Ok(_) => taken.push(format!("Set nickname to `{nick}`")),
+            Err(e) => println!(
+                "{HI_BLACK}Name filter failed to change the nickname of {}: {e}{RESET}",
+                member.user.id
+            ),
+        }
+    }
+
+    if !flagged.is_empty() {
+        alert(
+            ctx,
+            data,
+            guild_id,
+            member.user.id,
+            actions,
+            &flagged,
+            &taken,
+        )
+        .await;
+    }
+
+    Ok(())
+}
+
+/// If the bot can change the nickname of `member`, going by the cache.
+fn can_edit(ctx: &serenity::Context, member: &Member) -> bool {
+    let Some(guild) = ctx.cache.guild(member.guild_id) else {
+        return false;
+    };
+    if guild.owner_id == member.user.id {
+        return false;
+    }
+    let Some(bot) = guild.members.get(&ctx.cache.current_user().id) else {
+        return false;
+    };
+
+    guild.member_permissions(bot).manage_nicknames()
+        && guild.member_highest_role(bot) > guild.member_highest_role(member)
+}
+
+/// Checks the custom status of `new` if it changed since `old`.
+pub async fn check_presence(
+    ctx: &serenity::Context,
+    data: &Arc<Data>,
+    old: Option<&Presence>,
+    new: &Presence,
+) -> Result<(), Error> {
+    let Some(guild_id) = new.guild_id else {
+        return Ok(());
+    };
+    let Some(actions) = data.database.get_name_filter(guild_id) else {
+        return Ok(());
+    };
+
+    let custom_status = |presence: &Presence| {
+        presence
+            .activities
+            .iter()
+            .find(|a| a.kind == ActivityType::Custom)
+            .and_then(|a| a.state.as_ref())
+            .map(ToString::to_string)
+    };
+
+    let Some(status) = custom_status(new) else {
+        return Ok(());
+    };
+    if old.and_then(custom_status).as_ref() == Some(&status) {
+        return Ok(());
+    }
+
+    let words = flagged_words(data, guild_id, &status);
+    if !words.is_empty() {
+        let flagged = [Flagged {
+            field: "Custom status",
+            value: status,
+            words,
+        }];
+        alert(ctx, data, guild_id, new.user.id, actions, &flagged, &[]).await;
+    }
+
+    Ok(())
+}
+
+/// Prints what was flagged, and posts it to the modlog channel if the guild wants alerts.
+async fn alert(
+    ctx: &serenity::Context,
+    data: &Arc<Data>,
+    guild_id: GuildId,
+    user_id: UserId,
+    actions: NameFilterActions,
+    flagged: &[Flagged],
+    taken: &[String],
+) {
+    let guild_name = get_guild_name_override(ctx, data, Some(guild_id));
+    for f in flagged {
+        println!(
+            "{RED}[{guild_name}] Name filter: {} of {user_id}: {} ({}){RESET}",
+            f.field.to_lowercase(),
+            f.value,
+            f.words.join(", ")
+        );
+    }
+
+    if !actions.contains(NameFilterActions::ALERT) {
+        return;
+    }
+    let Some(modlog) = data.database.get_modlog(guild_id) else {
+        return;
+    };
+
+    let mut embed = CreateEmbed::new()
+        .title("Name filter")
+        .description(format!("**User:** <@{user_id}>"))
+        .colour(Colour::ORANGE)
+        .footer(CreateEmbedFooter::new(format!("User ID: {user_id}")))
+        .timestamp(serenity::Timestamp::now());
+
+    for f in flagged {
+        let words = f
+            .words
+            .iter()
+            .map(|w| format!("`{w}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let value = format!("{}\n**Flagged:** {words}", f.value);
+        embed = embed.field(f.field, truncate(&value, 1024), false);
+    }
+
+    if !taken.is_empty() {
+        embed = embed.field("Actions", taken.join("\n"), false);
+    }
+
+    let builder = CreateMessage::new().embed(embed);
+    if let Err(e) = modlog.channel_id.send_message(&ctx.http, builder).await {
+        println!(
+            "Failed to post to modlog channel {}: {e}",
+            modlog.channel_id
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Gathers the images of a message for OCR: attachments, stickers, embed images and the same
+//! inside forwarded messages, within the per-message budget of the OCR engine.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use lumi::serenity_prelude::{
+    self as serenity, Attachment, AttachmentId, Embed, StickerFormatType,
+};
+use moth_ansi::{RED, RESET};
+use moth_core::ocr::{
+    is_discord_cdn, is_gif, sample_gif_frames, OcrBudget, OcrPriority, CACHE_TTL, ENGINE_VERSION,
+};
+use tokio::sync::OnceCell;
+
+use crate::Data;
+
+/// Attachments larger than this are never downloaded, whatever is left of the budget.
+const MAX_ATTACHMENT_SIZE: u32 = 10_000_000;
+
+/// How often cached text older than [`CACHE_TTL`] is removed from the database.
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Where an image of a message came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageSource {
+    Attachment,
+    Sticker,
+    Embed,
+    Forwarded,
+    /// A link given directly, like to the `ocr` command.
+    Link,
+}
+
+impl std::fmt::Display for ImageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ImageSource::Attachment => "attachment",
+            ImageSource::Sticker => "sticker",
+            ImageSource::Embed => "embed",
+            ImageSource::Forwarded => "forwarded",
+            ImageSource::Link => "link",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An image to read, before it is downloaded.
+#[derive(Clone, Debug)]
+pub struct MessageImage {
+    pub url: String,
+    pub source: ImageSource,
+    /// The size reported by Discord, when known before downloading.
+    pub size: Option<u32>,
+}
+
+fn is_image_attachment(attachment: &Attachment) -> bool {
+    attachment.size < MAX_ATTACHMENT_SIZE
+        && attachment.content_type.as_deref().is_some_and(|c| {
+            matches!(
+                c,
+                "image/jpeg" | "image/jpg" | "image/png" | "image/webp" | "image/gif"
+            )
+        })
+}
+
+fn embed_images(embed: &Embed) -> impl Iterator<Item = String> + '_ {
+    // thumbnails are what link previews and gifv embeds show, where there is no full image.
+    // the proxied copies are served by discord, not whatever site the embed points to.
+    let image = embed
+        .image
+        .as_ref()
+        .and_then(|i| i.proxy_url.as_ref())
+        .map(ToString::to_string);
+    let thumbnail = embed
+        .thumbnail
+        .as_ref()
+        .and_then(|t| t.proxy_url.as_ref())
+        .map(ToString::to_string);
+
+    image.into_iter().chain(thumbnail)
+}
+
+/// Every image of `msg` worth reading, without duplicates, in the order they're shown.
+#[must_use]
+pub fn message_images(msg: &serenity::Message) -> Vec<MessageImage> {
+    let mut images = Vec::new();
+
+    for attachment in msg.attachments.iter().filter(|a| is_image_attachment(a)) {
+        images.push(MessageImage {
+            url: attachment.url.to_string(),
+            source: ImageSource::Attachment,
+            size: Some(attachment.size),
+        });
+    }
+
+    for sticker in &msg.sticker_items {
+        // lottie stickers are vector animations that can't be read as an image.
+        if matches!(sticker.format_type, StickerFormatType::Lottie) {
+            continue;
+        }
+
+        if let Some(url) = sticker.image_url() {
+            images.push(MessageImage {
+                url,
+                source: ImageSource::Sticker,
+                size: None,
+            });
+        }
+    }
+
+    for url in msg.embeds.iter().flat_map(embed_images) {
+        images.push(MessageImage {
+            url,
+            source: ImageSource::Embed,
+            size: None,
+        });
+    }
+
+    for snapshot in &msg.message_snapshots {
+        for attachment in snapshot
+            .attachments
+            .iter()
+            .filter(|a| is_image_attachment(a))
+        {
+            images.push(MessageImage {
+                url: attachment.url.to_string(),
+                source: ImageSource::Forwarded,
+                size: Some(attachment.size),
+            });
+        }
+
+        for url in snapshot.embeds.iter().flat_map(embed_images) {
+            images.push(MessageImage {
+                url,
+                source: ImageSource::Forwarded,
+                size: None,
+            });
+        }
+    }
+
+    let mut seen = HashSet::new();
+    images.retain(|image| seen.insert(image.url.clone()));
+
+    images
+}
+
+/// Downloads `images` and splits up animated GIFs, stopping once the budget runs out.
+///
+/// Every returned entry is a single frame ready for the OCR engine.
+pub async fn download_images(
+    data: &Arc<Data>,
+    images: &[MessageImage],
+    budget: OcrBudget,
+) -> Vec<(ImageSource, Vec<u8>)> {
+    let downloaded = download_all(data, images, budget.max_bytes).await;
+    split_frames(
+        downloaded
+            .into_iter()
+            .map(|(image, bytes)| (image.source, bytes)),
+        budget,
+    )
+    .await
+}
+
+/// Downloads `images` in order while `max_bytes` lasts, skipping any that fail.
+async fn download_all(
+    data: &Arc<Data>,
+    images: &[MessageImage],
+    max_bytes: usize,
+) -> Vec<(MessageImage, Vec<u8>)> {
+    let mut downloaded = Vec::new();
+    let mut bytes_left = max_bytes;
+
+    for image in images {
+        if image.size.is_some_and(|size| size as usize > bytes_left) {
+            continue;
+        }
+
+        match download(data, &image.url, bytes_left).await {
+            Ok(bytes) => {
+                bytes_left -= bytes.len();
+                downloaded.push((image.clone(), bytes));
+            }
+            Err(e) => println!("Failed to download image: {e}"),
+        }
+    }
+
+    downloaded
+}
+
+/// Splits up animated GIFs, stopping once the frame budget runs out.
+async fn split_frames(
+    images: impl Iterator<Item = (ImageSource, Vec<u8>)>,
+    budget: OcrBudget,
+) -> Vec<(ImageSource, Vec<u8>)> {
+    let mut frames = Vec::new();
+
+    for (source, bytes) in images {
+        let frames_left = budget.max_frames.saturating_sub(frames.len());
+        if frames_left == 0 {
+            break;
+        }
+
+        if !is_gif(&bytes) {
+            frames.push((source, bytes));
+            continue;
+        }
+
+        let count = budget.frames_per_gif.min(frames_left);
+        match tokio::task::spawn_blocking(move || sample_gif_frames(&bytes, count)).await {
+            Ok(Ok(sampled)) => frames.extend(sampled.into_iter().map(|f| (source, f))),
+            Ok(Err(e)) => println!("Failed to split GIF for OCR: {e}"),
+            Err(e) => println!("GIF sampling task failed: {e}"),
+        }
+    }
+
+    frames
+}
+
+/// Downloads `url`, refusing anything larger than `limit` bytes or not on Discord's CDN.
+pub async fn download(data: &Arc<Data>, url: &str, limit: usize) -> Result<Vec<u8>, crate::Error> {
+    if !is_discord_cdn(url) {
+        return Err(format!("{url} isn't on Discord's CDN").into());
+    }
+
+    let mut response = data.reqwest.get(url).send().await?.error_for_status()?;
+    // checked again in case it redirected elsewhere.
+    if !is_discord_cdn(response.url().as_str()) {
+        return Err(format!("{url} redirected away from Discord's CDN").into());
+    }
+
+    if response
+        .content_length()
+        .is_some_and(|length| length as usize > limit)
+    {
+        return Err(format!("{url} is larger than the remaining OCR budget").into());
+    }
+
+    // the length header can be missing or wrong, so stop reading once past the limit.
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if bytes.len() + chunk.len() > limit {
+            return Err(format!("{url} is larger than the remaining OCR budget").into());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+/// The images of a message, downloaded on first use and shared by everything reading or hashing
+/// them.
+pub struct MessageFrames<'a> {
+    data: &'a Arc<Data>,
+    msg: &'a serenity::Message,
+    images: OnceCell<Vec<(MessageImage, Vec<u8>)>>,
+    frames: OnceCell<Vec<(ImageSource, Vec<u8>)>>,
+}
+
+impl<'a> MessageFrames<'a> {
+    #[must_use]
+    pub fn new(data: &'a Arc<Data>, msg: &'a serenity::Message) -> Self {
+        Self {
+            data,
+            msg,
+            images: OnceCell::new(),
+            frames: OnceCell::new(),
+        }
+    }
+
+    async fn images(&self) -> &[(MessageImage, Vec<u8>)] {
+        self.images
+            .get_or_init(|| async {
+                let images = message_images(self.msg);
+                if images.is_empty() {
+                    return Vec::new();
+                }
+
+                download_all(self.data, &images, self.data.ocr_engine.budget.max_bytes).await
+            })
+            .await
+    }
+
+    async fn frames(&self) -> &[(ImageSource, Vec<u8>)] {
+        self.frames
+            .get_or_init(|| async {
+                let images = self.images().await;
+                let images = images
+                    .iter()
+                    .map(|(image, bytes)| (image.source, bytes.clone()));
+
+                split_frames(images, self.data.ocr_engine.budget).await
+            })
+            .await
+    }
+
+    /// The downloaded image attachments of the message, with their ids.
+    pub async fn attachments(&self) -> Vec<(AttachmentId, &[u8])> {
+        self.images()
+            .await
+            .iter()
+            .filter(|(image, _)| image.source == ImageSource::Attachment)
+            .filter_map(|(image, bytes)| {
+                let attachment = self.msg.attachments.iter().find(|a| a.url == image.url)?;
+                Some((attachment.id, bytes.as_slice()))
+            })
+            .collect()
+    }
+
+    /// The image attachments of the message left out of the downloads by the OCR budget.
+    pub async fn skipped_attachments(&self) -> Vec<&'a Attachment> {
+        let images = self.images().await;
+        self.msg
+            .attachments
+            .iter()
+            .filter(|a| is_image_attachment(a))
+            .filter(|a| !images.iter().any(|(image, _)| a.url == image.url))
+            .collect()
+    }
+
+    /// Reads the images once per language, returning the text along with the language it was
+    /// read in.
+    pub async fn read(
+        &self,
+        languages: &HashSet<String>,
+        priority: OcrPriority,
+    ) -> Vec<(String, String)> {
+        if languages.is_empty() {
+            return Vec::new();
+        }
+
+        let engine = &self.data.ocr_engine;
+        let mut texts = Vec::new();
+        for (_, frame) in self.frames().await {
+            for language in languages {
+                match engine
+                    .process_cached(&self.data.database, frame.clone(), language, priority)
+                    .await
+                {
+                    Ok(text) => texts.push((language.clone(), text)),
+                    Err(e) => println!("Failed to process image with OCR engine: {e}"),
+                }
+            }
+        }
+
+        texts
+    }
+}
+
+/// The text in the images of `msg` for the content filter, empty unless the guild enabled it.
+pub async fn filter_texts(
+    data: &Arc<Data>,
+    msg: &serenity::Message,
+    frames: &MessageFrames<'_>,
+) -> Vec<String> {
+    let Some(guild_id) = msg.guild_id else {
+        return Vec::new();
+    };
+
+    if msg.author.bot() || !data.database.get_ocr_filter(guild_id) {
+        return Vec::new();
+    }
+
+    let languages = HashSet::from([data.database.get_ocr_language(guild_id)]);
+    frames
+        .read(&languages, OcrPriority::Normal)
+        .await
+        .into_iter()
+        .map(|(_, text)| text)
+        .collect()
+}
+
+/// Removes expired text from the OCR cache every [`PRUNE_INTERVAL`].
+pub fn spawn_cache_pruner(data: Arc<Data>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let before = chrono::Utc::now().timestamp() - CACHE_TTL.as_secs() as i64;
+            if let Err(e) = data.database.prune_cached_ocr(before, ENGINE_VERSION).await {
+                println!("{RED}Failed to prune the OCR cache: {e}{RESET}");
+            }
+        }
+    });
+}
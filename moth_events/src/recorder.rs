@@ -0,0 +1,477 @@
+//! Records gateway events to a JSON Lines file so they can be replayed offline.
+//!
+//! Set `MOTH_RECORD_EVENTS` to a file path to enable recording, each line is a
+//! [`RecordedEvent`]. Events are written on a background thread and flushed every second.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use chrono::Utc;
+use lumi::serenity_prelude::{
+    AuditLogEntry, ChannelId, FullEvent, GenericChannelId, Guild, GuildChannel, GuildId,
+    GuildMemberUpdateEvent, GuildThread, Interaction, Member, Message, MessageId,
+    MessageUpdateEvent, PartialGuildThread, Presence, Reaction, Role, RoleId, UnavailableGuild,
+    User, VoiceState,
+};
+use moth_core::event_log::{BackgroundWriter, RecordSink};
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Unix timestamp in milliseconds of when the event was received.
+    pub timestamp: i64,
+    pub event: ReplayEvent,
+}
+
+/// The subset of [`FullEvent`] that can be replayed, every event the handler acts on.
+///
+/// `ready` and `guild_members_chunk` are left out, replaying `ready` would start the background
+/// tasks again and chunks are only logged. Events that aren't handled here are still recorded
+/// by name so the timeline stays intact.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayEvent {
+    Message {
+        new_message: Message,
+    },
+    MessageUpdate {
+        old_if_available: Option<Message>,
+        event: MessageUpdateEvent,
+    },
+    MessageDelete {
+        channel_id: GenericChannelId,
+        deleted_message_id: MessageId,
+        guild_id: Option<GuildId>,
+    },
+    ReactionAdd {
+        add_reaction: Reaction,
+    },
+    ReactionRemove {
+        removed_reaction: Reaction,
+    },
+    GuildMemberAddition {
+        new_member: Member,
+    },
+    GuildMemberRemoval {
+        guild_id: GuildId,
+        user: User,
+        member_data_if_available: Option<Member>,
+    },
+    InteractionCreate {
+        interaction: Interaction,
+    },
+    GuildCreate {
+        guild: Guild,
+        is_new: Option<bool>,
+    },
+    /// The cached guild isn't kept.
+    GuildDelete {
+        incomplete: UnavailableGuild,
+    },
+    GuildAuditLogEntryCreate {
+        entry: AuditLogEntry,
+        guild_id: GuildId,
+    },
+    GuildRoleCreate {
+        new: Role,
+    },
+    GuildRoleDelete {
+        guild_id: GuildId,
+        removed_role_id: RoleId,
+        removed_role_data_if_available: Option<Role>,
+    },
+    GuildRoleUpdate {
+        old_data_if_available: Option<Role>,
+        new: Role,
+    },
+    GuildMemberUpdate {
+        old_if_available: Option<Member>,
+        new: Option<Member>,
+        event: GuildMemberUpdateEvent,
+    },
+    ChannelCreate {
+        channel: GuildChannel,
+    },
+    /// The cached messages of the channel aren't kept.
+    ChannelDelete {
+        channel: GuildChannel,
+    },
+    ChannelUpdate {
+        old: Option<GuildChannel>,
+        new: GuildChannel,
+    },
+    ThreadCreate {
+        thread: GuildThread,
+        newly_created: Option<bool>,
+    },
+    ThreadDelete {
+        thread: PartialGuildThread,
+        full_thread_data: Option<GuildThread>,
+    },
+    ThreadUpdate {
+        old: Option<GuildThread>,
+        new: GuildThread,
+    },
+    VoiceChannelStatusUpdate {
+        old: Option<String>,
+        status: Option<String>,
+        id: ChannelId,
+        guild_id: GuildId,
+    },
+    VoiceStateUpdate {
+        old: Option<VoiceState>,
+        new: VoiceState,
+    },
+    PresenceUpdate {
+        old_data: Option<Presence>,
+        new_data: Presence,
+    },
+    /// An event that isn't replayable, only the name is kept.
+    Other {
+        name: String,
+    },
+}
+
+impl ReplayEvent {
+    #[must_use]
+    pub fn from_full(event: &FullEvent) -> Self {
+        match event {
+            FullEvent::Message { new_message, .. } => ReplayEvent::Message {
+                new_message: new_message.clone(),
+            },
+            FullEvent::MessageUpdate {
+                old_if_available,
+                event,
+                ..
+            } => ReplayEvent::MessageUpdate {
+                old_if_available: old_if_available.clone(),
+                event: event.clone(),
+            },
+            FullEvent::MessageDelete {
+                channel_id,
+                deleted_message_id,
+                guild_id,
+                ..
+            } => ReplayEvent::MessageDelete {
+                channel_id: *channel_id,
+                deleted_message_id: *deleted_message_id,
+                guild_id: *guild_id,
+            },
+            FullEvent::ReactionAdd { add_reaction, .. } => ReplayEvent::ReactionAdd {
+                add_reaction: add_reaction.clone(),
+            },
+            FullEvent::ReactionRemove {
+                removed_reaction, ..
+            } => ReplayEvent::ReactionRemove {
+                removed_reaction: removed_reaction.clone(),
+            },
+            FullEvent::GuildMemberAddition { new_member, .. } => ReplayEvent::GuildMemberAddition {
+                new_member: new_member.clone(),
+            },
+            FullEvent::GuildMemberRemoval {
+                guild_id,
+                user,
+                member_data_if_available,
+                ..
+            } => ReplayEvent::GuildMemberRemoval {
+                guild_id: *guild_id,
+                user: user.clone(),
+                member_data_if_available: member_data_if_available.clone(),
+            },
+            FullEvent::InteractionCreate { interaction, .. } => ReplayEvent::InteractionCreate {
+                interaction: interaction.clone(),
+            },
+            FullEvent::GuildCreate { guild, is_new, .. } => ReplayEvent::GuildCreate {
+                guild: guild.clone(),
+                is_new: *is_new,
+            },
+            FullEvent::GuildDelete { incomplete, .. } => ReplayEvent::GuildDelete {
+                incomplete: incomplete.clone(),
+            },
+            FullEvent::GuildAuditLogEntryCreate {
+                entry, guild_id, ..
+            } => ReplayEvent::GuildAuditLogEntryCreate {
+                entry: entry.clone(),
+                guild_id: *guild_id,
+            },
+            FullEvent::GuildRoleCreate { new, .. } => {
+                ReplayEvent::GuildRoleCreate { new: new.clone() }
+            }
+            FullEvent::GuildRoleDelete {
+                guild_id,
+                removed_role_id,
+                removed_role_data_if_available,
+                ..
+            } => ReplayEvent::GuildRoleDelete {
+                guild_id: *guild_id,
+                removed_role_id: *removed_role_id,
+                removed_role_data_if_available: removed_role_data_if_available.clone(),
+            },
+            FullEvent::GuildRoleUpdate {
+                old_data_if_available,
+                new,
+                ..
+            } => ReplayEvent::GuildRoleUpdate {
+                old_data_if_available: old_data_if_available.clone(),
+                new: new.clone(),
+            },
+            FullEvent::GuildMemberUpdate {
+                old_if_available,
+                new,
+                event,
+                ..
+            } => ReplayEvent::GuildMemberUpdate {
+                old_if_available: old_if_available.clone(),
+                new: new.clone(),
+                event: event.clone(),
+            },
+            FullEvent::ChannelCreate { channel, .. } => ReplayEvent::ChannelCreate {
+                channel: channel.clone(),
+            },
+            FullEvent::ChannelDelete { channel, .. } => ReplayEvent::ChannelDelete {
+                channel: channel.clone(),
+            },
+            FullEvent::ChannelUpdate { old, new, .. } => ReplayEvent::ChannelUpdate {
+                old: old.clone(),
+                new: new.clone(),
+            },
+            FullEvent::ThreadCreate {
+                thread,
+                newly_created,
+                ..
+            } => ReplayEvent::ThreadCreate {
+                thread: thread.clone(),
+                newly_created: *newly_created,
+            },
+            FullEvent::ThreadDelete {
+                thread,
+                full_thread_data,
+                ..
+            } => ReplayEvent::ThreadDelete {
+                thread: thread.clone(),
+                full_thread_data: full_thread_data.clone(),
+            },
+            FullEvent::ThreadUpdate { old, new, .. } => ReplayEvent::ThreadUpdate {
+                old: old.clone(),
+                new: new.clone(),
+            },
+            FullEvent::VoiceChannelStatusUpdate {
+                old,
+                status,
+                id,
+                guild_id,
+                ..
+            } => ReplayEvent::VoiceChannelStatusUpdate {
+                old: old.clone(),
+                status: status.clone(),
+                id: *id,
+                guild_id: *guild_id,
+            },
+            FullEvent::VoiceStateUpdate { old, new, .. } => ReplayEvent::VoiceStateUpdate {
+                old: old.clone(),
+                new: new.clone(),
+            },
+            FullEvent::PresenceUpdate {
+                old_data, new_data, ..
+            } => ReplayEvent::PresenceUpdate {
+                old_data: old_data.clone(),
+                new_data: new_data.clone(),
+            },
+            other => ReplayEvent::Other {
+                name: other.snake_case_name().to_string(),
+            },
+        }
+    }
+
+    /// Converts back into a [`FullEvent`], returns `None` for [`ReplayEvent::Other`].
+    #[must_use]
+    pub fn into_full(self) -> Option<FullEvent> {
+        let event = match self {
+            ReplayEvent::Message { new_message } => FullEvent::Message { new_message },
+            ReplayEvent::MessageUpdate {
+                old_if_available,
+                event,
+            } => FullEvent::MessageUpdate {
+                old_if_available,
+                event,
+            },
+            ReplayEvent::MessageDelete {
+                channel_id,
+                deleted_message_id,
+                guild_id,
+            } => FullEvent::MessageDelete {
+                channel_id,
+                deleted_message_id,
+                guild_id,
+            },
+            ReplayEvent::ReactionAdd { add_reaction } => FullEvent::ReactionAdd { add_reaction },
+            ReplayEvent::ReactionRemove { removed_reaction } => {
+                FullEvent::ReactionRemove { removed_reaction }
+            }
+            ReplayEvent::GuildMemberAddition { new_member } => {
+                FullEvent::GuildMemberAddition { new_member }
+            }
+            ReplayEvent::GuildMemberRemoval {
+                guild_id,
+                user,
+                member_data_if_available,
+            } => FullEvent::GuildMemberRemoval {
+                guild_id,
+                user,
+                member_data_if_available,
+            },
+            ReplayEvent::InteractionCreate { interaction } => {
+                FullEvent::InteractionCreate { interaction }
+            }
+            ReplayEvent::GuildCreate { guild, is_new } => FullEvent::GuildCreate { guild, is_new },
+            ReplayEvent::GuildDelete { incomplete } => FullEvent::GuildDelete {
+                incomplete,
+                full: None,
+            },
+            ReplayEvent::GuildAuditLogEntryCreate { entry, guild_id } => {
+                FullEvent::GuildAuditLogEntryCreate { entry, guild_id }
+            }
+            ReplayEvent::GuildRoleCreate { new } => FullEvent::GuildRoleCreate { new },
+            ReplayEvent::GuildRoleDelete {
+                guild_id,
+                removed_role_id,
+                removed_role_data_if_available,
+            } => FullEvent::GuildRoleDelete {
+                guild_id,
+                removed_role_id,
+                removed_role_data_if_available,
+            },
+            ReplayEvent::GuildRoleUpdate {
+                old_data_if_available,
+                new,
+            } => FullEvent::GuildRoleUpdate {
+                old_data_if_available,
+                new,
+            },
+            ReplayEvent::GuildMemberUpdate {
+                old_if_available,
+                new,
+                event,
+            } => FullEvent::GuildMemberUpdate {
+                old_if_available,
+                new,
+                event,
+            },
+            ReplayEvent::ChannelCreate { channel } => FullEvent::ChannelCreate { channel },
+            ReplayEvent::ChannelDelete { channel } => FullEvent::ChannelDelete {
+                channel,
+                messages: None,
+            },
+            ReplayEvent::ChannelUpdate { old, new } => FullEvent::ChannelUpdate { old, new },
+            ReplayEvent::ThreadCreate {
+                thread,
+                newly_created,
+            } => FullEvent::ThreadCreate {
+                thread,
+                newly_created,
+            },
+            ReplayEvent::ThreadDelete {
+                thread,
+                full_thread_data,
+            } => FullEvent::ThreadDelete {
+                thread,
+                full_thread_data,
+            },
+            ReplayEvent::ThreadUpdate { old, new } => FullEvent::ThreadUpdate { old, new },
+            ReplayEvent::VoiceChannelStatusUpdate {
+                old,
+                status,
+                id,
+                guild_id,
+            } => FullEvent::VoiceChannelStatusUpdate {
+                old,
+                status,
+                id,
+                guild_id,
+            },
+            ReplayEvent::VoiceStateUpdate { old, new } => FullEvent::VoiceStateUpdate { old, new },
+            ReplayEvent::PresenceUpdate { old_data, new_data } => {
+                FullEvent::PresenceUpdate { old_data, new_data }
+            }
+            ReplayEvent::Other { .. } => return None,
+        };
+
+        Some(event)
+    }
+}
+
+struct RecordingFile(BufWriter<File>);
+
+impl RecordSink<RecordedEvent> for RecordingFile {
+    fn write(&mut self, record: &RecordedEvent) -> Result<(), Error> {
+        serde_json::to_writer(&mut self.0, record)?;
+        self.0.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.0.flush()?;
+        Ok(())
+    }
+}
+
+pub struct EventRecorder {
+    writer: BackgroundWriter<RecordedEvent>,
+}
+
+impl EventRecorder {
+    /// Opens `path` for appending.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            writer: BackgroundWriter::spawn("event-recorder", RecordingFile(BufWriter::new(file)))?,
+        })
+    }
+
+    /// Creates a recorder if `MOTH_RECORD_EVENTS` is set.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("MOTH_RECORD_EVENTS").ok()?;
+
+        match Self::new(&path) {
+            Ok(recorder) => {
+                println!("Recording gateway events to {path}");
+                Some(recorder)
+            }
+            Err(e) => {
+                eprintln!("Failed to open {path} for recording: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn record(&self, event: &FullEvent) {
+        self.writer.send(RecordedEvent {
+            timestamp: Utc::now().timestamp_millis(),
+            event: ReplayEvent::from_full(event),
+        });
+    }
+}
+
+/// Reads a file written by [`EventRecorder`], skipping blank lines.
+pub fn read_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedEvent>, Error> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut events = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid event on line {}: {e}", index + 1))?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
@@ -7,6 +7,13 @@ use rustrict::{Censor, Trie, Type};
 
 use moth_ansi::{BOLD, RED, RESET};
 
+mod lists;
+mod normalize;
+mod verdict;
+pub use lists::{GuildFilter, TrieOverride, WordList};
+pub use normalize::{LeetMap, Normalized, normalize, normalize_entry};
+pub use verdict::{Source, Span, Tier, Verdict};
+
 pub static WHITESPACE: std::sync::LazyLock<regex::Regex> =
     std::sync::LazyLock::new(|| regex::Regex::new(r"(\s*)(\S+)").unwrap());
 
@@ -27,35 +34,22 @@ fn get_trie() -> &'static Trie {
     static TRIE: OnceLock<Trie> = OnceLock::new();
     TRIE.get_or_init(|| {
         let mut trie = Trie::default();
-        // patch fix
-        trie.set("fcing", Type::SAFE);
-        trie.set("pp", Type::SAFE);
-        trie.set("ppcat", Type::SAFE);
+        for (word, kind) in lists::DEFAULT_OVERRIDES {
+            trie.set(word, kind.as_type());
+        }
 
         trie
     })
 }
 
-/// A function that cleans stuff up that rustrict isn't good with.
+/// A function that cleans stuff up that rustrict isn't good with, using the default leet map.
 pub fn preprocess(content: &str) -> Cow<'_, str> {
-    let processed = EMOJI_REGEX.replace_all(content, |caps: &regex::Captures| {
-        let matched = caps.get(2).unwrap();
-        let start = matched.start();
-        let end = matched.end();
-        &content[start..end]
-    });
-
-    // mentions falsely trigger for spam/evasivze.
-    let processed = MENTIONS.replace_all(&processed, |_: &regex::Captures| "");
-    // remove a reasonable set of numbers, because for whatever reason its inappropriate.
-    let processed = NUMBERS.replace_all(&processed, |_: &regex::Captures| "");
-    // links can falsely trigger stuff, so they are ommitted.
-    let processed = LINKS.replace_all(&processed, |_: &regex::Captures| "");
-
-    if processed == content {
+    let normalized = normalize(content, &LeetMap::default());
+
+    if normalized.text == content {
         Cow::Borrowed(content)
     } else {
-        Cow::Owned(processed.into())
+        Cow::Owned(normalized.text)
     }
 }
 
@@ -69,54 +63,147 @@ pub fn analyze(content: &str) -> Type {
     censor.analyze()
 }
 
-pub fn filter_content<'a>(
-    content: &'a str,
-    badlist: &HashSet<String>,
-    fixlist: &HashSet<String>,
-) -> Cow<'a, str> {
-    let mut changed_words = Vec::new();
-    let processed = preprocess(content);
-    let mut censor = Censor::from_str(&processed);
-    let censor = censor
-        .with_trie(get_trie())
-        .with_censor_threshold(get_threshold());
+/// Everything [`check`] matches against.
+#[derive(Clone, Copy)]
+pub struct Lists<'a> {
+    /// Entries already passed through [`normalize_entry`] with `leet`.
+    pub badlist: &'a HashSet<String>,
+    /// Entries already passed through [`normalize_entry`] with `leet`.
+    pub fixlist: &'a HashSet<String>,
+    pub leet: &'a LeetMap,
+    /// The guild's own entries, checked on top of the global lists.
+    pub guild: Option<&'a GuildFilter>,
+}
+
+/// Flags words from the lists and rustrict, both matching against the normalised text.
+pub fn check<'a>(content: &'a str, lists: Lists<'_>) -> Verdict<'a> {
+    let normalized = normalize(content, lists.leet);
+    let trie = lists
+        .guild
+        .and_then(GuildFilter::trie)
+        .unwrap_or_else(get_trie);
 
-    content.split_whitespace().for_each(|word| {
-        let is_blacklisted = badlist.iter().any(|badword| word.contains(badword))
-            && !fixlist.iter().any(|fixword| word.contains(fixword));
-        if is_blacklisted {
-            changed_words.push(word);
+    // the first entry of the global, then the guild's list that `word` contains.
+    let find_entry = |word: &str, list: WordList| {
+        let global = match list {
+            WordList::Bad => lists.badlist,
+            WordList::Fix => lists.fixlist,
+        };
+        let find =
+            |list: &HashSet<String>| list.iter().find(|w| word.contains(w.as_str())).cloned();
+
+        match find(global) {
+            Some(entry) => Some((entry, false)),
+            None => lists
+                .guild
+                .and_then(|g| find(g.matched(list)))
+                .map(|entry| (entry, true)),
         }
-    });
+    };
+
+    let mut spans = Vec::new();
+    for (range, word) in normalized.words() {
+        let Some((entry, guild)) = find_entry(word, WordList::Bad) else {
+            continue;
+        };
+        // one normalised word can come from several original ones, like when split by zero
+        // width spaces that are now gone.
+        let Some(range) = normalized.origin_range(content, range) else {
+            continue;
+        };
 
-    if censor.analyze() != Type::NONE {
-        censor.reset(processed.chars());
+        spans.push(Span {
+            range,
+            source: Source::Badlist { entry, guild },
+            severity: Some(Tier::Moderate),
+            fixlist: find_entry(word, WordList::Fix).map(|(entry, _)| entry),
+        });
+    }
+
+    let mut censor = Censor::from_str(&normalized.text);
+    let censor = censor
+        .with_trie(trie)
+        .with_censor_threshold(get_threshold());
+
+    let kind = censor.analyze();
+    if kind != Type::NONE {
+        censor.reset(normalized.text.chars());
         let censored = censor.censor();
 
-        let mut orig_iter = content.split_whitespace();
-        let mut mapped_iter = processed.split_whitespace();
-        let mut censored_iter = censored.split_whitespace();
-
-        loop {
-            match (orig_iter.next(), mapped_iter.next(), censored_iter.next()) {
-                (Some(w1), Some(mapped), Some(w2)) if mapped != w2 => {
-                    changed_words.push(w1);
-                }
-                (Some(w1), None, _) => changed_words.push(w1),
-                (Some(_), Some(_), None) => continue,
-                (None, None, None) => break,
-                _ => continue,
+        // censoring replaces characters one for one, so runs of differing ones were censored.
+        let mut runs: Vec<std::ops::Range<usize>> = Vec::new();
+        for ((offset, original), censored) in normalized.text.char_indices().zip(censored.chars()) {
+            if original == censored {
+                continue;
+            }
+
+            let end = offset + original.len_utf8();
+            match runs.last_mut() {
+                Some(run) if run.end == offset => run.end = end,
+                _ => runs.push(offset..end),
             }
         }
+
+        for run in runs {
+            let word = normalized
+                .words()
+                .find(|(range, _)| range.start <= run.start && run.start < range.end)
+                .map_or(&normalized.text[run.clone()], |(_, word)| word);
+            let Some(range) = normalized.origin_range(content, run) else {
+                continue;
+            };
+
+            let word_kind = Censor::from_str(word).with_trie(trie).analyze();
+            let source = match find_override(word, lists.guild) {
+                Some((word, kind)) => Source::TrieOverride { word, kind },
+                None => Source::Rustrict(word_kind),
+            };
+
+            spans.push(Span {
+                range,
+                source,
+                severity: Tier::severity(word_kind),
+                fixlist: find_entry(word, WordList::Fix).map(|(entry, _)| entry),
+            });
+        }
     }
 
-    if changed_words.is_empty() {
+    spans.sort_by_key(|s| (s.range.start, s.range.end));
+    Verdict {
+        content,
+        kind,
+        spans,
+    }
+}
+
+/// The override that made rustrict flag `word`, preferring the guild's own.
+fn find_override(word: &str, guild: Option<&GuildFilter>) -> Option<(String, TrieOverride)> {
+    let guild = guild
+        .into_iter()
+        .flat_map(|g| g.matched_overrides().iter().map(|(w, k)| (w.as_str(), *k)));
+    let default = lists::DEFAULT_OVERRIDES.iter().map(|(w, k)| (*w, *k));
+
+    guild
+        .chain(default)
+        .find(|(w, kind)| *kind != TrieOverride::Safe && word.contains(w))
+        .map(|(w, kind)| (w.to_owned(), kind))
+}
+
+/// `content` with the words flagged in `verdict` in bold red.
+#[must_use]
+pub fn highlight<'a>(content: &'a str, verdict: &Verdict<'_>) -> Cow<'a, str> {
+    let words = verdict.words();
+    if words.is_empty() {
         Cow::Borrowed(content)
     } else {
-        Cow::Owned(colour_string(content, &changed_words))
+        Cow::Owned(colour_string(content, &words))
     }
 }
 
+pub fn filter_content<'a>(content: &'a str, lists: Lists<'_>) -> Cow<'a, str> {
+    highlight(content, &check(content, lists))
+}
+
 fn colour_string(content: &str, changed_words: &[&str]) -> String {
     let mut result = String::new();
     for cap in WHITESPACE.captures_iter(content) {
@@ -136,3 +223,88 @@ fn colour_string(content: &str, changed_words: &[&str]) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn entries(words: &[&str], leet: &LeetMap) -> HashSet<String> {
+        words
+            .iter()
+            .filter_map(|w| normalize_entry(w, leet))
+            .collect()
+    }
+
+    fn badlisted<'a>(verdict: &Verdict<'a>) -> Vec<&'a str> {
+        let content = verdict.content;
+        verdict
+            .flagged()
+            .filter(|s| matches!(s.source, Source::Badlist { .. }))
+            .map(|s| &content[s.range.clone()])
+            .collect()
+    }
+
+    #[test]
+    fn entries_are_normalised_like_messages() {
+        let leet = LeetMap::default();
+
+        assert_eq!(
+            normalize_entry("@everyone", &leet).as_deref(),
+            Some("aeveryone")
+        );
+        assert_eq!(normalize_entry(" Ｂａｄ ", &leet).as_deref(), Some("bad"));
+        assert_eq!(normalize_entry("\u{200B}", &leet), None);
+        assert_eq!(normalize_entry("", &leet), None);
+    }
+
+    // `@` is leet for `a`, so the raw entries from badwords.txt never matched.
+    #[test]
+    fn everyone_ping_is_badlisted() {
+        let leet = LeetMap::default();
+        let badlist = entries(&["@everyone", "@here"], &leet);
+        let fixlist = HashSet::new();
+        let lists = Lists {
+            badlist: &badlist,
+            fixlist: &fixlist,
+            leet: &leet,
+            guild: None,
+        };
+
+        let verdict = check("free nitro @everyone", lists);
+        assert_eq!(badlisted(&verdict), ["@everyone"]);
+
+        let verdict = check("@here", lists);
+        assert_eq!(badlisted(&verdict), ["@here"]);
+    }
+
+    #[test]
+    fn guild_entries_are_normalised() {
+        let leet = LeetMap::default();
+        let empty = HashSet::new();
+        let guild = GuildFilter::new(
+            HashSet::from(["@everyone".to_owned()]),
+            HashSet::new(),
+            BTreeMap::new(),
+            &leet,
+        );
+        let lists = Lists {
+            badlist: &empty,
+            fixlist: &empty,
+            leet: &leet,
+            guild: Some(&guild),
+        };
+
+        let verdict = check("hey @everyone", lists);
+        assert_eq!(badlisted(&verdict), ["@everyone"]);
+        assert!(verdict.spans.iter().any(|s| s.source
+            == Source::Badlist {
+                entry: "aeveryone".to_owned(),
+                guild: true,
+            }));
+
+        // listed as it was added.
+        assert!(guild.list(WordList::Bad).contains("@everyone"));
+    }
+}
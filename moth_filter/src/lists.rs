@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashSet};
+
+use rustrict::{Trie, Type};
+
+use crate::{LeetMap, normalize_entry};
+
+/// Words rustrict gets wrong everywhere, applied before any guild's own overrides.
+pub(crate) const DEFAULT_OVERRIDES: [(&str, TrieOverride); 3] = [
+    ("fcing", TrieOverride::Safe),
+    ("pp", TrieOverride::Safe),
+    ("ppcat", TrieOverride::Safe),
+];
+
+/// Which of the substring lists a word belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WordList {
+    /// Flagged when a word contains it.
+    Bad,
+    /// Unflags words flagged by the badlist that contain it.
+    Fix,
+}
+
+impl WordList {
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            WordList::Bad => "bad",
+            WordList::Fix => "fix",
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "bad" | "badlist" | "badwords" => Some(WordList::Bad),
+            "fix" | "fixlist" | "fixwords" => Some(WordList::Fix),
+            _ => None,
+        }
+    }
+}
+
+/// How rustrict should treat a word, overriding its own dictionary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TrieOverride {
+    Safe,
+    Mild,
+    Moderate,
+    Severe,
+}
+
+impl TrieOverride {
+    pub const ALL: [TrieOverride; 4] = [
+        TrieOverride::Safe,
+        TrieOverride::Mild,
+        TrieOverride::Moderate,
+        TrieOverride::Severe,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            TrieOverride::Safe => "safe",
+            TrieOverride::Mild => "mild",
+            TrieOverride::Moderate => "moderate",
+            TrieOverride::Severe => "severe",
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|o| o.name().eq_ignore_ascii_case(name))
+    }
+
+    #[must_use]
+    pub fn as_type(self) -> Type {
+        match self {
+            TrieOverride::Safe => Type::SAFE,
+            TrieOverride::Mild => Type::PROFANE & Type::MILD,
+            TrieOverride::Moderate => Type::PROFANE & Type::MODERATE,
+            TrieOverride::Severe => Type::PROFANE & Type::SEVERE,
+        }
+    }
+}
+
+/// A guild's own entries, checked alongside the global lists from `config/lists`.
+///
+/// Entries are kept as they were added for listing and removing them, and matched in their
+/// [`normalize_entry`] form.
+#[derive(Default)]
+pub struct GuildFilter {
+    pub badlist: HashSet<String>,
+    pub fixlist: HashSet<String>,
+    overrides: BTreeMap<String, TrieOverride>,
+    matched_badlist: HashSet<String>,
+    matched_fixlist: HashSet<String>,
+    matched_overrides: Vec<(String, TrieOverride)>,
+    /// Only built when the guild has overrides, as every trie is a full copy of rustrict's.
+    trie: Option<Trie>,
+}
+
+impl GuildFilter {
+    /// Normalises the entries with `leet`, which has to be the map messages are checked with.
+    #[must_use]
+    pub fn new(
+        badlist: HashSet<String>,
+        fixlist: HashSet<String>,
+        overrides: BTreeMap<String, TrieOverride>,
+        leet: &LeetMap,
+    ) -> Self {
+        let normalize_all = |list: &HashSet<String>| {
+            list.iter()
+                .filter_map(|entry| normalize_entry(entry, leet))
+                .collect::<HashSet<_>>()
+        };
+        let matched_overrides = overrides
+            .iter()
+            .filter_map(|(word, kind)| Some((normalize_entry(word, leet)?, *kind)))
+            .collect::<Vec<_>>();
+
+        let trie = (!matched_overrides.is_empty()).then(|| {
+            let mut trie = Trie::default();
+            for (word, kind) in DEFAULT_OVERRIDES {
+                trie.set(word, kind.as_type());
+            }
+            for (word, kind) in &matched_overrides {
+                trie.set(word, kind.as_type());
+            }
+            trie
+        });
+
+        Self {
+            matched_badlist: normalize_all(&badlist),
+            matched_fixlist: normalize_all(&fixlist),
+            matched_overrides,
+            badlist,
+            fixlist,
+            overrides,
+            trie,
+        }
+    }
+
+    #[must_use]
+    pub fn list(&self, list: WordList) -> &HashSet<String> {
+        match list {
+            WordList::Bad => &self.badlist,
+            WordList::Fix => &self.fixlist,
+        }
+    }
+
+    #[must_use]
+    pub fn overrides(&self) -> &BTreeMap<String, TrieOverride> {
+        &self.overrides
+    }
+
+    /// The normalised entries of `list` that words are matched against.
+    pub(crate) fn matched(&self, list: WordList) -> &HashSet<String> {
+        match list {
+            WordList::Bad => &self.matched_badlist,
+            WordList::Fix => &self.matched_fixlist,
+        }
+    }
+
+    pub(crate) fn matched_overrides(&self) -> &[(String, TrieOverride)] {
+        &self.matched_overrides
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.badlist.is_empty() && self.fixlist.is_empty() && self.overrides.is_empty()
+    }
+
+    pub(crate) fn trie(&self) -> Option<&Trie> {
+        self.trie.as_ref()
+    }
+}
@@ -0,0 +1,274 @@
+//! Folds text into a form the filter can match against, while remembering where every byte came
+//! from so flagged words can be traced back to the original message.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{EMOJI_REGEX, LINKS, MENTIONS, NUMBERS};
+
+/// Characters that render as nothing, used to split words up without it being visible.
+const ZERO_WIDTH: [char; 8] = [
+    '\u{00AD}', '\u{180E}', '\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{2061}', '\u{FEFF}',
+];
+
+/// Lookalikes from other scripts that NFKC leaves alone, mapped to the latin letter they mimic.
+///
+/// Only lowercase is needed as everything is lowercased first.
+const CONFUSABLES: [(char, char); 34] = [
+    // cyrillic
+    ('а', 'a'),
+    ('в', 'b'),
+    ('е', 'e'),
+    ('ё', 'e'),
+    ('һ', 'h'),
+    ('і', 'i'),
+    ('ї', 'i'),
+    ('ј', 'j'),
+    ('к', 'k'),
+    ('ӏ', 'l'),
+    ('м', 'm'),
+    ('н', 'h'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('с', 'c'),
+    ('ѕ', 's'),
+    ('т', 't'),
+    ('у', 'y'),
+    ('х', 'x'),
+    ('ԁ', 'd'),
+    ('ԛ', 'q'),
+    ('ԝ', 'w'),
+    // greek
+    ('α', 'a'),
+    ('β', 'b'),
+    ('ε', 'e'),
+    ('η', 'n'),
+    ('ι', 'i'),
+    ('κ', 'k'),
+    ('ν', 'v'),
+    ('ο', 'o'),
+    ('ρ', 'p'),
+    ('τ', 't'),
+    ('υ', 'u'),
+    // latin
+    ('ɡ', 'g'),
+];
+
+/// Which symbols get read as which letters, only inside words that also contain a letter so
+/// plain numbers are left alone.
+#[derive(Clone, Debug)]
+pub struct LeetMap(HashMap<char, char>);
+
+impl LeetMap {
+    /// Uses `map` instead of the defaults when given.
+    #[must_use]
+    pub fn new(map: Option<HashMap<char, char>>) -> Self {
+        map.map_or_else(Self::default, Self)
+    }
+
+    fn get(&self, c: char) -> Option<char> {
+        self.0.get(&c).copied()
+    }
+}
+
+impl Default for LeetMap {
+    fn default() -> Self {
+        Self(HashMap::from([
+            ('4', 'a'),
+            ('@', 'a'),
+            ('8', 'b'),
+            ('3', 'e'),
+            ('6', 'g'),
+            ('1', 'i'),
+            ('0', 'o'),
+            ('5', 's'),
+            ('$', 's'),
+            ('7', 't'),
+        ]))
+    }
+}
+
+/// Normalised text along with the original byte offset of each of its bytes.
+#[derive(Clone, Debug)]
+pub struct Normalized {
+    pub text: String,
+    origins: Vec<usize>,
+}
+
+impl Normalized {
+    /// Where the byte at `offset` in [`Normalized::text`] came from in the original.
+    #[must_use]
+    pub fn origin(&self, offset: usize) -> Option<usize> {
+        self.origins.get(offset).copied()
+    }
+
+    /// The byte range of `original` that `range` of [`Normalized::text`] came from.
+    #[must_use]
+    pub fn origin_range(&self, original: &str, range: Range<usize>) -> Option<Range<usize>> {
+        let start = self.origin(range.start)?;
+        let last = self.origin(range.end.checked_sub(1)?)?;
+        let len = original[last..].chars().next().map_or(0, char::len_utf8);
+
+        Some(start..last + len)
+    }
+
+    /// The words of [`Normalized::text`] with their byte ranges.
+    pub fn words(&self) -> impl Iterator<Item = (Range<usize>, &str)> {
+        let text = self.text.as_str();
+        crate::WHITESPACE.captures_iter(text).map(move |cap| {
+            let word = cap.get(2).unwrap();
+            (word.range(), &text[word.range()])
+        })
+    }
+}
+
+/// Ranges of `content` that are dropped before normalising: mentions, long numbers and links,
+/// and everything but the name of custom emoji.
+fn removed_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+
+    for caps in EMOJI_REGEX.captures_iter(content) {
+        let whole = caps.get(0).unwrap().range();
+        let name = caps.get(2).unwrap().range();
+        ranges.push(whole.start..name.start);
+        ranges.push(name.end..whole.end);
+    }
+    // mentions falsely trigger for spam/evasive.
+    ranges.extend(MENTIONS.find_iter(content).map(|m| m.range()));
+    // remove a reasonable set of numbers, because for whatever reason its inappropriate.
+    ranges.extend(NUMBERS.find_iter(content).map(|m| m.range()));
+    // links can falsely trigger stuff, so they are ommitted.
+    ranges.extend(LINKS.find_iter(content).map(|m| m.range()));
+
+    ranges.sort_unstable_by_key(|r| r.start);
+    ranges
+}
+
+/// Strips what rustrict isn't good with, then applies NFKC, lowercasing, confusables and `leet`.
+#[must_use]
+pub fn normalize(content: &str, leet: &LeetMap) -> Normalized {
+    let removed = removed_ranges(content);
+    let is_removed = |i: usize| removed.iter().any(|r| r.contains(&i));
+
+    // (char, original offset)
+    let mut chars = Vec::with_capacity(content.len());
+    for (i, c) in content.char_indices() {
+        if ZERO_WIDTH.contains(&c) || is_removed(i) {
+            continue;
+        }
+
+        for c in std::iter::once(c).nfkc().flat_map(char::to_lowercase) {
+            let c = CONFUSABLES
+                .iter()
+                .find(|(from, _)| *from == c)
+                .map_or(c, |(_, to)| *to);
+            chars.push((c, i));
+        }
+    }
+
+    // leet speak only within words that have letters in them.
+    let mut start = 0;
+    while start < chars.len() {
+        let space = chars[start].0.is_whitespace();
+        let end = chars[start..]
+            .iter()
+            .position(|(c, _)| c.is_whitespace() != space)
+            .map_or(chars.len(), |p| start + p);
+
+        if !space && chars[start..end].iter().any(|(c, _)| c.is_alphabetic()) {
+            for (c, _) in &mut chars[start..end] {
+                if let Some(mapped) = leet.get(*c) {
+                    *c = mapped;
+                }
+            }
+        }
+        start = end;
+    }
+
+    let mut text = String::with_capacity(chars.len());
+    let mut origins = Vec::with_capacity(chars.len());
+    for (c, origin) in chars {
+        text.push(c);
+        origins.extend(std::iter::repeat_n(origin, c.len_utf8()));
+    }
+
+    Normalized { text, origins }
+}
+
+/// Normalises a list entry like [`normalize`] does message text, so it matches what it looks
+/// like in a normalised message. `None` if nothing is left of it, as that would match anything.
+#[must_use]
+pub fn normalize_entry(entry: &str, leet: &LeetMap) -> Option<String> {
+    let normalized = normalize(entry.trim(), leet).text;
+    let normalized = normalized.trim();
+
+    (!normalized.is_empty()).then(|| normalized.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapped<'a>(original: &'a str, normalized: &Normalized, range: Range<usize>) -> &'a str {
+        &original[normalized.origin_range(original, range).unwrap()]
+    }
+
+    #[test]
+    fn zero_width_characters_are_covered() {
+        let original = "b\u{200B}ad";
+        let normalized = normalize(original, &LeetMap::default());
+
+        assert_eq!(normalized.text, "bad");
+        assert_eq!(
+            normalized.origin_range(original, 0..3),
+            Some(0..original.len())
+        );
+        assert_eq!(mapped(original, &normalized, 1..3), "ad");
+        assert_eq!(mapped(original, &normalized, 0..1), "b");
+    }
+
+    #[test]
+    fn expansions_map_back_to_the_whole_character() {
+        // U+FB01 is one 3 byte character that NFKC turns into "fi".
+        let original = "\u{FB01}ne";
+        let normalized = normalize(original, &LeetMap::default());
+
+        assert_eq!(normalized.text, "fine");
+        assert_eq!(normalized.origin_range(original, 0..1), Some(0..3));
+        assert_eq!(normalized.origin_range(original, 1..2), Some(0..3));
+        assert_eq!(mapped(original, &normalized, 1..4), original);
+        assert_eq!(mapped(original, &normalized, 2..4), "ne");
+    }
+
+    #[test]
+    fn wide_characters_keep_their_full_width() {
+        let original = "ＢＡＤ wоrd";
+        let normalized = normalize(original, &LeetMap::default());
+
+        assert_eq!(normalized.text, "bad word");
+        assert_eq!(mapped(original, &normalized, 0..3), "ＢＡＤ");
+        assert_eq!(mapped(original, &normalized, 1..2), "Ａ");
+        assert_eq!(mapped(original, &normalized, 4..8), "wоrd");
+    }
+
+    #[test]
+    fn removed_ranges_are_skipped() {
+        let original = "hi <@123456> there";
+        let normalized = normalize(original, &LeetMap::default());
+
+        assert_eq!(normalized.text, "hi  there");
+        assert_eq!(mapped(original, &normalized, 4..9), "there");
+        assert_eq!(mapped(original, &normalized, 0..9), original);
+    }
+
+    #[test]
+    fn empty_and_out_of_bounds_ranges() {
+        let original = "bad";
+        let normalized = normalize(original, &LeetMap::default());
+
+        assert_eq!(normalized.origin_range(original, 0..0), None);
+        assert_eq!(normalized.origin_range(original, 2..4), None);
+    }
+}
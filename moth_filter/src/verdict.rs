@@ -0,0 +1,173 @@
+use std::fmt;
+use std::ops::Range;
+
+use rustrict::Type;
+
+use crate::TrieOverride;
+
+/// Why a span was flagged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// rustrict's analysis of the word.
+    Rustrict(Type),
+    /// Contains an entry of the global badlist, or the guild's own when `guild` is set.
+    Badlist { entry: String, guild: bool },
+    /// rustrict flagged it because of a trie override.
+    TrieOverride { word: String, kind: TrieOverride },
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Rustrict(kind) => write!(f, "rustrict ({kind:?})"),
+            Source::Badlist {
+                entry,
+                guild: false,
+            } => write!(f, "badlist `{entry}`"),
+            Source::Badlist { entry, guild: true } => write!(f, "server badlist `{entry}`"),
+            Source::TrieOverride { word, kind } => {
+                write!(f, "override `{word}` ({})", kind.name())
+            }
+        }
+    }
+}
+
+/// A part of the original text that something matched.
+#[derive(Clone, Debug)]
+pub struct Span {
+    /// Byte range in the original text.
+    pub range: Range<usize>,
+    pub source: Source,
+    pub severity: Option<Tier>,
+    /// The fixlist entry the word contains, if any.
+    ///
+    /// Badlist matches with one aren't flagged, rustrict ones are flagged regardless.
+    pub fixlist: Option<String>,
+}
+
+impl Span {
+    #[must_use]
+    pub fn flagged(&self) -> bool {
+        !(matches!(self.source, Source::Badlist { .. }) && self.fixlist.is_some())
+    }
+}
+
+/// What [`check`](crate::check) found in some content.
+#[derive(Clone, Debug)]
+pub struct Verdict<'a> {
+    pub content: &'a str,
+    /// rustrict's analysis of the whole content.
+    pub kind: Type,
+    /// Everything that matched in order of where it starts, including badlist matches that the
+    /// fixlist unflagged.
+    pub spans: Vec<Span>,
+}
+
+/// How bad flagged content is, for deciding what automod does about it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Tier {
+    Mild,
+    Moderate,
+    Severe,
+    /// Trying to get around the filter, like spacing out letters.
+    Evasive,
+    Spam,
+}
+
+impl Tier {
+    pub const ALL: [Tier; 5] = [
+        Tier::Mild,
+        Tier::Moderate,
+        Tier::Severe,
+        Tier::Evasive,
+        Tier::Spam,
+    ];
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Tier::Mild => "mild",
+            Tier::Moderate => "moderate",
+            Tier::Severe => "severe",
+            Tier::Evasive => "evasive",
+            Tier::Spam => "spam",
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|t| t.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The severity of inappropriate content in `kind`, ignoring evasion and spam.
+    #[must_use]
+    pub fn severity(kind: Type) -> Option<Self> {
+        let inappropriate = kind & Type::INAPPROPRIATE;
+
+        if inappropriate.is(Type::SEVERE) {
+            Some(Tier::Severe)
+        } else if inappropriate.is(Type::MODERATE) {
+            Some(Tier::Moderate)
+        } else if inappropriate.is(Type::MILD) {
+            Some(Tier::Mild)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Verdict<'a> {
+    /// The flagged spans.
+    pub fn flagged(&self) -> impl Iterator<Item = &Span> {
+        self.spans.iter().filter(|s| s.flagged())
+    }
+
+    /// The whitespace separated words of the content that contain a flagged span, without
+    /// duplicates.
+    #[must_use]
+    pub fn words(&self) -> Vec<&'a str> {
+        let content = self.content;
+        let mut words: Vec<&'a str> = crate::WHITESPACE
+            .captures_iter(content)
+            .map(|cap| cap.get(2).unwrap())
+            .filter(|word| {
+                self.flagged()
+                    .any(|s| s.range.start < word.end() && word.start() < s.range.end)
+            })
+            .map(|word| &content[word.range()])
+            .collect();
+
+        let mut seen = std::collections::HashSet::new();
+        words.retain(|w| seen.insert(*w));
+        words
+    }
+
+    #[must_use]
+    pub fn badlisted(&self) -> bool {
+        self.flagged()
+            .any(|s| matches!(s.source, Source::Badlist { .. }))
+    }
+
+    /// Every tier the content falls into, badlisted words count as moderate.
+    #[must_use]
+    pub fn tiers(&self) -> Vec<Tier> {
+        let mut tiers = Vec::new();
+
+        match Tier::severity(self.kind) {
+            Some(Tier::Mild) | None if self.badlisted() => tiers.push(Tier::Moderate),
+            Some(tier) => tiers.push(tier),
+            None => {}
+        }
+
+        if self.kind.is(Type::EVASIVE & Type::MODERATE_OR_HIGHER) {
+            tiers.push(Tier::Evasive);
+        }
+        if self.kind.is(Type::SPAM & Type::MODERATE_OR_HIGHER) {
+            tiers.push(Tier::Spam);
+        }
+
+        tiers
+    }
+}
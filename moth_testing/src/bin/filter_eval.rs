@@ -0,0 +1,263 @@
+//! Scores the content filter against a labelled corpus, for judging list and threshold changes
+//! before they are deployed.
+//!
+//! Usage:
+//! - `filter_eval <corpus.jsonl> [--badlist <file>] [--fixlist <file>] [--top <n>]`
+//! - `filter_eval --sample <n> <out.jsonl>`
+//!
+//! Every line of the corpus is a JSON object with the text and whether it should be flagged:
+//! ```json
+//! { "text": "some message", "flagged": false }
+//! ```
+//!
+//! The lists default to the ones in `config/lists`, and the leet map comes from
+//! `config/config.json`. The lists are normalised with it, as the bot does when loading them.
+//!
+//! `--sample` takes random messages from the `messages` table at `DATABASE_URL` and writes them
+//! as a corpus, labelled with what the current lists decide so only the mistakes need fixing up
+//! by hand.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+use moth_core::config::{MothConfig, read_list};
+use moth_core::data::structs::Error;
+use moth_filter::{LeetMap, Lists, Tier, Verdict};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    text: String,
+    flagged: bool,
+}
+
+enum Mode {
+    Evaluate {
+        corpus: String,
+        badlist: String,
+        fixlist: String,
+        top: usize,
+    },
+    Sample {
+        count: i64,
+        out: String,
+    },
+}
+
+const USAGE: &str = "Usage: filter_eval <corpus.jsonl> [--badlist <file>] [--fixlist <file>] \
+                     [--top <n>]\n       filter_eval --sample <n> <out.jsonl>";
+
+fn parse_args() -> Result<Mode, Error> {
+    let mut corpus = None;
+    let mut badlist = String::from("config/lists/badwords.txt");
+    let mut fixlist = String::from("config/lists/fixwords.txt");
+    let mut top = 10;
+    let mut sample = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--badlist" => badlist = args.next().ok_or("--badlist needs a path")?,
+            "--fixlist" => fixlist = args.next().ok_or("--fixlist needs a path")?,
+            "--top" => top = args.next().ok_or("--top needs a number")?.parse()?,
+            "--sample" => sample = Some(args.next().ok_or("--sample needs a number")?.parse()?),
+            _ if corpus.is_none() => corpus = Some(arg),
+            _ => return Err(format!("Unexpected argument: {arg}").into()),
+        }
+    }
+
+    let path = corpus.ok_or(USAGE)?;
+    Ok(match sample {
+        Some(count) => Mode::Sample { count, out: path },
+        None => Mode::Evaluate {
+            corpus: path,
+            badlist,
+            fixlist,
+            top,
+        },
+    })
+}
+
+fn read_words(path: &str, leet: &LeetMap) -> Result<HashSet<String>, Error> {
+    read_list(path, leet).map_err(|e| format!("Failed to read {path}: {e}").into())
+}
+
+fn read_corpus(path: &str) -> Result<Vec<Entry>, Error> {
+    let corpus = std::fs::read_to_string(path)?;
+
+    let mut entries = Vec::new();
+    for (i, line) in corpus.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry = serde_json::from_str(line).map_err(|e| format!("Line {}: {e}", i + 1))?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// How strongly the filter leaned towards flagging, for ordering mistakes worst first.
+fn score(verdict: &Verdict<'_>) -> (Option<Tier>, usize) {
+    let severity = verdict
+        .flagged()
+        .filter_map(|s| s.severity)
+        .max()
+        .or_else(|| Tier::severity(verdict.kind));
+
+    (severity, verdict.flagged().count())
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// One line describing a mistake, with the flagged words highlighted.
+fn describe(verdict: &Verdict<'_>) -> String {
+    let mut line = moth_filter::highlight(verdict.content, verdict).into_owned();
+    write!(line, " ({:?})", verdict.kind).unwrap();
+
+    for span in verdict.flagged() {
+        write!(
+            line,
+            "\n    {}: {}",
+            &verdict.content[span.range.clone()],
+            span.source
+        )
+        .unwrap();
+        if let Some(fix) = &span.fixlist {
+            write!(line, ", despite fixlist `{fix}`").unwrap();
+        }
+    }
+
+    line
+}
+
+fn evaluate(corpus: &str, badlist: &str, fixlist: &str, top: usize) -> Result<(), Error> {
+    let entries = read_corpus(corpus)?;
+    let config = MothConfig::load_config();
+    let badlist = read_words(badlist, &config.events.leet)?;
+    let fixlist = read_words(fixlist, &config.events.leet)?;
+
+    let lists = Lists {
+        badlist: &badlist,
+        fixlist: &fixlist,
+        leet: &config.events.leet,
+        guild: None,
+    };
+
+    let (mut true_pos, mut true_neg) = (0, 0);
+    let mut false_pos = Vec::new();
+    let mut false_neg = Vec::new();
+
+    for entry in &entries {
+        let verdict = moth_filter::check(&entry.text, lists);
+        let flagged = verdict.flagged().next().is_some();
+
+        match (flagged, entry.flagged) {
+            (true, true) => true_pos += 1,
+            (false, false) => true_neg += 1,
+            (true, false) => false_pos.push(verdict),
+            (false, true) => false_neg.push(verdict),
+        }
+    }
+
+    let precision = ratio(true_pos, true_pos + false_pos.len());
+    let recall = ratio(true_pos, true_pos + false_neg.len());
+    let f1 = if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    };
+
+    println!("Evaluated {} entries from {corpus}", entries.len());
+    println!(
+        "True positives: {true_pos}, false positives: {}, true negatives: {true_neg}, false \
+         negatives: {}",
+        false_pos.len(),
+        false_neg.len()
+    );
+    println!("Precision: {precision:.3}, recall: {recall:.3}, F1: {f1:.3}");
+
+    // flagged with the highest severity is the worst false positive, while the worst false
+    // negative is the one rustrict thought was least bad.
+    false_pos.sort_by_key(|v| std::cmp::Reverse(score(v)));
+    false_neg.sort_by_key(score);
+
+    if !false_pos.is_empty() {
+        println!("\nWorst false positives:");
+        for verdict in false_pos.iter().take(top) {
+            println!("  {}", describe(verdict));
+        }
+    }
+
+    if !false_neg.is_empty() {
+        println!("\nWorst false negatives:");
+        for verdict in false_neg.iter().take(top) {
+            println!("  {}", describe(verdict));
+        }
+    }
+
+    Ok(())
+}
+
+async fn sample(count: i64, out: &str) -> Result<(), Error> {
+    let database_url = std::env::var("DATABASE_URL").map_err(|_| "DATABASE_URL is not set")?;
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .connect(&database_url)
+        .await?;
+
+    let contents = sqlx::query_scalar!(
+        "SELECT content FROM messages WHERE content <> '' ORDER BY random() LIMIT $1",
+        count
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let config = MothConfig::load_config();
+    let lists = Lists {
+        badlist: &config.events.badlist,
+        fixlist: &config.events.fixlist,
+        leet: &config.events.leet,
+        guild: None,
+    };
+
+    let written = contents.len();
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(out)?);
+    for text in contents {
+        let flagged = moth_filter::check(&text, lists).flagged().next().is_some();
+        serde_json::to_writer(&mut writer, &Entry { text, flagged })?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+
+    println!("Wrote {written} messages to {out}, check the labels before evaluating.");
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), Error> {
+    match parse_args()? {
+        Mode::Evaluate {
+            corpus,
+            badlist,
+            fixlist,
+            top,
+        } => evaluate(&corpus, &badlist, &fixlist, top),
+        Mode::Sample { count, out } => sample(count, &out).await,
+    }
+}
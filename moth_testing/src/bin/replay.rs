@@ -0,0 +1,213 @@
+//! Replays a recording from `MOTH_RECORD_EVENTS` through the event handler against the mock
+//! HTTP server and in-memory storage.
+//!
+//! Usage: `replay <recording.jsonl> [--expect <expectations.json>] [--realtime]`
+//!
+//! Every event the handler acts on is replayed except `ready`, which would start the background
+//! tasks again, and `guild_members_chunk`, which is only logged. Deleted channels come back
+//! without their cached messages. Other events are only recorded by name and skipped.
+//!
+//! By default events are handled one after the other. `--realtime` dispatches each event at its
+//! recorded offset without waiting for earlier ones to finish, which is what races like the
+//! starboard double post need to show up.
+//!
+//! The expectations file is a JSON object where every key is optional:
+//! ```json
+//! {
+//!     "sent_messages": 1,
+//!     "stored_messages": 4,
+//!     "edits": 0,
+//!     "deletions": 0,
+//!     "starboard": 1,
+//!     "requests": [{ "method": "PUT", "path": "/channels/1/messages/2/reactions", "count": 1 }]
+//! }
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use moth_core::data::storage::{MemoryStorage, Storage};
+use moth_core::data::structs::Error;
+use moth_events::recorder::{RecordedEvent, read_recording};
+use moth_testing::{MockHttp, test_context, test_data};
+use serde::Deserialize;
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expectations {
+    sent_messages: Option<usize>,
+    stored_messages: Option<usize>,
+    edits: Option<usize>,
+    deletions: Option<usize>,
+    starboard: Option<usize>,
+    #[serde(default)]
+    requests: Vec<ExpectedRequest>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectedRequest {
+    method: String,
+    /// Matches any request whose path starts with this.
+    path: String,
+    count: usize,
+}
+
+struct Args {
+    recording: String,
+    expect: Option<String>,
+    realtime: bool,
+}
+
+fn parse_args() -> Result<Args, Error> {
+    let mut recording = None;
+    let mut expect = None;
+    let mut realtime = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--expect" => expect = Some(args.next().ok_or("--expect needs a path")?),
+            "--realtime" => realtime = true,
+            _ if recording.is_none() => recording = Some(arg),
+            _ => return Err(format!("Unexpected argument: {arg}").into()),
+        }
+    }
+
+    Ok(Args {
+        recording: recording
+            .ok_or("Usage: replay <recording.jsonl> [--expect <file>] [--realtime]")?,
+        expect,
+        realtime,
+    })
+}
+
+async fn dispatch(ctx: &lumi::serenity_prelude::Context, recorded: RecordedEvent) {
+    let Some(event) = recorded.event.into_full() else {
+        return;
+    };
+
+    if let Err(e) = moth_events::event_handler(ctx, &event).await {
+        println!("{} errored: {e}", event.snake_case_name());
+    }
+}
+
+fn check(failures: &mut Vec<String>, name: &str, expected: Option<usize>, actual: usize) {
+    if let Some(expected) = expected {
+        if expected != actual {
+            failures.push(format!("{name}: expected {expected}, got {actual}"));
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), Error> {
+    let args = parse_args()?;
+
+    let expectations = match &args.expect {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => Expectations::default(),
+    };
+
+    let events = read_recording(&args.recording)?;
+    let Some(start) = events.first().map(|e| e.timestamp) else {
+        return Err("Recording is empty.".into());
+    };
+
+    let mock = MockHttp::start().await?;
+    let storage = Arc::new(MemoryStorage::new());
+    let data = test_data(storage.clone()).await;
+    let ctx = test_context(data, mock.http());
+
+    println!("Replaying {} events from {}", events.len(), args.recording);
+
+    if args.realtime {
+        let mut handles = Vec::with_capacity(events.len());
+        for recorded in events {
+            let ctx = ctx.clone();
+            let offset = Duration::from_millis((recorded.timestamp - start).max(0) as u64);
+            handles.push(tokio::spawn(async move {
+                tokio::time::sleep(offset).await;
+                dispatch(&ctx, recorded).await;
+            }));
+        }
+
+        for handle in handles {
+            handle.await?;
+        }
+    } else {
+        for recorded in events {
+            dispatch(&ctx, recorded).await;
+        }
+    }
+
+    let sent = mock.sent_messages();
+    let starboard = storage.get_all_starboard().await?;
+
+    println!("Requests made: {}", mock.requests().len());
+    println!("Messages sent: {}", sent.len());
+    println!("Messages stored: {}", storage.messages().len());
+    println!("Edits stored: {}", storage.edits().len());
+    println!("Deletions stored: {}", storage.deletions().len());
+    println!("Starboard entries: {}", starboard.len());
+
+    let mut failures = Vec::new();
+    check(
+        &mut failures,
+        "sent_messages",
+        expectations.sent_messages,
+        sent.len(),
+    );
+    check(
+        &mut failures,
+        "stored_messages",
+        expectations.stored_messages,
+        storage.messages().len(),
+    );
+    check(
+        &mut failures,
+        "edits",
+        expectations.edits,
+        storage.edits().len(),
+    );
+    check(
+        &mut failures,
+        "deletions",
+        expectations.deletions,
+        storage.deletions().len(),
+    );
+    check(
+        &mut failures,
+        "starboard",
+        expectations.starboard,
+        starboard.len(),
+    );
+
+    for expected in &expectations.requests {
+        let actual = mock.requests_to(&expected.method, &expected.path).len();
+        check(
+            &mut failures,
+            &format!("{} {}", expected.method, expected.path),
+            Some(expected.count),
+            actual,
+        );
+    }
+
+    if failures.is_empty() {
+        println!("All expectations met.");
+        return Ok(());
+    }
+
+    for failure in &failures {
+        println!("FAILED {failure}");
+    }
+
+    Err(format!("{} expectation(s) failed.", failures.len()).into())
+}
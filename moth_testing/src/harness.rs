@@ -0,0 +1,282 @@
+//! Runs commands through a real [`lumi::Framework`] against the mock HTTP server.
+//!
+//! ```ignore
+//! let harness = TestHarness::new().await?;
+//! harness.seed_guild();
+//! harness.run_prefix(harness.owner(), "-say hello").await?;
+//! assert_eq!(harness.mock.sent_messages().len(), 1);
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use lumi::serenity_prelude::{self as serenity, Framework as _};
+use serde_json::json;
+
+use moth_core::data::storage::MemoryStorage;
+use moth_core::data::structs::{Data, Error, StarboardConfig};
+
+use crate::mock_http::{
+    BOT_ID, MockHttp, RecordedRequest, channel_json, member_json, message_json, user_json,
+};
+use crate::{test_context, test_data_with};
+
+/// Guild every seeded object belongs to.
+pub const GUILD_ID: u64 = 900000000000000000;
+/// Text channel commands are invoked in.
+pub const CHANNEL_ID: u64 = 900000000000000001;
+/// A user that is a framework owner.
+pub const OWNER_ID: u64 = 900000000000000002;
+/// A user without any special access.
+pub const USER_ID: u64 = 900000000000000003;
+
+pub struct TestHarness {
+    pub mock: MockHttp,
+    pub storage: Arc<MemoryStorage>,
+    pub data: Arc<Data>,
+    pub ctx: serenity::Context,
+    pub framework: lumi::Framework<Data, Error>,
+}
+
+impl TestHarness {
+    /// Builds the harness with every command registered, using the same options as the bot.
+    pub async fn new() -> Result<Self, Error> {
+        Self::with_starboard(StarboardConfig::from_env()).await
+    }
+
+    /// Like [`TestHarness::new`], with `starboard_config` instead of the one from the
+    /// environment.
+    pub async fn with_starboard(starboard_config: StarboardConfig) -> Result<Self, Error> {
+        let options = lumi::FrameworkOptions {
+            commands: moth_commands::commands(),
+            prefix_options: lumi::PrefixFrameworkOptions {
+                prefix: Some("-".into()),
+                ..Default::default()
+            },
+            command_check: Some(|ctx| Box::pin(moth_commands::command_check(ctx))),
+            owners: [serenity::UserId::new(OWNER_ID)].into_iter().collect(),
+            initialize_owners: false,
+            skip_checks_for_owners: false,
+            ..Default::default()
+        };
+
+        let mock = MockHttp::start().await?;
+        let storage = Arc::new(MemoryStorage::new());
+        let data = test_data_with(storage.clone(), starboard_config).await;
+        let ctx = test_context(data.clone(), mock.http());
+
+        Ok(Self {
+            framework: lumi::Framework::new(options),
+            mock,
+            storage,
+            data,
+            ctx,
+        })
+    }
+
+    #[must_use]
+    pub fn owner(&self) -> serenity::UserId {
+        serenity::UserId::new(OWNER_ID)
+    }
+
+    #[must_use]
+    pub fn user(&self) -> serenity::UserId {
+        serenity::UserId::new(USER_ID)
+    }
+
+    /// Seeds [`GUILD_ID`] with an administrator role held by everyone, [`CHANNEL_ID`], and
+    /// members for the bot, [`OWNER_ID`] and [`USER_ID`].
+    pub fn seed_guild(&self) {
+        self.mock.add_guild(json!({
+            "id": GUILD_ID.to_string(),
+            "name": "moth testing",
+            "icon": null,
+            "owner_id": OWNER_ID.to_string(),
+            "roles": [{
+                "id": GUILD_ID.to_string(),
+                "name": "@everyone",
+                "permissions": "8",
+                "position": 0,
+                "color": 0,
+                "hoist": false,
+                "managed": false,
+                "mentionable": false,
+                "flags": 0
+            }],
+            "emojis": [],
+            "features": [],
+            "verification_level": 0,
+            "default_message_notifications": 0,
+            "explicit_content_filter": 0,
+            "mfa_level": 0,
+            "system_channel_flags": 0,
+            "premium_tier": 0,
+            "nsfw_level": 0,
+            "preferred_locale": "en-US",
+            "afk_timeout": 300,
+            "stickers": []
+        }));
+
+        self.mock
+            .add_channel(channel_json(CHANNEL_ID, GUILD_ID, "general"));
+
+        for (id, name, bot) in [
+            (BOT_ID, "moth", true),
+            (OWNER_ID, "owner", false),
+            (USER_ID, "user", false),
+        ] {
+            self.mock
+                .add_member(GUILD_ID, member_json(user_json(id, name, bot)));
+        }
+    }
+
+    /// Adds `count` messages from `author` to the channel history, returning their ids oldest
+    /// first.
+    pub fn seed_messages(&self, author: serenity::UserId, count: usize) -> Vec<u64> {
+        (0..count)
+            .map(|i| {
+                let id = self.mock.next_id();
+                self.mock.add_message(message_json(
+                    id,
+                    CHANNEL_ID,
+                    user_json(author.get(), "seeded", false),
+                    &format!("message {i}"),
+                ));
+                id
+            })
+            .collect()
+    }
+
+    /// Builds a guild message in [`CHANNEL_ID`] as if `author` sent it.
+    pub fn message(
+        &self,
+        author: serenity::UserId,
+        content: &str,
+    ) -> Result<serenity::Message, Error> {
+        let name = if author.get() == OWNER_ID {
+            "owner"
+        } else {
+            "user"
+        };
+
+        let mut message = message_json(
+            self.mock.next_id(),
+            CHANNEL_ID,
+            user_json(author.get(), name, false),
+            content,
+        );
+        message["guild_id"] = json!(GUILD_ID.to_string());
+        message["member"] = json!({
+            "roles": [],
+            "joined_at": "2025-01-01T00:00:00.000000+00:00",
+            "deaf": false,
+            "mute": false,
+            "flags": 0
+        });
+
+        Ok(serde_json::from_value(message)?)
+    }
+
+    /// Dispatches an event to the framework and the event handler, as the client would.
+    pub async fn dispatch(&self, event: &serenity::FullEvent) {
+        self.framework.dispatch(&self.ctx, event).await;
+        let _ = moth_events::event_handler(&self.ctx, event).await;
+    }
+
+    /// Sends `content` as `author` and waits for the framework to handle it.
+    pub async fn run_prefix(&self, author: serenity::UserId, content: &str) -> Result<(), Error> {
+        let new_message = self.message(author, content)?;
+        self.framework
+            .dispatch(&self.ctx, &serenity::FullEvent::Message { new_message })
+            .await;
+
+        Ok(())
+    }
+
+    /// Presses the button `custom_id` on message `message_id` in `channel_id` as `user`, a
+    /// member holding `roles`, and waits for the handlers to finish.
+    ///
+    /// Returns the interaction id, the callback is posted to `/interactions/{id}/token/callback`.
+    pub async fn press_button(
+        &self,
+        user: serenity::UserId,
+        roles: &[serenity::RoleId],
+        channel_id: u64,
+        message_id: u64,
+        custom_id: &str,
+    ) -> Result<u64, Error> {
+        let id = self.mock.next_id();
+        let mut message = message_json(message_id, channel_id, user_json(BOT_ID, "moth", true), "");
+        message["guild_id"] = json!(GUILD_ID.to_string());
+
+        let interaction = json!({
+            "id": id.to_string(),
+            "application_id": BOT_ID.to_string(),
+            "type": 3,
+            "data": {
+                "custom_id": custom_id,
+                "component_type": 2
+            },
+            "guild_id": GUILD_ID.to_string(),
+            "channel_id": channel_id.to_string(),
+            "member": {
+                "user": user_json(user.get(), "presser", false),
+                "roles": roles.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                "joined_at": "2025-01-01T00:00:00.000000+00:00",
+                "deaf": false,
+                "mute": false,
+                "flags": 0,
+                "permissions": "8"
+            },
+            "token": "token",
+            "version": 1,
+            "message": message,
+            "app_permissions": "8",
+            "locale": "en-US",
+            "guild_locale": "en-US",
+            "entitlements": [],
+            "authorizing_integration_owners": {},
+            "context": 0,
+            "attachment_size_limit": 26214400
+        });
+
+        let interaction: serenity::Interaction = serde_json::from_value(interaction)?;
+        self.dispatch(&serenity::FullEvent::InteractionCreate { interaction })
+            .await;
+
+        Ok(id)
+    }
+
+    /// Waits until at least `count` requests matching `method` and `path` were made, or
+    /// `timeout` passes.
+    ///
+    /// For commands that do their work after responding, such as `purge-in`.
+    pub async fn wait_for_requests(
+        &self,
+        method: &str,
+        path: &str,
+        count: usize,
+        timeout: Duration,
+    ) -> Vec<RecordedRequest> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let requests = self.mock.requests_to(method, path);
+            if requests.len() >= count || tokio::time::Instant::now() >= deadline {
+                return requests;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    /// Panics unless exactly `count` requests match `method` and `path`.
+    pub fn assert_requests(&self, method: &str, path: &str, count: usize) -> Vec<RecordedRequest> {
+        let requests = self.mock.requests_to(method, path);
+        assert_eq!(
+            requests.len(),
+            count,
+            "expected {count} {method} {path} request(s), got: {:#?}",
+            self.mock.requests()
+        );
+        requests
+    }
+}
@@ -0,0 +1,67 @@
+//! Helpers for running the bot without Discord or Postgres.
+//!
+//! [`MockHttp`] stands in for the REST API and [`MemoryStorage`] for the database, the replay
+//! binary uses both to feed recorded gateway events through the event handler, and
+//! [`TestHarness`] uses them to run commands.
+
+#![warn(clippy::pedantic)]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+pub mod harness;
+pub mod mock_http;
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use dashmap::DashMap;
+use lumi::serenity_prelude as serenity;
+use moth_core::data::database::Database;
+use moth_core::data::storage::MemoryStorage;
+use moth_core::data::structs::{AntiDeleteCache, Data, StarboardConfig};
+
+pub use harness::TestHarness;
+pub use mock_http::{MockHttp, RecordedRequest};
+
+/// Builds [`Data`] backed by `storage`, with the default config and starboard config from the
+/// environment.
+pub async fn test_data(storage: Arc<MemoryStorage>) -> Arc<Data> {
+    test_data_with(storage, StarboardConfig::from_env()).await
+}
+
+/// Like [`test_data`], but with an explicit starboard config.
+pub async fn test_data_with(
+    storage: Arc<MemoryStorage>,
+    starboard_config: StarboardConfig,
+) -> Arc<Data> {
+    Arc::new(Data {
+        has_started: AtomicBool::new(true),
+        time_started: std::time::Instant::now(),
+        database: Database::in_memory(storage).await,
+        reqwest: reqwest::Client::new(),
+        config: parking_lot::RwLock::new(moth_core::config::MothConfig::new()),
+        anti_delete_cache: AntiDeleteCache::default(),
+        starboard_config,
+        ocr_engine: moth_core::ocr::OcrEngine::new(),
+        new_join_vc: DashMap::default(),
+        event_log: moth_core::event_log::EventLog::disabled(),
+        modlog_queue: moth_core::data::modlog::ModlogQueue::default(),
+        checked_names: moth_core::data::names::CheckedNames::default(),
+    })
+}
+
+/// A context equivalent to what a shard runner hands to the event handler, without a gateway
+/// connection behind it.
+///
+/// Anything sent to the shard, such as presence updates, is dropped.
+#[must_use]
+pub fn test_context(data: Arc<Data>, http: Arc<serenity::Http>) -> serenity::Context {
+    let (tx, _rx) = futures_channel::mpsc::unbounded();
+
+    serenity::Context::new(
+        data,
+        serenity::ShardMessenger::new(tx),
+        serenity::ShardId(0),
+        http,
+        Arc::new(serenity::Cache::new()),
+    )
+}
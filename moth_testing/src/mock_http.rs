@@ -0,0 +1,534 @@
+//! A stand-in for the Discord REST API that records every request it receives.
+//!
+//! Point serenity at it with [`MockHttp::http`], which routes requests through the server as a
+//! proxy. Endpoints the bot uses return objects seeded with the `add_*` methods, or a plausible
+//! default; anything else gets an empty object.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use lumi::serenity_prelude as serenity;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use moth_core::data::structs::Error;
+
+/// The user id the mock hands out for the bot itself.
+pub const BOT_ID: u64 = 1000000000000000000;
+/// The application id used for command registration endpoints.
+pub const APPLICATION_ID: u64 = BOT_ID;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path without the `/api/v10` prefix, such as `/channels/1/messages`.
+    pub path: String,
+    /// Query string without the leading `?`.
+    pub query: Option<String>,
+    pub body: Option<Value>,
+}
+
+impl RecordedRequest {
+    /// Gets a query parameter from the request.
+    #[must_use]
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        self.query.as_deref()?.split('&').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            (key == name).then_some(value)
+        })
+    }
+}
+
+/// Objects served back by the mock, keyed by their snowflake as a string.
+#[derive(Default)]
+struct Seeded {
+    users: HashMap<String, Value>,
+    guilds: HashMap<String, Value>,
+    channels: HashMap<String, Value>,
+    /// (guild, user) to member.
+    members: HashMap<(String, String), Value>,
+    /// Channel to messages, newest last.
+    messages: HashMap<String, Vec<Value>>,
+    audit_logs: HashMap<String, Value>,
+    invites: HashMap<String, Value>,
+}
+
+struct Inner {
+    requests: Mutex<Vec<RecordedRequest>>,
+    seeded: Mutex<Seeded>,
+    next_id: AtomicU64,
+}
+
+#[derive(Clone)]
+pub struct MockHttp {
+    addr: SocketAddr,
+    inner: Arc<Inner>,
+}
+
+impl MockHttp {
+    /// Binds to a random local port and starts serving in the background.
+    pub async fn start() -> Result<Self, Error> {
+        let inner = Arc::new(Inner {
+            requests: Mutex::default(),
+            seeded: Mutex::default(),
+            // snowflakes handed out for created objects, high enough to not collide with recordings.
+            next_id: AtomicU64::new(1 << 60),
+        });
+
+        let app = Router::new().fallback(handle).with_state(inner.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                eprintln!("Mock HTTP server stopped: {e}");
+            }
+        });
+
+        let mock = Self { addr, inner };
+        mock.add_user(user_json(BOT_ID, "moth", true));
+
+        Ok(mock)
+    }
+
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A serenity HTTP client that sends everything to this server.
+    #[must_use]
+    pub fn http(&self) -> Arc<serenity::Http> {
+        let token = "MTAwMDAwMDAwMDAwMDAwMDAw.AAAAAA.mock-token-for-local-testing"
+            .parse::<serenity::Token>()
+            .expect("mock token is well formed");
+
+        let http = serenity::HttpBuilder::new(token)
+            .proxy(self.url())
+            .ratelimiter_disabled(true)
+            .build();
+        http.set_application_id(serenity::ApplicationId::new(APPLICATION_ID));
+
+        Arc::new(http)
+    }
+
+    /// Hands out a snowflake that hasn't been used by the mock yet.
+    #[must_use]
+    pub fn next_id(&self) -> u64 {
+        self.inner.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    pub fn add_user(&self, user: Value) {
+        let id = snowflake_of(&user);
+        self.inner.seeded.lock().users.insert(id, user);
+    }
+
+    pub fn add_guild(&self, guild: Value) {
+        let id = snowflake_of(&guild);
+        self.inner.seeded.lock().guilds.insert(id, guild);
+    }
+
+    pub fn add_channel(&self, channel: Value) {
+        let id = snowflake_of(&channel);
+        self.inner.seeded.lock().channels.insert(id, channel);
+    }
+
+    /// Adds a member, the user inside it is also served from `/users`.
+    pub fn add_member(&self, guild_id: u64, member: Value) {
+        let user_id = member["user"]["id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let mut seeded = self.inner.seeded.lock();
+        seeded.users.insert(user_id.clone(), member["user"].clone());
+        seeded
+            .members
+            .insert((guild_id.to_string(), user_id), member);
+    }
+
+    /// Adds a message to the channel history, messages should be added oldest first.
+    pub fn add_message(&self, message: Value) {
+        let channel_id = message["channel_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        self.inner
+            .seeded
+            .lock()
+            .messages
+            .entry(channel_id)
+            .or_default()
+            .push(message);
+    }
+
+    pub fn set_audit_log(&self, guild_id: u64, audit_log: Value) {
+        self.inner
+            .seeded
+            .lock()
+            .audit_logs
+            .insert(guild_id.to_string(), audit_log);
+    }
+
+    pub fn add_invite(&self, invite: Value) {
+        let code = invite["code"].as_str().unwrap_or_default().to_string();
+        self.inner.seeded.lock().invites.insert(code, invite);
+    }
+
+    /// Every request received so far.
+    #[must_use]
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.inner.requests.lock().clone()
+    }
+
+    /// Requests matching `method` whose path starts with `path`.
+    #[must_use]
+    pub fn requests_to(&self, method: &str, path: &str) -> Vec<RecordedRequest> {
+        self.inner
+            .requests
+            .lock()
+            .iter()
+            .filter(|r| r.method == method && r.path.starts_with(path))
+            .cloned()
+            .collect()
+    }
+
+    /// Messages sent by the bot, across every channel.
+    #[must_use]
+    pub fn sent_messages(&self) -> Vec<RecordedRequest> {
+        self.inner
+            .requests
+            .lock()
+            .iter()
+            .filter(|r| r.method == "POST" && is_message_create(&r.path))
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        self.inner.requests.lock().clear();
+    }
+}
+
+fn snowflake_of(value: &Value) -> String {
+    value["id"].as_str().unwrap_or_default().to_string()
+}
+
+fn strip_api_prefix(path: &str) -> &str {
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    path.strip_prefix("/v10").unwrap_or(path)
+}
+
+fn is_message_create(path: &str) -> bool {
+    let segments = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+    matches!(segments.as_slice(), ["channels", _, "messages"])
+}
+
+fn json_or_not_found(value: Option<Value>, message: &str) -> Response {
+    match value {
+        Some(value) => axum::Json(value).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            axum::Json(json!({ "code": 10000, "message": message })),
+        )
+            .into_response(),
+    }
+}
+
+async fn handle(
+    State(inner): State<Arc<Inner>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let path = strip_api_prefix(uri.path()).to_string();
+    let body = serde_json::from_slice::<Value>(&body).ok();
+
+    let request = RecordedRequest {
+        method: method.to_string(),
+        path: path.clone(),
+        query: uri.query().map(ToString::to_string),
+        body,
+    };
+    inner.requests.lock().push(request.clone());
+
+    let segments = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+    respond(&inner, &segments, &request)
+}
+
+fn respond(inner: &Inner, segments: &[&str], request: &RecordedRequest) -> Response {
+    let body = request.body.as_ref();
+    let next_id = || inner.next_id.fetch_add(1, Ordering::Relaxed);
+
+    match (request.method.as_str(), segments) {
+        ("GET", ["channels", channel_id]) => {
+            let channel = inner.seeded.lock().channels.get(*channel_id).cloned();
+            json_or_not_found(channel, "Unknown Channel")
+        }
+        ("GET", ["channels", channel_id, "messages"]) => {
+            let limit = request
+                .query_param("limit")
+                .and_then(|l| l.parse::<usize>().ok())
+                .unwrap_or(50);
+            let before = request
+                .query_param("before")
+                .and_then(|b| b.parse::<u64>().ok());
+
+            let seeded = inner.seeded.lock();
+            let messages = seeded
+                .messages
+                .get(*channel_id)
+                .map(|messages| {
+                    messages
+                        .iter()
+                        .rev()
+                        .filter(|m| {
+                            let id = m["id"].as_str().and_then(|id| id.parse::<u64>().ok());
+                            before.is_none_or(|before| id.is_some_and(|id| id < before))
+                        })
+                        .take(limit)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            axum::Json(messages).into_response()
+        }
+        ("GET", ["channels", channel_id, "messages", message_id]) => {
+            let message = inner
+                .seeded
+                .lock()
+                .messages
+                .get(*channel_id)
+                .and_then(|m| m.iter().find(|m| m["id"] == *message_id).cloned());
+
+            json_or_not_found(message, "Unknown Message")
+        }
+        ("POST", ["channels", channel_id, "messages"]) => {
+            axum::Json(fake_message(next_id(), channel_id, body)).into_response()
+        }
+        ("PATCH", ["channels", channel_id, "messages", message_id]) => {
+            let id = message_id.parse().unwrap_or_default();
+            axum::Json(fake_message(id, channel_id, body)).into_response()
+        }
+        ("POST", ["channels", channel_id, "messages", "bulk-delete"]) => {
+            if let Some(ids) = body.and_then(|b| b["messages"].as_array()) {
+                let mut seeded = inner.seeded.lock();
+                if let Some(messages) = seeded.messages.get_mut(*channel_id) {
+                    messages.retain(|m| !ids.contains(&m["id"]));
+                }
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
+        ("DELETE", ["channels", channel_id, "messages", message_id]) => {
+            let mut seeded = inner.seeded.lock();
+            if let Some(messages) = seeded.messages.get_mut(*channel_id) {
+                messages.retain(|m| m["id"] != *message_id);
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
+        ("GET", ["users", user_id]) => {
+            let user_id = if *user_id == "@me" {
+                BOT_ID.to_string()
+            } else {
+                (*user_id).to_string()
+            };
+
+            let user = inner.seeded.lock().users.get(&user_id).cloned();
+            json_or_not_found(user, "Unknown User")
+        }
+        ("POST", ["users", "@me", "channels"]) => axum::Json(json!({
+            "id": next_id().to_string(),
+            "type": 1,
+            "recipients": [{ "id": body.map_or(Value::Null, |b| b["recipient_id"].clone()) }],
+        }))
+        .into_response(),
+        ("GET", ["guilds", guild_id]) => {
+            let guild = inner.seeded.lock().guilds.get(*guild_id).cloned();
+            json_or_not_found(guild, "Unknown Guild")
+        }
+        ("GET", ["guilds", guild_id, "members", user_id]) => {
+            let member = inner
+                .seeded
+                .lock()
+                .members
+                .get(&((*guild_id).to_string(), (*user_id).to_string()))
+                .cloned();
+
+            json_or_not_found(member, "Unknown Member")
+        }
+        ("PATCH", ["guilds", guild_id, "members", user_id]) => {
+            let mut seeded = inner.seeded.lock();
+            let member = seeded
+                .members
+                .get_mut(&((*guild_id).to_string(), (*user_id).to_string()))
+                .map(|member| {
+                    if let (Some(member), Some(Value::Object(changes))) =
+                        (member.as_object_mut(), body)
+                    {
+                        for (key, value) in changes {
+                            member.insert(key.clone(), value.clone());
+                        }
+                    }
+                    member.clone()
+                });
+
+            json_or_not_found(member, "Unknown Member")
+        }
+        ("GET", ["guilds", guild_id, "audit-logs"]) => {
+            let audit_log = inner
+                .seeded
+                .lock()
+                .audit_logs
+                .get(*guild_id)
+                .cloned()
+                .unwrap_or_else(|| {
+                    json!({
+                        "application_commands": [],
+                        "audit_log_entries": [],
+                        "auto_moderation_rules": [],
+                        "guild_scheduled_events": [],
+                        "integrations": [],
+                        "threads": [],
+                        "users": [],
+                        "webhooks": []
+                    })
+                });
+
+            axum::Json(audit_log).into_response()
+        }
+        ("GET", ["invites", code]) => {
+            let invite = inner.seeded.lock().invites.get(*code).cloned();
+            json_or_not_found(invite, "Unknown Invite")
+        }
+        // Registration echoes back what was sent, with ids filled in.
+        ("PUT", ["applications", _, "commands"] | ["applications", _, "guilds", _, "commands"]) => {
+            let commands = body
+                .and_then(Value::as_array)
+                .map(|commands| {
+                    commands
+                        .iter()
+                        .map(|command| {
+                            let mut command = command.clone();
+                            command["id"] = json!(next_id().to_string());
+                            command["application_id"] = json!(APPLICATION_ID.to_string());
+                            command["version"] = json!("1");
+                            command
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            axum::Json(commands).into_response()
+        }
+        ("GET", ["applications", _, "commands"] | ["applications", _, "guilds", _, "commands"]) => {
+            axum::Json(json!([])).into_response()
+        }
+        ("POST" | "PATCH", ["webhooks", _, _, ..]) => {
+            axum::Json(fake_message(next_id(), "0", body)).into_response()
+        }
+        // reactions, roles, bans, kicks and interaction callbacks have no body.
+        ("PUT" | "DELETE", _) | ("POST", ["interactions", _, _, "callback"]) => {
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => axum::Json(json!({})).into_response(),
+    }
+}
+
+/// A minimal user object.
+#[must_use]
+pub fn user_json(id: u64, name: &str, bot: bool) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": name,
+        "discriminator": "0",
+        "global_name": null,
+        "avatar": null,
+        "bot": bot
+    })
+}
+
+/// A minimal text channel object in `guild_id`, without permission overwrites.
+#[must_use]
+pub fn channel_json(id: u64, guild_id: u64, name: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "type": 0,
+        "guild_id": guild_id.to_string(),
+        "name": name,
+        "position": 0,
+        "permission_overwrites": [],
+        "nsfw": false,
+        "topic": null,
+        "last_message_id": null,
+        "rate_limit_per_user": 0,
+        "parent_id": null,
+        "flags": 0
+    })
+}
+
+/// A minimal member object, joined without roles.
+#[must_use]
+pub fn member_json(user: Value) -> Value {
+    json!({
+        "user": user,
+        "nick": null,
+        "avatar": null,
+        "roles": [],
+        "joined_at": "2025-01-01T00:00:00.000000+00:00",
+        "deaf": false,
+        "mute": false,
+        "flags": 0
+    })
+}
+
+/// A minimal message object in `channel_id` sent by `author`.
+#[must_use]
+pub fn message_json(id: u64, channel_id: u64, author: Value, content: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel_id.to_string(),
+        "author": author,
+        "content": content,
+        "timestamp": "2025-01-01T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "components": [],
+        "pinned": false,
+        "type": 0,
+        "flags": 0
+    })
+}
+
+/// A message from the bot echoing what was sent.
+fn fake_message(id: u64, channel_id: &str, body: Option<&Value>) -> Value {
+    let mut message = message_json(
+        id,
+        channel_id.parse().unwrap_or_default(),
+        user_json(BOT_ID, "moth", true),
+        "",
+    );
+
+    if let Some(body) = body {
+        for field in ["content", "embeds", "components", "flags"] {
+            if let Some(value) = body.get(field) {
+                message[field] = value.clone();
+            }
+        }
+    }
+
+    message
+}
@@ -0,0 +1,192 @@
+//! Runs prefix commands through the harness and checks what they sent to Discord.
+
+use lumi::serenity_prelude as serenity;
+use moth_core::data::structs::Error;
+use moth_testing::TestHarness;
+use moth_testing::harness::{CHANNEL_ID, OWNER_ID, USER_ID};
+use serde_json::Value;
+
+fn bulk_delete_path() -> String {
+    format!("/channels/{CHANNEL_ID}/messages/bulk-delete")
+}
+
+fn deleted_ids(request: &moth_testing::RecordedRequest) -> Vec<u64> {
+    request.body.as_ref().unwrap()["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|id| match id {
+            Value::String(id) => id.parse().unwrap(),
+            id => id.as_u64().unwrap(),
+        })
+        .collect()
+}
+
+fn contents(harness: &TestHarness) -> Vec<String> {
+    harness
+        .mock
+        .sent_messages()
+        .iter()
+        .filter_map(|r| {
+            r.body.as_ref()?["content"]
+                .as_str()
+                .map(ToString::to_string)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn purge_deletes_history_and_the_command() -> Result<(), Error> {
+    let harness = TestHarness::new().await?;
+    harness.seed_guild();
+    let seeded = harness.seed_messages(harness.user(), 5);
+
+    harness.run_prefix(harness.owner(), "-purge 5").await?;
+
+    let requests = harness.assert_requests("POST", &bulk_delete_path(), 1);
+    let deleted = deleted_ids(&requests[0]);
+    assert_eq!(deleted.len(), 6, "five seeded messages and the command");
+    for id in seeded {
+        assert!(deleted.contains(&id), "{id} wasn't purged: {deleted:?}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn purge_user_modifier_only_deletes_their_messages() -> Result<(), Error> {
+    let harness = TestHarness::new().await?;
+    harness.seed_guild();
+    let kept = harness.seed_messages(harness.owner(), 3);
+    let purged = harness.seed_messages(harness.user(), 3);
+
+    harness
+        .run_prefix(harness.owner(), &format!("-purge 10 user <@{USER_ID}>"))
+        .await?;
+
+    let requests = harness.assert_requests("POST", &bulk_delete_path(), 1);
+    let deleted = deleted_ids(&requests[0]);
+    for id in purged {
+        assert!(deleted.contains(&id), "{id} wasn't purged: {deleted:?}");
+    }
+    for id in kept {
+        assert!(!deleted.contains(&id), "{id} was purged: {deleted:?}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn purge_rejects_out_of_range_limits() -> Result<(), Error> {
+    let harness = TestHarness::new().await?;
+    harness.seed_guild();
+    harness.seed_messages(harness.user(), 3);
+
+    harness.run_prefix(harness.owner(), "-purge 1").await?;
+
+    harness.assert_requests("POST", &bulk_delete_path(), 0);
+    assert!(
+        harness
+            .mock
+            .requests_to("GET", &format!("/channels/{CHANNEL_ID}/messages"))
+            .is_empty(),
+        "history was fetched for an invalid limit"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn say_sends_the_text_for_owners() -> Result<(), Error> {
+    let harness = TestHarness::new().await?;
+    harness.seed_guild();
+
+    harness
+        .run_prefix(harness.owner(), "-say hello there")
+        .await?;
+
+    let requests = harness.assert_requests("POST", &format!("/channels/{CHANNEL_ID}/messages"), 1);
+    assert_eq!(requests[0].body.as_ref().unwrap()["content"], "hello there");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn say_ignores_other_users() -> Result<(), Error> {
+    let harness = TestHarness::new().await?;
+    harness.seed_guild();
+
+    harness
+        .run_prefix(harness.user(), "-say hello there")
+        .await?;
+
+    assert!(
+        !contents(&harness).iter().any(|c| c == "hello there"),
+        "a non-owner made the bot speak: {:#?}",
+        harness.mock.sent_messages()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bot_ban_blocks_later_commands() -> Result<(), Error> {
+    let harness = TestHarness::new().await?;
+    harness.seed_guild();
+
+    harness
+        .run_prefix(harness.owner(), &format!("-bot-ban {USER_ID}"))
+        .await?;
+
+    assert!(harness.data.database.is_banned(&harness.user()));
+    assert!(
+        contents(&harness)
+            .iter()
+            .any(|c| c.ends_with("has been banned from using moth.")),
+        "no confirmation: {:#?}",
+        harness.mock.sent_messages()
+    );
+
+    harness.mock.clear();
+    harness
+        .run_prefix(harness.user(), "-say hello there")
+        .await?;
+
+    let sent = harness.mock.sent_messages();
+    assert_eq!(sent.len(), 1, "expected only the ban notice: {sent:#?}");
+    let embed = &sent[0].body.as_ref().unwrap()["embeds"][0];
+    assert!(
+        embed["description"]
+            .as_str()
+            .is_some_and(|d| d.starts_with("You have been banned from using the bot.")),
+        "unexpected notice: {embed:#?}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn bot_ban_is_owner_only() -> Result<(), Error> {
+    let harness = TestHarness::new().await?;
+    harness.seed_guild();
+
+    harness
+        .run_prefix(harness.user(), &format!("-bot-ban {OWNER_ID}"))
+        .await?;
+
+    assert!(
+        !harness
+            .data
+            .database
+            .is_banned(&serenity::UserId::new(OWNER_ID))
+    );
+    assert!(
+        !contents(&harness)
+            .iter()
+            .any(|c| c.contains("banned from using moth")),
+        "a non-owner banned someone: {:#?}",
+        harness.mock.sent_messages()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Presses the starboard review buttons through the harness.
+
+use lumi::serenity_prelude as serenity;
+use moth_core::data::database::{
+    ChannelIdWrapper, MaybeMessageIdWrapper, MessageIdWrapper, StarboardMessage, StarboardStatus,
+    UserIdWrapper,
+};
+use moth_core::data::storage::Storage;
+use moth_core::data::structs::{Error, StarboardConfig};
+use moth_testing::TestHarness;
+use moth_testing::harness::{CHANNEL_ID, GUILD_ID, OWNER_ID, USER_ID};
+
+const QUEUE_ID: u64 = 900000000000000010;
+const POST_ID: u64 = 900000000000000011;
+const REVIEWER_ROLE: u64 = 900000000000000012;
+const ORIGINAL_ID: u64 = 900000000000000020;
+const QUEUED_ID: u64 = 900000000000000021;
+
+async fn harness() -> Result<TestHarness, Error> {
+    let harness = TestHarness::with_starboard(StarboardConfig {
+        active: true,
+        queue_channel: serenity::GenericChannelId::new(QUEUE_ID),
+        post_channel: serenity::GenericChannelId::new(POST_ID),
+        star_emoji: "⭐".to_string(),
+        guild_id: serenity::GuildId::new(GUILD_ID),
+        allowed_role: serenity::RoleId::new(REVIEWER_ROLE),
+        threshold: 3,
+    })
+    .await?;
+    harness.seed_guild();
+
+    harness
+        .data
+        .database
+        .insert_starboard_msg(
+            StarboardMessage {
+                id: 0,
+                user_id: UserIdWrapper(serenity::UserId::new(USER_ID)),
+                username: "user".to_string(),
+                avatar_url: None,
+                content: "a starred message".to_string(),
+                channel_id: ChannelIdWrapper(serenity::GenericChannelId::new(CHANNEL_ID)),
+                message_id: MessageIdWrapper(serenity::MessageId::new(ORIGINAL_ID)),
+                attachment_urls: Vec::new(),
+                star_count: 3,
+                starboard_status: StarboardStatus::InReview,
+                starboard_message_id: MessageIdWrapper(serenity::MessageId::new(QUEUED_ID)),
+                starboard_message_channel: ChannelIdWrapper(serenity::GenericChannelId::new(
+                    QUEUE_ID,
+                )),
+                reply_message_id: MaybeMessageIdWrapper::new(None),
+                reply_username: None,
+                forwarded: false,
+            },
+            Some(serenity::GuildId::new(GUILD_ID)),
+        )
+        .await?;
+
+    Ok(harness)
+}
+
+async fn stored(harness: &TestHarness) -> Result<StarboardMessage, Error> {
+    harness
+        .storage
+        .get_starboard(serenity::MessageId::new(ORIGINAL_ID))
+        .await
+}
+
+fn callback(harness: &TestHarness, interaction_id: u64) -> serde_json::Value {
+    let requests = harness.assert_requests(
+        "POST",
+        &format!("/interactions/{interaction_id}/token/callback"),
+        1,
+    );
+    requests[0].body.clone().unwrap()
+}
+
+#[tokio::test]
+async fn accept_posts_to_the_starboard() -> Result<(), Error> {
+    let harness = harness().await?;
+    let reviewer = [serenity::RoleId::new(REVIEWER_ROLE)];
+
+    let interaction = harness
+        .press_button(
+            harness.owner(),
+            &reviewer,
+            QUEUE_ID,
+            QUEUED_ID,
+            "starboard_accept",
+        )
+        .await?;
+
+    let response = callback(&harness, interaction);
+    assert_eq!(response["type"], 7, "the queue message should be updated");
+    assert_eq!(
+        response["data"]["content"],
+        format!("Approved by <@{OWNER_ID}>")
+    );
+    assert_eq!(response["data"]["components"], serde_json::json!([]));
+
+    let posted = harness.assert_requests("POST", &format!("/channels/{POST_ID}/messages"), 1);
+    assert!(posted[0].body.is_some());
+
+    let starboard = stored(&harness).await?;
+    assert_eq!(starboard.starboard_status, StarboardStatus::Accepted);
+    assert_eq!(
+        *starboard.starboard_message_channel,
+        serenity::GenericChannelId::new(POST_ID)
+    );
+    assert_ne!(
+        *starboard.starboard_message_id,
+        serenity::MessageId::new(QUEUED_ID)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn deny_marks_the_message_denied() -> Result<(), Error> {
+    let harness = harness().await?;
+    let reviewer = [serenity::RoleId::new(REVIEWER_ROLE)];
+
+    let interaction = harness
+        .press_button(
+            harness.owner(),
+            &reviewer,
+            QUEUE_ID,
+            QUEUED_ID,
+            "starboard_deny",
+        )
+        .await?;
+
+    let response = callback(&harness, interaction);
+    assert_eq!(response["type"], 7);
+    assert_eq!(
+        response["data"]["content"],
+        format!("Denied by <@{OWNER_ID}>")
+    );
+
+    harness.assert_requests("POST", &format!("/channels/{POST_ID}/messages"), 0);
+    assert_eq!(
+        stored(&harness).await?.starboard_status,
+        StarboardStatus::Denied
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn members_without_the_role_are_refused() -> Result<(), Error> {
+    let harness = harness().await?;
+
+    let interaction = harness
+        .press_button(harness.user(), &[], QUEUE_ID, QUEUED_ID, "starboard_accept")
+        .await?;
+
+    let response = callback(&harness, interaction);
+    assert_eq!(response["type"], 4, "expected a new ephemeral message");
+    assert_eq!(
+        response["data"]["content"],
+        "You are not allowed to do this."
+    );
+    assert_eq!(response["data"]["flags"], 64);
+
+    harness.assert_requests("POST", &format!("/channels/{POST_ID}/messages"), 0);
+    assert_eq!(
+        stored(&harness).await?.starboard_status,
+        StarboardStatus::InReview
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn buttons_outside_the_queue_are_ignored() -> Result<(), Error> {
+    let harness = harness().await?;
+    let reviewer = [serenity::RoleId::new(REVIEWER_ROLE)];
+
+    harness
+        .press_button(
+            harness.owner(),
+            &reviewer,
+            CHANNEL_ID,
+            QUEUED_ID,
+            "starboard_accept",
+        )
+        .await?;
+
+    assert!(
+        harness
+            .mock
+            .requests_to("POST", "/interactions/")
+            .is_empty()
+    );
+    assert_eq!(
+        stored(&harness).await?.starboard_status,
+        StarboardStatus::InReview
+    );
+
+    Ok(())
+}
@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use lumi::serenity_prelude::{Cache, Colour, GenericChannelId, GuildId, UserId};
+use moth_core::event_log::{EventKind, LogRecord};
+
+/// How many events are kept, and held while paused, before the oldest are dropped.
+const MAX_ENTRIES: usize = 10_000;
+/// How far `PageUp`/`PageDown` move the selection.
+const PAGE: usize = 20;
+
+pub const KINDS: [EventKind; 21] = [
+    EventKind::MessageCreate,
+    EventKind::MessageEdit,
+    EventKind::MessageDelete,
+    EventKind::ReactionAdd,
+    EventKind::ReactionRemove,
+    EventKind::ChannelCreate,
+    EventKind::ChannelUpdate,
+    EventKind::ChannelDelete,
+    EventKind::ThreadCreate,
+    EventKind::ThreadUpdate,
+    EventKind::ThreadDelete,
+    EventKind::RoleCreate,
+    EventKind::RoleUpdate,
+    EventKind::RoleDelete,
+    EventKind::MemberJoin,
+    EventKind::MemberLeave,
+    EventKind::MemberUpdate,
+    EventKind::MemberRoleUpdate,
+    EventKind::VoiceJoin,
+    EventKind::VoiceLeave,
+    EventKind::VoiceMove,
+];
+
+/// A record with the names it refers to, resolved when it was received so later cache changes
+/// don't rename old entries.
+pub struct Entry {
+    pub record: LogRecord,
+    pub guild: Option<String>,
+    pub channel: Option<String>,
+    pub user: Option<String>,
+    /// The user's role colour, as shown on the console.
+    pub colour: Option<Colour>,
+}
+
+impl Entry {
+    pub fn resolve(cache: &Cache, record: LogRecord) -> Self {
+        let mut entry = Self {
+            guild: None,
+            channel: None,
+            user: None,
+            colour: None,
+            record,
+        };
+
+        let Some(guild_id) = entry.record.guild_id else {
+            return entry;
+        };
+
+        if let Some(channel_id) = entry.record.channel_id {
+            entry.channel = moth_events::helper::cached_channel_name(cache, guild_id, channel_id);
+        }
+
+        let Some(guild) = cache.guild(guild_id) else {
+            return entry;
+        };
+
+        entry.guild = Some(guild.name.to_string());
+
+        if let Some(member) = entry.record.user_id.and_then(|id| guild.members.get(&id)) {
+            entry.user = Some(member.user.tag());
+            entry.colour = moth_events::handlers::messages::role_colour(&guild, &member.roles);
+        }
+
+        entry
+    }
+
+    /// The one line shown in the event list.
+    pub fn summary(&self) -> String {
+        let record = &self.record;
+        match (&record.before, &record.after) {
+            (Some(before), Some(after)) if record.kind == EventKind::MessageEdit => {
+                format!("{before} -> {after}")
+            }
+            (Some(before), Some(after)) if before != after => format!("{before} -> {after}"),
+            (_, Some(after)) => after.clone(),
+            (Some(before), None) => before.clone(),
+            (None, None) => record.changes.first().cloned().unwrap_or_default(),
+        }
+    }
+
+    fn matches(&self, filters: &Filters) -> bool {
+        let record = &self.record;
+
+        if filters.guild.is_some() && record.guild_id != filters.guild {
+            return false;
+        }
+        if filters.channel.is_some() && record.channel_id != filters.channel {
+            return false;
+        }
+        if filters.user.is_some() && record.user_id != filters.user {
+            return false;
+        }
+        if filters.kind.is_some_and(|k| k != record.kind) {
+            return false;
+        }
+
+        if filters.search.is_empty() {
+            return true;
+        }
+
+        let needle = filters.search.to_lowercase();
+        [
+            self.guild.as_deref(),
+            self.channel.as_deref(),
+            self.user.as_deref(),
+            record.before.as_deref(),
+            record.after.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(record.changes.iter().map(String::as_str))
+        .any(|s| s.to_lowercase().contains(&needle))
+    }
+}
+
+#[derive(Default)]
+pub struct Filters {
+    pub guild: Option<GuildId>,
+    pub channel: Option<GenericChannelId>,
+    pub user: Option<UserId>,
+    pub kind: Option<EventKind>,
+    pub search: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Search,
+    Details,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Events,
+    Guilds,
+}
+
+pub struct App {
+    pub entries: VecDeque<Entry>,
+    /// Events received while paused, added once resumed.
+    pub pending: VecDeque<Entry>,
+    pub paused: bool,
+    pub filters: Filters,
+    pub mode: Mode,
+    pub focus: Focus,
+    /// Index into [`App::visible`].
+    pub selected: usize,
+    /// Keep the newest event selected.
+    pub follow: bool,
+    /// Index into [`App::guilds`], 0 being every guild.
+    pub guild_selected: usize,
+    /// Every guild seen so far with how many events it had.
+    pub guild_counts: BTreeMap<GuildId, (String, usize)>,
+    pub quit: bool,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            pending: VecDeque::new(),
+            paused: false,
+            filters: Filters::default(),
+            mode: Mode::Normal,
+            focus: Focus::Events,
+            selected: 0,
+            follow: true,
+            guild_selected: 0,
+            guild_counts: BTreeMap::new(),
+            quit: false,
+        }
+    }
+
+    pub fn push(&mut self, entry: Entry) {
+        if self.paused {
+            // anything past this would be trimmed from `entries` on resume anyway.
+            if self.pending.len() >= MAX_ENTRIES {
+                self.pending.pop_front();
+            }
+            self.pending.push_back(entry);
+            return;
+        }
+
+        if let Some(guild_id) = entry.record.guild_id {
+            let name = entry.guild.clone().unwrap_or_else(|| guild_id.to_string());
+            let count = self.guild_counts.entry(guild_id).or_insert((name, 0));
+            count.1 += 1;
+        }
+
+        self.entries.push_back(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            // `selected` indexes the visible entries, so it only shifts if one of them went.
+            if self
+                .entries
+                .pop_front()
+                .is_some_and(|e| e.matches(&self.filters))
+            {
+                self.selected = self.selected.saturating_sub(1);
+            }
+        }
+    }
+
+    pub fn visible(&self) -> Vec<&Entry> {
+        self.entries
+            .iter()
+            .filter(|e| e.matches(&self.filters))
+            .collect()
+    }
+
+    /// The guilds pane, sorted by name.
+    pub fn guilds(&self) -> Vec<(GuildId, &str, usize)> {
+        let mut guilds = self
+            .guild_counts
+            .iter()
+            .map(|(id, (name, count))| (*id, name.as_str(), *count))
+            .collect::<Vec<_>>();
+        guilds.sort_by_key(|(_, name, _)| name.to_lowercase());
+        guilds
+    }
+
+    pub fn selected_entry(&self) -> Option<&Entry> {
+        self.visible().get(self.selected).copied()
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+
+        if !self.paused {
+            for entry in std::mem::take(&mut self.pending) {
+                self.push(entry);
+            }
+        }
+    }
+
+    fn move_selection(&mut self, up: bool, amount: usize) {
+        if self.focus == Focus::Guilds {
+            let max = self.guild_counts.len();
+            self.guild_selected = if up {
+                self.guild_selected.saturating_sub(amount)
+            } else {
+                (self.guild_selected + amount).min(max)
+            };
+            return;
+        }
+
+        let len = self.visible().len();
+        if up {
+            self.selected = self.selected.saturating_sub(amount);
+            self.follow = false;
+        } else {
+            self.selected = (self.selected + amount).min(len.saturating_sub(1));
+            self.follow = self.selected + 1 >= len;
+        }
+    }
+
+    /// Filters changed, so the old selection index means nothing anymore.
+    fn reset_selection(&mut self) {
+        self.selected = self.visible().len().saturating_sub(1);
+        self.follow = true;
+    }
+
+    fn cycle_kind(&mut self) {
+        self.filters.kind = match self.filters.kind {
+            None => Some(KINDS[0]),
+            Some(kind) => KINDS
+                .iter()
+                .position(|k| *k == kind)
+                .and_then(|i| KINDS.get(i + 1))
+                .copied(),
+        };
+        self.reset_selection();
+    }
+
+    /// Cycles through the channels seen in the filtered guild.
+    fn cycle_channel(&mut self) {
+        let mut channels = self
+            .entries
+            .iter()
+            .filter(|e| self.filters.guild.is_none() || e.record.guild_id == self.filters.guild)
+            .filter_map(|e| e.record.channel_id)
+            .collect::<Vec<_>>();
+        channels.sort_unstable();
+        channels.dedup();
+
+        self.filters.channel = match self.filters.channel {
+            None => channels.first().copied(),
+            Some(current) => channels
+                .iter()
+                .position(|c| *c == current)
+                .and_then(|i| channels.get(i + 1))
+                .copied(),
+        };
+        self.reset_selection();
+    }
+
+    fn toggle_user(&mut self) {
+        self.filters.user = if self.filters.user.is_some() {
+            None
+        } else {
+            self.selected_entry().and_then(|e| e.record.user_id)
+        };
+        self.reset_selection();
+    }
+
+    fn select_guild(&mut self) {
+        self.filters.guild = match self.guild_selected {
+            0 => None,
+            i => self.guilds().get(i - 1).map(|(id, _, _)| *id),
+        };
+        self.filters.channel = None;
+        self.focus = Focus::Events;
+        self.reset_selection();
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match self.mode {
+            Mode::Search => match key.code {
+                KeyCode::Enter => self.mode = Mode::Normal,
+                KeyCode::Esc => {
+                    self.filters.search.clear();
+                    self.mode = Mode::Normal;
+                    self.reset_selection();
+                }
+                KeyCode::Backspace => {
+                    self.filters.search.pop();
+                    self.reset_selection();
+                }
+                KeyCode::Char(c) => {
+                    self.filters.search.push(c);
+                    self.reset_selection();
+                }
+                _ => {}
+            },
+            Mode::Details => {
+                if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                    self.mode = Mode::Normal;
+                }
+            }
+            Mode::Normal => match key.code {
+                KeyCode::Char('q') => self.quit = true,
+                KeyCode::Char(' ' | 'p') => self.toggle_pause(),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(true, 1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(false, 1),
+                KeyCode::PageUp => self.move_selection(true, PAGE),
+                KeyCode::PageDown => self.move_selection(false, PAGE),
+                KeyCode::Home => self.move_selection(true, usize::MAX),
+                KeyCode::End => self.reset_selection(),
+                KeyCode::Tab => {
+                    self.focus = match self.focus {
+                        Focus::Events => Focus::Guilds,
+                        Focus::Guilds => Focus::Events,
+                    };
+                }
+                KeyCode::Enter => match self.focus {
+                    Focus::Guilds => self.select_guild(),
+                    Focus::Events => {
+                        if self.selected_entry().is_some() {
+                            self.mode = Mode::Details;
+                        }
+                    }
+                },
+                KeyCode::Char('/') => self.mode = Mode::Search,
+                KeyCode::Char('t') => self.cycle_kind(),
+                KeyCode::Char('c') => self.cycle_channel(),
+                KeyCode::Char('u') => self.toggle_user(),
+                KeyCode::Char('x') => {
+                    self.filters = Filters::default();
+                    self.guild_selected = 0;
+                    self.reset_selection();
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// Called before drawing so following keeps up with new events.
+    pub fn tick(&mut self) {
+        if self.follow {
+            self.selected = self.visible().len().saturating_sub(1);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Moves stdout out of the way while the TUI owns the terminal, so the `println!`s from the
+//! event handlers don't draw over it.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+
+pub struct Redirect {
+    saved: RawFd,
+}
+
+impl Redirect {
+    /// Points stdout at `MOTH_TUI_STDOUT` (default `/dev/null`), returning a handle to the
+    /// terminal stdout used to point to.
+    pub fn start() -> io::Result<(Self, File)> {
+        io::stdout().flush()?;
+
+        let path = std::env::var("MOTH_TUI_STDOUT").unwrap_or_else(|_| "/dev/null".to_owned());
+        let sink = OpenOptions::new().create(true).append(true).open(path)?;
+
+        // SAFETY: dup only creates new descriptors, both are checked before use.
+        let saved = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if saved < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let terminal = unsafe { libc::dup(saved) };
+        if terminal < 0 {
+            let err = io::Error::last_os_error();
+            unsafe { libc::close(saved) };
+            return Err(err);
+        }
+
+        if unsafe { libc::dup2(sink.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            let err = io::Error::last_os_error();
+            unsafe {
+                libc::close(saved);
+                libc::close(terminal);
+            }
+            return Err(err);
+        }
+
+        // SAFETY: `terminal` is a fresh descriptor that nothing else owns.
+        let terminal = unsafe { File::from_raw_fd(terminal) };
+
+        Ok((Self { saved }, terminal))
+    }
+}
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+
+        unsafe {
+            libc::dup2(self.saved, libc::STDOUT_FILENO);
+            libc::close(self.saved);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+#![warn(clippy::pedantic)]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+//! An optional terminal UI over the event stream, enabled with `MOTH_TUI=true`.
+//!
+//! While it is open stdout goes to `MOTH_TUI_STDOUT` (default `/dev/null`), quitting it restores
+//! the plain console output.
+
+mod app;
+mod console;
+mod ui;
+
+use std::io;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use lumi::serenity_prelude::Cache;
+use moth_core::event_log::LogRecord;
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+
+use app::{App, Entry};
+
+#[must_use]
+pub fn enabled() -> bool {
+    std::env::var("MOTH_TUI")
+        .map(|e| e.parse::<bool>().unwrap())
+        .unwrap_or(false)
+}
+
+/// Runs the TUI on its own thread until it is quit.
+pub fn spawn(cache: Arc<Cache>, events: Receiver<LogRecord>) {
+    std::thread::spawn(move || {
+        if let Err(e) = run(&cache, &events) {
+            eprintln!("TUI exited with an error: {e}");
+        }
+    });
+}
+
+fn run(cache: &Cache, events: &Receiver<LogRecord>) -> io::Result<()> {
+    let (redirect, mut tty) = console::Redirect::start()?;
+
+    enable_raw_mode()?;
+    execute!(tty, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(tty))?;
+
+    let result = event_loop(&mut terminal, cache, events);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    drop(redirect);
+
+    println!("TUI closed, back to console output.");
+    result
+}
+
+fn event_loop<W: io::Write>(
+    terminal: &mut Terminal<CrosstermBackend<W>>,
+    cache: &Cache,
+    events: &Receiver<LogRecord>,
+) -> io::Result<()> {
+    let mut app = App::new();
+
+    loop {
+        loop {
+            match events.try_recv() {
+                Ok(record) => app.push(Entry::resolve(cache, record)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+
+        app.tick();
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+
+        if app.quit {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use moth_core::event_log::EventKind;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap};
+
+use crate::app::{App, Entry, Focus, Mode};
+
+/// The same colours the console uses for each kind of event.
+fn kind_style(kind: EventKind) -> (&'static str, Color) {
+    match kind {
+        EventKind::MessageCreate => ("message", Color::Reset),
+        EventKind::MessageEdit => ("edit", Color::Cyan),
+        EventKind::MessageDelete => ("delete", Color::LightRed),
+        EventKind::ReactionAdd => ("react+", Color::LightMagenta),
+        EventKind::ReactionRemove => ("react-", Color::LightMagenta),
+        EventKind::ChannelCreate => ("channel+", Color::Blue),
+        EventKind::ChannelUpdate => ("channel", Color::Blue),
+        EventKind::ChannelDelete => ("channel-", Color::Blue),
+        EventKind::ThreadCreate => ("thread+", Color::LightBlue),
+        EventKind::ThreadUpdate => ("thread", Color::LightBlue),
+        EventKind::ThreadDelete => ("thread-", Color::LightBlue),
+        EventKind::RoleCreate => ("role+", Color::Magenta),
+        EventKind::RoleUpdate => ("role", Color::Magenta),
+        EventKind::RoleDelete => ("role-", Color::Magenta),
+        EventKind::MemberJoin => ("join", Color::Yellow),
+        EventKind::MemberLeave => ("leave", Color::Yellow),
+        EventKind::MemberUpdate => ("member", Color::LightGreen),
+        EventKind::MemberRoleUpdate => ("roles", Color::Magenta),
+        EventKind::VoiceJoin => ("vc+", Color::Green),
+        EventKind::VoiceLeave => ("vc-", Color::Green),
+        EventKind::VoiceMove => ("vc", Color::Green),
+    }
+}
+
+fn user_span(entry: &Entry) -> Span<'static> {
+    let name = match (&entry.user, entry.record.user_id) {
+        (Some(name), _) => name.clone(),
+        (None, Some(id)) => id.to_string(),
+        (None, None) => return Span::raw(""),
+    };
+
+    match entry.colour {
+        Some(c) => Span::styled(
+            name,
+            Style::new().fg(Color::Rgb(
+                moth_ansi::r(c.0),
+                moth_ansi::g(c.0),
+                moth_ansi::b(c.0),
+            )),
+        ),
+        None => Span::raw(name),
+    }
+}
+
+fn entry_line(entry: &Entry) -> Line<'static> {
+    let record = &entry.record;
+    let (label, colour) = kind_style(record.kind);
+    // RFC 3339, so this is the time of day.
+    let time = record.timestamp.get(11..19).unwrap_or_default().to_owned();
+
+    let mut location = String::new();
+    if let Some(guild) = &entry.guild {
+        location.push_str(&format!("[{guild}] "));
+    }
+    if let Some(channel) = &entry.channel {
+        location.push_str(&format!("[#{channel}] "));
+    }
+
+    Line::from(vec![
+        Span::styled(format!("{time} "), Style::new().dark_gray()),
+        Span::styled(format!("{label:<9}"), Style::new().fg(colour)),
+        Span::styled(location, Style::new().dark_gray()),
+        user_span(entry),
+        Span::raw(": "),
+        Span::styled(entry.summary().replace('\n', " "), Style::new().fg(colour)),
+    ])
+}
+
+fn pane_block(title: String, focused: bool) -> Block<'static> {
+    let block = Block::bordered().title(title);
+    if focused {
+        block.border_style(Style::new().yellow())
+    } else {
+        block
+    }
+}
+
+fn draw_guilds(frame: &mut Frame, app: &App, area: Rect) {
+    let mut items = vec![ListItem::new("All guilds")];
+    items.extend(
+        app.guilds()
+            .into_iter()
+            .map(|(_, name, count)| ListItem::new(format!("{name} ({count})"))),
+    );
+
+    let list = List::new(items)
+        .block(pane_block("Guilds".to_owned(), app.focus == Focus::Guilds))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.guild_selected));
+
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_events(frame: &mut Frame, app: &App, area: Rect) {
+    let visible = app.visible();
+    let title = format!("Events ({}/{})", visible.len(), app.entries.len());
+
+    let list = List::new(visible.into_iter().map(entry_line))
+        .block(pane_block(title, app.focus == Focus::Events))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.selected));
+
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let filters = &app.filters;
+    let mut parts = Vec::new();
+
+    if app.paused {
+        parts.push(Span::styled(
+            format!(" PAUSED ({} waiting) ", app.pending.len()),
+            Style::new().black().on_yellow(),
+        ));
+    }
+    if let Some(guild) = filters.guild {
+        let name = app
+            .guild_counts
+            .get(&guild)
+            .map_or_else(|| guild.to_string(), |(n, _)| n.clone());
+        parts.push(Span::raw(format!(" guild:{name}")));
+    }
+    if let Some(channel) = filters.channel {
+        parts.push(Span::raw(format!(" channel:{channel}")));
+    }
+    if let Some(user) = filters.user {
+        parts.push(Span::raw(format!(" user:{user}")));
+    }
+    if let Some(kind) = filters.kind {
+        parts.push(Span::raw(format!(" kind:{}", kind_style(kind).0)));
+    }
+
+    if app.mode == Mode::Search {
+        parts.push(Span::styled(
+            format!(" /{}", filters.search),
+            Style::new().yellow(),
+        ));
+    } else if !filters.search.is_empty() {
+        parts.push(Span::raw(format!(" search:{}", filters.search)));
+    }
+
+    parts.push(Span::styled(
+        "  q quit · space pause · / search · t kind · c channel · u user · x clear · tab guilds \
+         · enter details",
+        Style::new().dark_gray(),
+    ));
+
+    frame.render_widget(Paragraph::new(Line::from(parts)), area);
+}
+
+fn draw_details(frame: &mut Frame, entry: &Entry, area: Rect) {
+    let record = &entry.record;
+    let bold = Style::new().add_modifier(Modifier::BOLD);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Kind: ", bold),
+            Span::raw(kind_style(record.kind).0),
+        ]),
+        Line::from(vec![
+            Span::styled("Time: ", bold),
+            Span::raw(record.timestamp.clone()),
+        ]),
+    ];
+
+    let ids = [
+        (
+            "Guild",
+            entry.guild.clone(),
+            record.guild_id.map(|i| i.get()),
+        ),
+        (
+            "Channel",
+            entry.channel.clone(),
+            record.channel_id.map(|i| i.get()),
+        ),
+        ("User", entry.user.clone(), record.user_id.map(|i| i.get())),
+        ("By", None, record.actor_id.map(|i| i.get())),
+        ("Message", None, record.message_id.map(|i| i.get())),
+    ];
+    for (label, name, id) in ids {
+        let Some(id) = id else {
+            continue;
+        };
+        let value = match name {
+            Some(name) => format!("{name} (ID:{id})"),
+            None => id.to_string(),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{label}: "), bold),
+            Span::raw(value),
+        ]));
+    }
+
+    if let Some(before) = &record.before {
+        lines.push(Line::default());
+        lines.push(Line::styled("Before:", bold.red()));
+        lines.extend(before.lines().map(|l| Line::raw(l.to_owned())));
+    }
+    if let Some(after) = &record.after {
+        lines.push(Line::default());
+        lines.push(Line::styled("After:", bold.green()));
+        lines.extend(after.lines().map(|l| Line::raw(l.to_owned())));
+    }
+    if !record.changes.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled("Changes:", bold));
+        for change in &record.changes {
+            let style = if change.starts_with('+') {
+                Style::new().light_green()
+            } else if change.starts_with('-') {
+                Style::new().light_red()
+            } else {
+                Style::new()
+            };
+            lines.push(Line::styled(change.clone(), style));
+        }
+    }
+
+    let [_, area, _] = Layout::vertical([
+        Constraint::Percentage(10),
+        Constraint::Percentage(80),
+        Constraint::Percentage(10),
+    ])
+    .areas(area);
+    let [_, area, _] = Layout::horizontal([
+        Constraint::Percentage(10),
+        Constraint::Percentage(80),
+        Constraint::Percentage(10),
+    ])
+    .areas(area);
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .wrap(Wrap { trim: false })
+        .block(Block::bordered().title("Details (esc to close)"));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+    let [guilds, events] =
+        Layout::horizontal([Constraint::Length(28), Constraint::Min(20)]).areas(main);
+
+    draw_guilds(frame, app, guilds);
+    draw_events(frame, app, events);
+    draw_status(frame, app, status);
+
+    if app.mode == Mode::Details {
+        if let Some(entry) = app.selected_entry() {
+            draw_details(frame, entry, frame.area());
+        }
+    }
+}
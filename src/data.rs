@@ -1,13 +1,12 @@
 use dashmap::DashMap;
 use moth_core::data::structs::{Data, StarboardConfig};
-use serenity::all::{GenericChannelId, GuildId, RoleId};
 use std::sync::{atomic::AtomicBool, Arc};
 
 pub async fn setup() -> Arc<Data> {
-    let handler = moth_core::data::database::init_data().await;
-
     let config = moth_core::config::MothConfig::load_config();
-    let starboard_config = starboard_config();
+    let handler = moth_core::data::database::init_data(config.events.leet.clone()).await;
+
+    let starboard_config = StarboardConfig::from_env();
 
     Arc::new(Data {
         has_started: AtomicBool::new(false),
@@ -19,40 +18,8 @@ pub async fn setup() -> Arc<Data> {
         starboard_config,
         ocr_engine: moth_core::ocr::OcrEngine::new(),
         new_join_vc: DashMap::default(),
+        event_log: moth_core::event_log::EventLog::from_env(),
+        modlog_queue: moth_core::data::modlog::ModlogQueue::default(),
+        checked_names: moth_core::data::names::CheckedNames::default(),
     })
 }
-
-macro_rules! get_env_or_default {
-    ($var_name:expr, $kind:ty, $default:expr) => {
-        std::env::var($var_name)
-            .ok()
-            .and_then(|val| val.parse::<$kind>().ok())
-            .unwrap_or_else(|| <$kind>::new($default))
-    };
-}
-
-fn starboard_config() -> StarboardConfig {
-    StarboardConfig {
-        active: std::env::var("STARBOARD_ACTIVE")
-            .map(|e| e.parse::<bool>().unwrap())
-            .unwrap_or(true),
-
-        queue_channel: get_env_or_default!(
-            "STARBOARD_QUEUE",
-            GenericChannelId,
-            1324543000600383549
-        ),
-        post_channel: get_env_or_default!(
-            "STARBOARD_CHANNEL",
-            GenericChannelId,
-            1324437745854316564
-        ),
-        guild_id: get_env_or_default!("STARBOARD_GUILD", GuildId, 98226572468690944),
-        allowed_role: get_env_or_default!("STARBOARD_ROLE", RoleId, 98459030455853056),
-        star_emoji: std::env::var("STARBOARD_EMOJI").unwrap_or("⭐".to_owned()),
-        threshold: std::env::var("STARBOARD_THRESHOLD")
-            .ok()
-            .and_then(|val| val.parse::<u8>().ok())
-            .unwrap_or(5),
-    }
-}
@@ -45,13 +45,20 @@ async fn main() {
 
     let data = data::setup().await;
 
+    // subscribe before connecting so nothing is missed.
+    let tui_events = moth_tui::enabled().then(|| data.event_log.subscribe());
+
     let mut client = serenity::Client::builder(token, intents)
         .framework(framework)
         .data(data)
         .cache_settings(settings)
-        .event_handler(moth_events::Handler)
+        .event_handler(moth_events::Handler::new())
         .await
         .unwrap();
 
+    if let Some(events) = tui_events {
+        moth_tui::spawn(client.cache.clone(), events);
+    }
+
     client.start().await.unwrap();
 }